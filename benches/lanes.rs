@@ -0,0 +1,51 @@
+//! Compares PcgX4 and PcgX8 with as many scalar Pcg streams drawn in
+//! turn, filling the same interleaved layout. Run with
+//! `cargo bench --bench lanes`.
+use pcg::{Pcg, PcgLanes};
+use rand_core::RngCore;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// The best of several runs of fill, in u32s per nanosecond
+fn throughput<F: FnMut(&mut [u32])>(mut fill: F, buffer: &mut [u32]) -> f64 {
+    let mut best = Duration::MAX;
+    for _ in 0..20 {
+        let start = Instant::now();
+        fill(black_box(&mut *buffer));
+        best = best.min(start.elapsed());
+    }
+    buffer.len() as f64 / best.as_nanos() as f64
+}
+
+fn compare<const N: usize>(buffer: &mut [u32]) {
+    let mut lanes = PcgLanes::<N>::new(12345);
+    let mut scalars: Vec<Pcg> = (0..N).map(|i| lanes.lane(i)).collect();
+    let lanes = throughput(|dest| lanes.fill_u32(dest), buffer);
+    let scalar = throughput(
+        |dest| {
+            for chunk in dest.chunks_mut(N) {
+                for (out, pcg) in chunk.iter_mut().zip(scalars.iter_mut()) {
+                    *out = pcg.next_u32();
+                }
+            }
+        },
+        buffer,
+    );
+    println!(
+        "{:>8} u32s, {} lanes: {:.2} u32/ns, {} scalar Pcgs {:.2} u32/ns ({:.2}x)",
+        buffer.len(),
+        N,
+        lanes,
+        N,
+        scalar,
+        lanes / scalar
+    );
+}
+
+fn main() {
+    for &len in &[1 << 10, 1 << 16, 1 << 22] {
+        let mut buffer = vec![0u32; len];
+        compare::<4>(&mut buffer);
+        compare::<8>(&mut buffer);
+    }
+}
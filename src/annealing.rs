@@ -0,0 +1,147 @@
+/*! Simulated annealing: the Metropolis acceptance criterion and cooling
+ *  schedules.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::annealing::{accept, Schedule};
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut x: f64 = 3.0;
+ *  for temperature in Schedule::exponential(1.0, 0.999).take(10000) {
+ *      let step = if pcg.next_u32() >> 31 == 0 { 0.1 } else { -0.1 };
+ *      let candidate = x + step;
+ *      if accept(&mut pcg, candidate.abs() - x.abs(), temperature) {
+ *          x = candidate;
+ *      }
+ *  }
+ *  assert!(x.abs() < 0.5);
+ *  ```
+ */
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// The Metropolis criterion: accepts a move that changes the energy by
+/// `delta_energy` always if it does not increase the energy, and
+/// otherwise with probability exp(-delta_energy / temperature).
+/// Exactly one value is drawn per call, whatever the outcome, so that
+/// draw counts do not depend on the moves proposed.
+pub fn accept<R: RngCore + ?Sized>(rng: &mut R, delta_energy: f64, temperature: f64) -> bool {
+    let u = unit_f64(rng);
+    if delta_energy <= 0.0 {
+        true
+    } else if temperature <= 0.0 {
+        false
+    } else {
+        u < (-delta_energy / temperature).exp()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Cooling {
+    Exponential { alpha: f64 },
+    Linear { steps: u64 },
+    Logarithmic,
+}
+
+/// A cooling schedule, iterating over the temperature at each step
+#[derive(Clone, Copy, Debug)]
+pub struct Schedule {
+    initial: f64,
+    cooling: Cooling,
+    step: u64,
+}
+
+impl Schedule {
+    /// Temperatures initial * alpha^k, for 0 < alpha < 1.
+    pub fn exponential(initial: f64, alpha: f64) -> Schedule {
+        assert!(alpha > 0.0 && alpha < 1.0, "alpha must be in (0, 1)");
+        Schedule::new(initial, Cooling::Exponential { alpha })
+    }
+
+    /// Temperatures falling linearly from initial to zero over `steps`
+    /// steps, after which the schedule ends.
+    pub fn linear(initial: f64, steps: u64) -> Schedule {
+        Schedule::new(initial, Cooling::Linear { steps })
+    }
+
+    /// Temperatures initial / ln(k + e), the slow classical schedule.
+    pub fn logarithmic(initial: f64) -> Schedule {
+        Schedule::new(initial, Cooling::Logarithmic)
+    }
+
+    fn new(initial: f64, cooling: Cooling) -> Schedule {
+        assert!(initial > 0.0, "initial temperature must be positive");
+        Schedule {
+            initial,
+            cooling,
+            step: 0,
+        }
+    }
+
+    /// The number of temperatures produced so far.
+    pub fn step(&self) -> u64 {
+        self.step
+    }
+}
+
+impl Iterator for Schedule {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        let k = self.step as f64;
+        let temperature = match self.cooling {
+            Cooling::Exponential { alpha } => self.initial * alpha.powf(k),
+            Cooling::Linear { steps } => {
+                if self.step >= steps {
+                    return None;
+                }
+                self.initial * (1.0 - k / steps as f64)
+            }
+            Cooling::Logarithmic => self.initial / (k + std::f64::consts::E).ln(),
+        };
+        self.step += 1;
+        Some(temperature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_accept() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        assert!(accept(&mut pcg, -1.0, 0.0));
+        assert!(accept(&mut pcg, 0.0, 1.0));
+        assert!(!accept(&mut pcg, 1.0, 0.0));
+        let n = 20000;
+        let accepted = (0..n).filter(|_| accept(&mut pcg, 1.0, 1.0)).count();
+        let expected = (-1.0f64).exp();
+        assert!((accepted as f64 / n as f64 - expected).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_accept_draws_once() {
+        let seed = rand::random::<u64>();
+        let mut a = Pcg::seed_from_u64(seed);
+        let mut b = Pcg::seed_from_u64(seed);
+        accept(&mut a, -1.0, 1.0);
+        accept(&mut b, 1.0, 0.0);
+        assert_eq!(a.next_u64(), b.next_u64());
+    }
+
+    #[test]
+    fn test_schedules() {
+        let exponential: Vec<f64> = Schedule::exponential(8.0, 0.5).take(4).collect();
+        assert_eq!(exponential, vec![8.0, 4.0, 2.0, 1.0]);
+        let linear: Vec<f64> = Schedule::linear(4.0, 4).collect();
+        assert_eq!(linear, vec![4.0, 3.0, 2.0, 1.0]);
+        let mut logarithmic = Schedule::logarithmic(2.0);
+        assert_eq!(logarithmic.next(), Some(2.0));
+        assert!(logarithmic.next().unwrap() < 2.0);
+        assert_eq!(logarithmic.step(), 2);
+    }
+}
@@ -0,0 +1,211 @@
+/*! Randomized backoff between retries, and retry policies built on it.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::backoff::DecorrelatedJitter;
+ *  # use rand_core::SeedableRng;
+ *  # use std::time::Duration;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut backoff = DecorrelatedJitter::new(Duration::from_millis(10), Duration::from_secs(5));
+ *
+ *  for _ in 0..20 {
+ *      let delay = backoff.next_delay(&mut pcg);
+ *      assert!(delay >= Duration::from_millis(10));
+ *      assert!(delay <= Duration::from_secs(5));
+ *  }
+ *  ```
+ */
+use crate::{bounded_u64, unit_f64};
+use rand_core::RngCore;
+use std::time::Duration;
+
+/// "Decorrelated jitter" backoff: each delay is uniform between the base
+/// delay and three times the previous delay, capped. Delays grow roughly
+/// exponentially, but clients that failed together spread out quickly.
+///
+/// Delays are drawn in whole nanoseconds, so they are exactly uniform.
+#[derive(Clone, Debug)]
+pub struct DecorrelatedJitter {
+    base: u64,
+    cap: u64,
+    previous: u64,
+}
+
+impl DecorrelatedJitter {
+    /// Creates a backoff starting at `base` and never exceeding `cap`.
+    /// Durations beyond u64::MAX nanoseconds (about 584 years) are
+    /// treated as that. Panics if `base` is under a nanosecond, since
+    /// delays grow from the previous one and would stay at zero.
+    pub fn new(base: Duration, cap: Duration) -> DecorrelatedJitter {
+        assert!(base <= cap, "base delay exceeds the cap");
+        let base = nanos(base);
+        assert!(base > 0, "base delay must be at least a nanosecond");
+        DecorrelatedJitter {
+            base,
+            cap: nanos(cap),
+            previous: base,
+        }
+    }
+
+    /// Draws the delay before the next retry.
+    pub fn next_delay<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> Duration {
+        // base is at least 1, so the span cannot overflow
+        let hi = self.previous.saturating_mul(3).min(self.cap);
+        let delay = self.base + bounded_u64(rng, hi - self.base + 1);
+        self.previous = delay;
+        Duration::from_nanos(delay)
+    }
+
+    /// Starts over from the base delay, e.g. after a success.
+    pub fn reset(&mut self) {
+        self.previous = self.base;
+    }
+}
+
+/// What a client does after a failed attempt
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetryDecision {
+    RetryAfter(Duration),
+    GiveUp,
+}
+
+/// A client's retry behavior: at most `max_attempts` attempts, jittered
+/// backoff between them, and a chance of giving up early after each
+/// failure, as impatient users and upstream timeouts do
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    give_up_probability: f64,
+    backoff: DecorrelatedJitter,
+    attempts: u32,
+}
+
+impl RetryPolicy {
+    /// Allows `max_attempts` attempts in all, the first included, with
+    /// `DecorrelatedJitter` delays between `base` and `cap`, and never
+    /// gives up early.
+    pub fn new(max_attempts: u32, base: Duration, cap: Duration) -> RetryPolicy {
+        assert!(max_attempts > 0, "at least one attempt is needed");
+        RetryPolicy {
+            max_attempts,
+            give_up_probability: 0.0,
+            backoff: DecorrelatedJitter::new(base, cap),
+            attempts: 1,
+        }
+    }
+
+    /// Gives up after each failure with probability `p`, even while
+    /// attempts remain.
+    pub fn with_give_up_probability(mut self, p: f64) -> RetryPolicy {
+        assert!((0.0..=1.0).contains(&p), "probability must lie in [0, 1]");
+        self.give_up_probability = p;
+        self
+    }
+
+    /// The attempts made so far, counting the one in progress.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Decides what to do after the current attempt failed. Once this
+    /// returns `GiveUp`, call `reset` before the next request.
+    pub fn on_failure<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> RetryDecision {
+        if self.attempts >= self.max_attempts || unit_f64(rng) < self.give_up_probability {
+            return RetryDecision::GiveUp;
+        }
+        self.attempts += 1;
+        RetryDecision::RetryAfter(self.backoff.next_delay(rng))
+    }
+
+    /// Starts a new request, with a fresh attempt count and backoff, e.g.
+    /// after a success.
+    pub fn reset(&mut self) {
+        self.attempts = 1;
+        self.backoff.reset();
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_bounds() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let (base, cap) = (Duration::from_millis(1), Duration::from_millis(100));
+        let mut backoff = DecorrelatedJitter::new(base, cap);
+        let mut previous = base;
+        for _ in 0..1000 {
+            let delay = backoff.next_delay(&mut pcg);
+            assert!(delay >= base && delay <= cap);
+            assert!(delay <= previous * 3);
+            previous = delay;
+        }
+    }
+
+    #[test]
+    fn test_growth_and_reset() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let (base, cap) = (Duration::from_millis(1), Duration::from_secs(10));
+        let mut backoff = DecorrelatedJitter::new(base, cap);
+        let longest = (0..100).map(|_| backoff.next_delay(&mut pcg)).max();
+        assert!(longest.unwrap() > Duration::from_millis(100));
+        backoff.reset();
+        assert!(backoff.next_delay(&mut pcg) <= base * 3);
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let (base, cap) = (Duration::from_millis(1), Duration::from_millis(100));
+        let mut policy = RetryPolicy::new(4, base, cap);
+        for attempt in 2..=4 {
+            match policy.on_failure(&mut pcg) {
+                RetryDecision::RetryAfter(delay) => assert!(delay >= base && delay <= cap),
+                RetryDecision::GiveUp => panic!("gave up with attempts left"),
+            }
+            assert_eq!(policy.attempts(), attempt);
+        }
+        assert_eq!(policy.on_failure(&mut pcg), RetryDecision::GiveUp);
+        policy.reset();
+        assert_eq!(policy.attempts(), 1);
+
+        // giving up early after the first failure, a quarter of the time
+        let mut policy = RetryPolicy::new(10, base, cap).with_give_up_probability(0.25);
+        let mut gave_up = 0;
+        for _ in 0..10000 {
+            if policy.on_failure(&mut pcg) == RetryDecision::GiveUp {
+                gave_up += 1;
+            }
+            policy.reset();
+        }
+        assert!((2300..2700).contains(&gave_up));
+    }
+
+    #[test]
+    fn test_extremes() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let fixed = Duration::from_millis(5);
+        let mut backoff = DecorrelatedJitter::new(fixed, fixed);
+        assert!((0..100).all(|_| backoff.next_delay(&mut pcg) == fixed));
+        // the smallest base and largest cap still grow, and stay in range
+        let base = Duration::from_nanos(1);
+        let mut backoff = DecorrelatedJitter::new(base, Duration::MAX);
+        let delays: Vec<Duration> = (0..1000).map(|_| backoff.next_delay(&mut pcg)).collect();
+        assert!(delays.iter().all(|&delay| delay >= base));
+        assert!(delays.iter().max().unwrap() > &Duration::from_secs(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least a nanosecond")]
+    fn test_zero_base() {
+        DecorrelatedJitter::new(Duration::ZERO, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,130 @@
+/*! A bag that deals every variant of an enum once per cycle, in random
+ *  order, for mechanics where every option must come up before any
+ *  repeats, like the piece randomizer in falling-block games.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::bag::{EnumBag, Variants};
+ *  # use rand_core::SeedableRng;
+ *  #[derive(Clone, Copy, Debug, PartialEq)]
+ *  enum Attack {
+ *      Slash,
+ *      Thrust,
+ *      Sweep,
+ *  }
+ *
+ *  impl Variants for Attack {
+ *      const VARIANTS: &'static [Attack] = &[Attack::Slash, Attack::Thrust, Attack::Sweep];
+ *  }
+ *
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut bag = EnumBag::<Attack>::new();
+ *  let cycle: Vec<Attack> = (0..3).map(|_| bag.deal(&mut pcg)).collect();
+ *
+ *  assert!(cycle.contains(&Attack::Slash));
+ *  assert!(cycle.contains(&Attack::Thrust));
+ *  assert!(cycle.contains(&Attack::Sweep));
+ *  ```
+ */
+use crate::bounded_usize;
+use rand_core::RngCore;
+
+/// Types with a fixed list of values, usually fieldless enums
+pub trait Variants: Copy + 'static {
+    /// Every value, each listed once
+    const VARIANTS: &'static [Self];
+}
+
+/// Deals each variant of `E` exactly once per cycle, in random order
+#[derive(Clone, Debug)]
+pub struct EnumBag<E> {
+    remaining: Vec<E>,
+}
+
+impl<E: Variants> EnumBag<E> {
+    /// An empty bag, which fills itself on the first deal. `E` must have
+    /// at least one variant.
+    pub fn new() -> EnumBag<E> {
+        assert!(!E::VARIANTS.is_empty(), "enum has no variants to deal");
+        EnumBag {
+            remaining: Vec::with_capacity(E::VARIANTS.len()),
+        }
+    }
+
+    /// Deals the next variant, starting a new cycle once every variant
+    /// has been dealt.
+    pub fn deal<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> E {
+        if self.remaining.is_empty() {
+            self.remaining.extend_from_slice(E::VARIANTS);
+        }
+        let i = bounded_usize(rng, self.remaining.len());
+        self.remaining.swap_remove(i)
+    }
+
+    /// How many variants are left before the current cycle ends.
+    pub fn remaining(&self) -> usize {
+        self.remaining.len()
+    }
+
+    /// Abandons the current cycle, so that the next deal starts a new one.
+    pub fn reset(&mut self) {
+        self.remaining.clear();
+    }
+}
+
+impl<E: Variants> Default for EnumBag<E> {
+    fn default() -> EnumBag<E> {
+        EnumBag::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    enum Suit {
+        Clubs,
+        Diamonds,
+        Hearts,
+        Spades,
+    }
+
+    impl Variants for Suit {
+        const VARIANTS: &'static [Suit] =
+            &[Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+    }
+
+    #[test]
+    fn test_cycles() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut bag = EnumBag::<Suit>::new();
+        for _ in 0..100 {
+            let mut cycle: Vec<Suit> = (0..4).map(|_| bag.deal(&mut pcg)).collect();
+            assert_eq!(bag.remaining(), 0);
+            cycle.sort_unstable();
+            assert_eq!(cycle, Suit::VARIANTS);
+        }
+
+        bag.deal(&mut pcg);
+        assert_eq!(bag.remaining(), 3);
+        bag.reset();
+        bag.deal(&mut pcg);
+        assert_eq!(bag.remaining(), 3);
+    }
+
+    #[test]
+    fn test_order_is_uniform() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut bag = EnumBag::<Suit>::default();
+        let mut firsts = [0; 4];
+        for _ in 0..20000 {
+            firsts[bag.deal(&mut pcg) as usize] += 1;
+            bag.reset();
+        }
+        assert!(firsts.iter().all(|&n| (4700..5300).contains(&n)));
+    }
+}
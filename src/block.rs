@@ -0,0 +1,97 @@
+/*! `Pcg` as a block generator, for use with `rand_core::block::BlockRng`.
+ *
+ *  `PcgCore` produces sixteen u32 outputs at a time, and `BlockRng` hands
+ *  them out from its buffer, which amortizes the call overhead when
+ *  drawing many small values. The u32 outputs are those `Pcg` would
+ *  give; u64 outputs differ, because `BlockRng` puts the first of each
+ *  pair of u32s in the low half rather than the high half.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::{Pcg, PcgCore};
+ *  # use rand_core::block::BlockRng;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut buffered = BlockRng::new(PcgCore::seed_from_u64(12345));
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *
+ *  for _ in 0..100 {
+ *      assert_eq!(buffered.next_u32(), pcg.next_u32());
+ *  }
+ *  ```
+ */
+use crate::{Pcg, PcgSeed};
+use rand_core::block::BlockRngCore;
+use rand_core::{RngCore, SeedableRng};
+
+/// A `Pcg` producing 16-word blocks
+#[derive(Clone)]
+pub struct PcgCore {
+    pcg: Pcg,
+}
+
+impl PcgCore {
+    /// Wraps a `Pcg`, continuing from its current position.
+    pub fn new(pcg: Pcg) -> PcgCore {
+        PcgCore { pcg }
+    }
+
+    pub fn into_inner(self) -> Pcg {
+        self.pcg
+    }
+}
+
+impl BlockRngCore for PcgCore {
+    type Item = u32;
+    type Results = [u32; 16];
+
+    fn generate(&mut self, results: &mut [u32; 16]) {
+        for x in results.iter_mut() {
+            *x = self.pcg.next_u32();
+        }
+    }
+}
+
+impl SeedableRng for PcgCore {
+    type Seed = PcgSeed;
+
+    fn from_seed(seed: PcgSeed) -> PcgCore {
+        PcgCore::new(Pcg::from_seed(seed))
+    }
+
+    fn seed_from_u64(seed: u64) -> PcgCore {
+        PcgCore::new(Pcg::seed_from_u64(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::block::BlockRng;
+
+    #[test]
+    fn test_blocks() {
+        let seed = rand::random::<u64>();
+        let mut core = PcgCore::seed_from_u64(seed);
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut block = [0; 16];
+        for _ in 0..3 {
+            core.generate(&mut block);
+            for &x in block.iter() {
+                assert_eq!(x, pcg.next_u32());
+            }
+        }
+        assert_eq!(core.into_inner().draw_count(), 48);
+    }
+
+    #[test]
+    fn test_block_rng() {
+        let seed = rand::random::<u64>();
+        let mut buffered = BlockRng::new(PcgCore::seed_from_u64(seed));
+        let mut pcg = Pcg::seed_from_u64(seed);
+        for _ in 0..40 {
+            assert_eq!(buffered.next_u32(), pcg.next_u32());
+        }
+        let (low, high) = (pcg.next_u32() as u64, pcg.next_u32() as u64);
+        assert_eq!(buffered.next_u64(), high << 32 | low);
+    }
+}
@@ -0,0 +1,145 @@
+/*! A ring of recent generator checkpoints, for stepping backwards
+ *  through a randomized algorithm while debugging it.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::checkpoint::CheckpointRing;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut ring = CheckpointRing::new(8, 100);
+ *
+ *  let mut outputs = Vec::new();
+ *  for _ in 0..1000 {
+ *      ring.record(&pcg);
+ *      outputs.push(pcg.next_u32());
+ *  }
+ *
+ *  // go back to draw 950 and replay from there
+ *  let mut rewound = ring.rewind_to(950).unwrap();
+ *  assert_eq!(rewound.next_u32(), outputs[950]);
+ *  ```
+ */
+use crate::Pcg;
+use std::collections::VecDeque;
+
+/// Keeps the last `capacity` checkpoints of a generator, taken at least
+/// `interval` draws apart as counted by `Pcg::draw_count`
+#[derive(Clone)]
+pub struct CheckpointRing {
+    capacity: usize,
+    interval: u64,
+    /// Oldest first
+    checkpoints: VecDeque<Pcg>,
+}
+
+impl CheckpointRing {
+    pub fn new(capacity: usize, interval: u64) -> CheckpointRing {
+        assert!(capacity > 0, "ring needs room for a checkpoint");
+        CheckpointRing {
+            capacity,
+            interval,
+            checkpoints: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Takes a checkpoint if `interval` draws have passed since the last
+    /// one, returning whether it did. Call this as often as convenient.
+    pub fn record(&mut self, pcg: &Pcg) -> bool {
+        let due = match self.checkpoints.back() {
+            Some(last) => pcg.draw_count().wrapping_sub(last.draw_count()) >= self.interval,
+            None => true,
+        };
+        if due {
+            self.checkpoint(pcg);
+        }
+        due
+    }
+
+    /// Takes a checkpoint regardless of the interval, evicting the oldest
+    /// checkpoint if the ring is full.
+    pub fn checkpoint(&mut self, pcg: &Pcg) {
+        if self.checkpoints.len() == self.capacity {
+            self.checkpoints.pop_front();
+        }
+        self.checkpoints.push_back(pcg.clone());
+    }
+
+    /// The generator as of a stored checkpoint, counting back from the
+    /// most recent, which is 0.
+    pub fn rewind(&self, back: usize) -> Option<Pcg> {
+        let len = self.checkpoints.len();
+        if back < len {
+            Some(self.checkpoints[len - 1 - back].clone())
+        } else {
+            None
+        }
+    }
+
+    /// The generator as it was when its draw count was `draw_count`,
+    /// replayed from the latest checkpoint at or before it. Returns None
+    /// if that point is older than every stored checkpoint.
+    pub fn rewind_to(&self, draw_count: u64) -> Option<Pcg> {
+        let mut pcg = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|c| c.draw_count() <= draw_count)?
+            .clone();
+        // distances past i64::MAX wrap, which is harmless, since the
+        // period divides 2^64
+        pcg.skip((draw_count - pcg.draw_count()) as i64);
+        Some(pcg)
+    }
+
+    /// The draw counts of the stored checkpoints, oldest first.
+    pub fn positions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.checkpoints.iter().map(Pcg::draw_count)
+    }
+
+    pub fn len(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::{RngCore, SeedableRng};
+
+    #[test]
+    fn test_intervals_and_eviction() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut ring = CheckpointRing::new(3, 10);
+        for _ in 0..100 {
+            ring.record(&pcg);
+            pcg.next_u64();
+        }
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.positions().collect::<Vec<_>>(), vec![170, 180, 190]);
+        assert_eq!(ring.rewind(0).unwrap().draw_count(), 190);
+        assert_eq!(ring.rewind(2).unwrap().draw_count(), 170);
+        assert!(ring.rewind(3).is_none());
+    }
+
+    #[test]
+    fn test_rewind_to() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut ring = CheckpointRing::new(4, 25);
+        let mut outputs = Vec::new();
+        for _ in 0..200 {
+            ring.record(&pcg);
+            outputs.push(pcg.next_u32());
+        }
+        for draw in 100..200 {
+            let mut rewound = ring.rewind_to(draw).unwrap();
+            assert_eq!(rewound.draw_count(), draw);
+            assert_eq!(rewound.next_u32(), outputs[draw as usize]);
+        }
+        assert!(ring.rewind_to(99).is_none());
+    }
+}
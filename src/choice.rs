@@ -0,0 +1,194 @@
+/*! Replayable branch choices for model checkers.
+ *
+ *  A model checker exploring a state space asks a `ChoicePoint` which of
+ *  n branches to take at each step. The choices made are recorded as a
+ *  choice string, such as "2.0.1", and a run can be restarted from any
+ *  prefix of one: the prefix is replayed exactly, and the choices after
+ *  it are made at random from a seed, or always the first branch for
+ *  depth-first search. `next_prefix` then gives the prefix of the next
+ *  run in depth-first order, so the same API drives both randomized and
+ *  exhaustive exploration, and any run can be replayed from its string.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::choice::{parse_choice_string, ChoicePoint};
+ *  // a model with two steps of three and two branches
+ *  fn run(cp: &mut ChoicePoint) -> (usize, usize) {
+ *      (cp.choose(3), cp.choose(2))
+ *  }
+ *
+ *  // random exploration, then replaying a run from its choice string
+ *  let mut cp = ChoicePoint::new(12345);
+ *  let outcome = run(&mut cp);
+ *  let recorded = cp.choice_string();
+ *  let prefix = parse_choice_string(&recorded).unwrap();
+ *  assert_eq!(run(&mut ChoicePoint::from_prefix(&prefix, 0)), outcome);
+ *
+ *  // exhaustive depth-first exploration
+ *  let mut leaves = 0;
+ *  let mut prefix = Some(Vec::new());
+ *  while let Some(p) = prefix {
+ *      let mut cp = ChoicePoint::depth_first(&p);
+ *      run(&mut cp);
+ *      leaves += 1;
+ *      prefix = cp.next_prefix();
+ *  }
+ *  assert_eq!(leaves, 6);
+ *  ```
+ */
+use crate::{bounded_usize, mix64, Pcg};
+use rand_core::SeedableRng;
+
+/// How choices past the prefix are made
+#[derive(Clone)]
+enum Fallback {
+    Random(Pcg),
+    First,
+}
+
+/// Records the branches taken at each choice, replaying a prefix first
+#[derive(Clone)]
+pub struct ChoicePoint {
+    prefix: Vec<usize>,
+    fallback: Fallback,
+    /// The choice made at each step, and the number of branches offered
+    choices: Vec<(usize, usize)>,
+}
+
+impl ChoicePoint {
+    /// Makes every choice at random from `seed`.
+    pub fn new(seed: u64) -> ChoicePoint {
+        ChoicePoint::from_prefix(&[], seed)
+    }
+
+    /// Replays `prefix`, then makes choices at random. The random part
+    /// depends on the prefix as well as the seed, so that runs restarted
+    /// from different prefixes explore differently.
+    pub fn from_prefix(prefix: &[usize], seed: u64) -> ChoicePoint {
+        let seed = prefix
+            .iter()
+            .fold(mix64(seed), |acc, &c| mix64(acc ^ c as u64));
+        ChoicePoint {
+            prefix: prefix.to_vec(),
+            fallback: Fallback::Random(Pcg::seed_from_u64(seed)),
+            choices: Vec::new(),
+        }
+    }
+
+    /// Replays `prefix`, then always takes the first branch.
+    pub fn depth_first(prefix: &[usize]) -> ChoicePoint {
+        ChoicePoint {
+            prefix: prefix.to_vec(),
+            fallback: Fallback::First,
+            choices: Vec::new(),
+        }
+    }
+
+    /// Chooses one of `n` branches, which must be at least one. Panics if
+    /// the prefix names a branch out of range, as happens when replaying
+    /// a choice string against a model that has since changed.
+    pub fn choose(&mut self, n: usize) -> usize {
+        assert!(n > 0, "there must be at least one branch");
+        let step = self.choices.len();
+        let choice = match (self.prefix.get(step), &mut self.fallback) {
+            (Some(&c), _) => {
+                assert!(c < n, "replayed choice {} of {} at step {}", c, n, step);
+                c
+            }
+            (None, Fallback::Random(pcg)) => bounded_usize(pcg, n),
+            (None, Fallback::First) => 0,
+        };
+        self.choices.push((choice, n));
+        choice
+    }
+
+    /// The choices made so far.
+    pub fn choices(&self) -> Vec<usize> {
+        self.choices.iter().map(|&(c, _)| c).collect()
+    }
+
+    /// The choices made so far, separated by dots, as `parse_choice_string`
+    /// reads them.
+    pub fn choice_string(&self) -> String {
+        let choices: Vec<String> = self.choices.iter().map(|(c, _)| c.to_string()).collect();
+        choices.join(".")
+    }
+
+    /// The prefix that follows this run's choices in depth-first order:
+    /// the choices up to the last step with untried later branches, with
+    /// that step's branch moved on by one. None once every branch of
+    /// every step has been tried.
+    pub fn next_prefix(&self) -> Option<Vec<usize>> {
+        let step = self.choices.iter().rposition(|&(c, n)| c + 1 < n)?;
+        let mut prefix: Vec<usize> = self.choices[..step].iter().map(|&(c, _)| c).collect();
+        prefix.push(self.choices[step].0 + 1);
+        Some(prefix)
+    }
+}
+
+/// Reads a choice string written by `choice_string`, or None if it is
+/// malformed. The empty string is the empty prefix.
+pub fn parse_choice_string(s: &str) -> Option<Vec<usize>> {
+    if s.is_empty() {
+        return Some(Vec::new());
+    }
+    s.split('.').map(|c| c.parse().ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small model: a count of up to three steps, each with four branches
+    fn walk(cp: &mut ChoicePoint) -> Vec<usize> {
+        let steps = cp.choose(3) + 1;
+        (0..steps).map(|_| cp.choose(4)).collect()
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut cp = ChoicePoint::new(rand::random::<u64>());
+        let outcome = walk(&mut cp);
+        assert_eq!(cp.choices().len(), outcome.len() + 1);
+
+        let choices = parse_choice_string(&cp.choice_string()).unwrap();
+        assert_eq!(choices, cp.choices());
+        let mut replay = ChoicePoint::from_prefix(&choices, rand::random::<u64>());
+        assert_eq!(walk(&mut replay), outcome);
+
+        // a prefix fixes the start and the seed the rest
+        let seed = rand::random::<u64>();
+        let a = walk(&mut ChoicePoint::from_prefix(&[2, 1], seed));
+        let b = walk(&mut ChoicePoint::from_prefix(&[2, 1], seed));
+        assert_eq!(a, b);
+        assert_eq!((a.len(), a[0]), (3, 1));
+    }
+
+    #[test]
+    fn test_depth_first() {
+        let mut runs = Vec::new();
+        let mut prefix = Some(Vec::new());
+        while let Some(p) = prefix {
+            let mut cp = ChoicePoint::depth_first(&p);
+            runs.push(walk(&mut cp));
+            prefix = cp.next_prefix();
+        }
+        // 4 + 16 + 64 leaves, each visited once, in order
+        assert_eq!(runs.len(), 84);
+        assert_eq!(runs[0], vec![0]);
+        assert_eq!(runs[4], vec![0, 0]);
+        assert_eq!(runs[83], vec![3, 3, 3]);
+        let mut sorted = runs.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 84);
+    }
+
+    #[test]
+    fn test_parse_choice_string() {
+        assert_eq!(parse_choice_string(""), Some(vec![]));
+        assert_eq!(parse_choice_string("3.0.12"), Some(vec![3, 0, 12]));
+        assert_eq!(parse_choice_string("3..1"), None);
+        assert_eq!(parse_choice_string("a"), None);
+    }
+}
@@ -0,0 +1,244 @@
+/*! Random colors and palettes, for charts and debug views.
+ *
+ *  Colors are drawn in OKLCh, a polar form of the OKLab color space, in
+ *  which equal distances look roughly equally different. Hue is an angle
+ *  in degrees, lightness runs from 0 (black) to 1 (white), and chroma is
+ *  colorfulness, up to about 0.37 for the most vivid displayable colors.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::color::{gen_palette, PaletteConstraints};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let constraints = PaletteConstraints::new()
+ *      .with_min_hue_separation(40.0)
+ *      .with_lightness(0.6, 0.8);
+ *
+ *  let palette = gen_palette(&mut pcg, 5, &constraints).unwrap();
+ *  for color in &palette {
+ *      let [r, g, b] = color.to_rgb().unwrap();
+ *      println!("#{:02x}{:02x}{:02x}", r, g, b);
+ *  }
+ *  ```
+ */
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// A color in OKLCh coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color {
+    pub lightness: f64,
+    pub chroma: f64,
+    /// In degrees, in [0, 360)
+    pub hue: f64,
+}
+
+impl Color {
+    /// Converts to 8-bit sRGB, or returns None if the color lies outside
+    /// the sRGB gamut.
+    pub fn to_rgb(&self) -> Option<[u8; 3]> {
+        let (a, b) = (
+            self.chroma * self.hue.to_radians().cos(),
+            self.chroma * self.hue.to_radians().sin(),
+        );
+        let l = (self.lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+        let m = (self.lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+        let s = (self.lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+        let linear = [
+            4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+            -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+            -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+        ];
+        let mut rgb = [0; 3];
+        for (channel, &x) in rgb.iter_mut().zip(&linear) {
+            // allow for rounding in the matrices at the gamut's edge
+            if !(-1e-4..=1.0 + 1e-4).contains(&x) {
+                return None;
+            }
+            let x = x.clamp(0.0, 1.0);
+            let encoded = if x <= 0.0031308 {
+                12.92 * x
+            } else {
+                1.055 * x.powf(1.0 / 2.4) - 0.055
+            };
+            *channel = (encoded * 255.0).round() as u8;
+        }
+        Some(rgb)
+    }
+
+    /// The angle between two colors' hues, in degrees, in [0, 180].
+    pub fn hue_distance(&self, other: &Color) -> f64 {
+        let d = (self.hue - other.hue).rem_euclid(360.0);
+        d.min(360.0 - d)
+    }
+}
+
+/// Limits on the colors of a palette. By default hues are at least 30
+/// degrees apart, with mid-range lightness and moderate chroma.
+#[derive(Clone, Debug)]
+pub struct PaletteConstraints {
+    min_hue_separation: f64,
+    lightness: (f64, f64),
+    chroma: (f64, f64),
+    max_attempts: usize,
+}
+
+impl PaletteConstraints {
+    pub fn new() -> PaletteConstraints {
+        PaletteConstraints {
+            min_hue_separation: 30.0,
+            lightness: (0.55, 0.8),
+            chroma: (0.08, 0.16),
+            max_attempts: 1000,
+        }
+    }
+
+    /// The smallest angle, in degrees, between the hues of any two colors.
+    pub fn with_min_hue_separation(mut self, degrees: f64) -> PaletteConstraints {
+        assert!(
+            (0.0..=180.0).contains(&degrees),
+            "hue separation must lie in [0, 180]"
+        );
+        self.min_hue_separation = degrees;
+        self
+    }
+
+    /// The range of lightness, within [0, 1].
+    pub fn with_lightness(mut self, min: f64, max: f64) -> PaletteConstraints {
+        assert!(
+            0.0 <= min && min <= max && max <= 1.0,
+            "lightness out of order or range"
+        );
+        self.lightness = (min, max);
+        self
+    }
+
+    /// The range of chroma, which must not be negative.
+    pub fn with_chroma(mut self, min: f64, max: f64) -> PaletteConstraints {
+        assert!(0.0 <= min && min <= max, "chroma out of order or range");
+        self.chroma = (min, max);
+        self
+    }
+
+    /// How many candidates to draw for each color before giving up.
+    pub fn with_max_attempts(mut self, attempts: usize) -> PaletteConstraints {
+        self.max_attempts = attempts;
+        self
+    }
+}
+
+impl Default for PaletteConstraints {
+    fn default() -> PaletteConstraints {
+        PaletteConstraints::new()
+    }
+}
+
+/// Generates n displayable colors satisfying the constraints, by
+/// rejecting candidates outside the sRGB gamut or too close in hue to an
+/// earlier color. Returns None if some color could not be placed within
+/// the constraints' attempt limit, which becomes likely as n times the
+/// hue separation approaches 360 degrees.
+pub fn gen_palette<R: RngCore + ?Sized>(
+    rng: &mut R,
+    n: usize,
+    constraints: &PaletteConstraints,
+) -> Option<Vec<Color>> {
+    let (l_min, l_max) = constraints.lightness;
+    let (c_min, c_max) = constraints.chroma;
+    let mut palette: Vec<Color> = Vec::with_capacity(n);
+    while palette.len() < n {
+        let placed = (0..constraints.max_attempts).find_map(|_| {
+            let candidate = Color {
+                lightness: l_min + unit_f64(rng) * (l_max - l_min),
+                chroma: c_min + unit_f64(rng) * (c_max - c_min),
+                hue: unit_f64(rng) * 360.0,
+            };
+            let separated = palette
+                .iter()
+                .all(|c| c.hue_distance(&candidate) >= constraints.min_hue_separation);
+            if separated && candidate.to_rgb().is_some() {
+                Some(candidate)
+            } else {
+                None
+            }
+        });
+        palette.push(placed?);
+    }
+    Some(palette)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    fn gray(lightness: f64) -> Color {
+        Color {
+            lightness,
+            chroma: 0.0,
+            hue: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_to_rgb() {
+        assert_eq!(gray(0.0).to_rgb(), Some([0, 0, 0]));
+        assert_eq!(gray(1.0).to_rgb(), Some([255, 255, 255]));
+        // sRGB red is about (0.628, 0.258, 29.2 degrees)
+        let red = Color {
+            lightness: 0.62796,
+            chroma: 0.25768,
+            hue: 29.2339,
+        };
+        assert_eq!(red.to_rgb(), Some([255, 0, 0]));
+        let too_vivid = Color { chroma: 0.4, ..red };
+        assert_eq!(too_vivid.to_rgb(), None);
+    }
+
+    #[test]
+    fn test_hue_distance() {
+        let at = |hue| Color { hue, ..gray(0.5) };
+        assert_eq!(at(10.0).hue_distance(&at(350.0)), 20.0);
+        assert_eq!(at(90.0).hue_distance(&at(270.0)), 180.0);
+        assert_eq!(at(45.0).hue_distance(&at(45.0)), 0.0);
+    }
+
+    #[test]
+    fn test_gen_palette() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let constraints = PaletteConstraints::new()
+            .with_min_hue_separation(45.0)
+            .with_lightness(0.4, 0.6)
+            .with_chroma(0.05, 0.1);
+        // each color blocks at most 90 degrees of hue, so four always fit
+        let palette = gen_palette(&mut pcg, 4, &constraints).unwrap();
+        assert_eq!(palette.len(), 4);
+        for (i, a) in palette.iter().enumerate() {
+            assert!((0.4..=0.6).contains(&a.lightness));
+            assert!((0.05..=0.1).contains(&a.chroma));
+            assert!(a.to_rgb().is_some());
+            for b in &palette[i + 1..] {
+                assert!(a.hue_distance(b) >= 45.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gen_palette_impossible() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let constraints = PaletteConstraints::new().with_min_hue_separation(120.0);
+        assert!(gen_palette(&mut pcg, 4, &constraints).is_none());
+        assert_eq!(gen_palette(&mut pcg, 0, &constraints), Some(vec![]));
+    }
+
+    #[test]
+    fn test_gen_palette_reproducible() {
+        let seed = rand::random::<u64>();
+        let constraints = PaletteConstraints::new();
+        let a = gen_palette(&mut Pcg::seed_from_u64(seed), 8, &constraints);
+        let b = gen_palette(&mut Pcg::seed_from_u64(seed), 8, &constraints);
+        assert_eq!(a, b);
+    }
+}
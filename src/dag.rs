@@ -0,0 +1,199 @@
+/*! Random directed acyclic graphs, such as task graphs for testing
+ *  schedulers.
+ *
+ *  Graphs are built in layers. Every task outside the first layer depends
+ *  on at least one task in the layer before it, so the longest dependency
+ *  chain runs through every layer, and no layer is wider than requested.
+ *  Tasks are numbered layer by layer, which is a topological order.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::dag::random_dag;
+ *  # use pcg::distributions::Gamma;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let durations = Gamma::new(2.0, 0.5).unwrap();
+ *  let dag = random_dag(&mut pcg, 5, 4, 0.2, &durations);
+ *
+ *  assert_eq!(dag.depth(), 5);
+ *  for &(from, to) in dag.edges() {
+ *      assert!(from < to);
+ *  }
+ *  ```
+ */
+use crate::distributions::Distribution;
+use crate::{bounded_usize, unit_f64};
+use rand_core::RngCore;
+
+/// A layered DAG of tasks annotated with durations
+#[derive(Clone, Debug)]
+pub struct Dag {
+    /// The layer of each task
+    layers: Vec<usize>,
+    /// Dependency edges (from, to), sorted, with from < to
+    edges: Vec<(usize, usize)>,
+    durations: Vec<f64>,
+    depth: usize,
+}
+
+impl Dag {
+    /// The number of tasks.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The number of layers, which is the length of the longest chain.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The layer of a task, counting from zero.
+    pub fn layer(&self, task: usize) -> usize {
+        self.layers[task]
+    }
+
+    /// The duration of a task.
+    pub fn duration(&self, task: usize) -> f64 {
+        self.durations[task]
+    }
+
+    /// The dependency edges (from, to), where `to` waits for `from`.
+    pub fn edges(&self) -> &[(usize, usize)] {
+        &self.edges
+    }
+
+    /// The tasks that `task` depends on.
+    pub fn predecessors(&self, task: usize) -> impl Iterator<Item = usize> + '_ {
+        self.edges
+            .iter()
+            .filter(move |&&(_, to)| to == task)
+            .map(|&(from, _)| from)
+    }
+
+    /// The earliest finishing time of all tasks given unlimited workers,
+    /// i.e. the duration of the critical path.
+    pub fn critical_path(&self) -> f64 {
+        let mut finish = vec![0.0f64; self.len()];
+        let mut edges = self.edges.iter().peekable();
+        for task in 0..self.len() {
+            let mut start = 0.0f64;
+            while let Some(&&(from, to)) = edges.peek() {
+                if to != task {
+                    break;
+                }
+                start = start.max(finish[from]);
+                edges.next();
+            }
+            finish[task] = start + self.durations[task];
+        }
+        finish.into_iter().fold(0.0, f64::max)
+    }
+}
+
+/// Generates a random DAG with `depth` layers of between 1 and `max_width`
+/// tasks each. Each task depends on one random task in the previous layer,
+/// and on every other earlier task with probability `edge_probability`.
+/// Durations are drawn from `durations`.
+pub fn random_dag<D, R>(
+    rng: &mut R,
+    depth: usize,
+    max_width: usize,
+    edge_probability: f64,
+    durations: &D,
+) -> Dag
+where
+    D: Distribution<f64>,
+    R: RngCore + ?Sized,
+{
+    assert!(max_width > 0 || depth == 0, "layers need room for a task");
+    let mut layers = Vec::new();
+    let mut starts = Vec::with_capacity(depth + 1);
+    for layer in 0..depth {
+        starts.push(layers.len());
+        let width = 1 + bounded_usize(rng, max_width);
+        layers.resize(layers.len() + width, layer);
+    }
+    starts.push(layers.len());
+
+    // edges are generated in order of their target, then source
+    let mut edges = Vec::new();
+    for layer in 1..depth {
+        let (previous, current) = (starts[layer - 1], starts[layer]);
+        for task in current..starts[layer + 1] {
+            let parent = previous + bounded_usize(rng, current - previous);
+            for from in 0..current {
+                if from == parent || unit_f64(rng) < edge_probability {
+                    edges.push((from, task));
+                }
+            }
+        }
+    }
+    let durations = (0..layers.len()).map(|_| durations.sample(rng)).collect();
+    Dag {
+        layers,
+        edges,
+        durations,
+        depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::Empirical;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_structure() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let durations = Empirical::from_samples(&[1.0, 2.0, 3.0]).unwrap();
+        for _ in 0..50 {
+            let dag = random_dag(&mut pcg, 6, 3, 0.3, &durations);
+            assert_eq!(dag.depth(), 6);
+            for layer in 0..6 {
+                let width = (0..dag.len()).filter(|&t| dag.layer(t) == layer).count();
+                assert!((1..=3).contains(&width));
+            }
+            for &(from, to) in dag.edges() {
+                assert!(dag.layer(from) < dag.layer(to));
+            }
+            for task in 0..dag.len() {
+                if dag.layer(task) > 0 {
+                    assert!(dag
+                        .predecessors(task)
+                        .any(|p| dag.layer(p) == dag.layer(task) - 1));
+                }
+                assert!([1.0, 2.0, 3.0].contains(&dag.duration(task)));
+            }
+            assert!(dag.critical_path() >= 6.0);
+        }
+        assert!(random_dag(&mut pcg, 0, 3, 0.3, &durations).is_empty());
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let durations = Empirical::from_samples(&[2.0]).unwrap();
+        // with one task per layer, the graph is a chain
+        let dag = random_dag(&mut pcg, 4, 1, 0.0, &durations);
+        assert_eq!(dag.len(), 4);
+        assert_eq!(dag.edges(), &[(0, 1), (1, 2), (2, 3)]);
+        assert_eq!(dag.critical_path(), 8.0);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seed = rand::random::<u64>();
+        let durations = Empirical::from_samples(&[1.0, 5.0]).unwrap();
+        let a = random_dag(&mut Pcg::seed_from_u64(seed), 5, 4, 0.5, &durations);
+        let b = random_dag(&mut Pcg::seed_from_u64(seed), 5, 4, 0.5, &durations);
+        assert_eq!(a.edges(), b.edges());
+        assert_eq!(a.critical_path(), b.critical_path());
+    }
+}
@@ -0,0 +1,77 @@
+use super::{Distribution, ParamError};
+use crate::Pcg;
+use rand_core::RngCore;
+
+/// The Bernoulli distribution, true with probability `p`. The
+/// probability is rounded to a multiple of 2^-32 once, at construction,
+/// so each draw costs one u32 and an integer comparison.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bernoulli {
+    /// A draw is true when next_u32() falls below this, up to 2^32
+    threshold: u64,
+}
+
+impl Bernoulli {
+    pub fn new(p: f64) -> Result<Bernoulli, ParamError> {
+        if !(0.0..=1.0).contains(&p) {
+            return Err(ParamError("probability must lie in [0, 1]"));
+        }
+        Ok(Bernoulli {
+            threshold: (p * 4294967296.0).round() as u64,
+        })
+    }
+
+    /// The probability of true, after rounding.
+    pub fn p(&self) -> f64 {
+        self.threshold as f64 / 4294967296.0
+    }
+}
+
+impl Distribution<bool> for Bernoulli {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> bool {
+        (rng.next_u32() as u64) < self.threshold
+    }
+}
+
+impl Pcg {
+    /// Generate a bool that is true with probability `p`, to within
+    /// 2^-32. Panics unless p lies in [0, 1]. For many flips with the same
+    /// probability, build a `Bernoulli` once instead.
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        Bernoulli::new(p)
+            .expect("probability must lie in [0, 1]")
+            .sample(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_bernoulli() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let coin = Bernoulli::new(0.3).unwrap();
+        let heads = (0..100000).filter(|_| coin.sample(&mut pcg)).count();
+        assert!((29000..31000).contains(&heads));
+        let flips = (0..100000).filter(|_| pcg.gen_bool(0.75)).count();
+        assert!((74000..76000).contains(&flips));
+
+        let never = Bernoulli::new(0.0).unwrap();
+        let always = Bernoulli::new(1.0).unwrap();
+        for _ in 0..1000 {
+            assert!(!never.sample(&mut pcg));
+            assert!(always.sample(&mut pcg));
+        }
+        assert_eq!(always.p(), 1.0);
+        assert!((coin.p() - 0.3).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Bernoulli::new(-0.1).is_err());
+        assert!(Bernoulli::new(1.1).is_err());
+        assert!(Bernoulli::new(f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use super::special::{beta_i, invert_cdf};
+use super::{standard_normal, Continuous, Distribution, Gamma, ParamError};
+use rand_core::RngCore;
+
+/// The chi-square distribution with `df` degrees of freedom,
+/// sampled as Gamma(df / 2, 2)
+#[derive(Clone, Copy, Debug)]
+pub struct ChiSquare {
+    gamma: Gamma,
+}
+
+/// Student's t distribution with `df` degrees of freedom,
+/// sampled as Z / sqrt(V / df) for standard normal Z and chi-square V
+#[derive(Clone, Copy, Debug)]
+pub struct StudentT {
+    chi_square: ChiSquare,
+    df: f64,
+}
+
+impl ChiSquare {
+    pub fn new(df: f64) -> Result<ChiSquare, ParamError> {
+        if !(df > 0.0 && df.is_finite()) {
+            return Err(ParamError("degrees of freedom must be positive and finite"));
+        }
+        Ok(ChiSquare {
+            gamma: Gamma::new(df / 2.0, 2.0)?,
+        })
+    }
+}
+
+impl Distribution<f64> for ChiSquare {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.gamma.sample(rng)
+    }
+}
+
+impl Continuous for ChiSquare {
+    fn cdf(&self, x: f64) -> f64 {
+        self.gamma.cdf(x)
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        self.gamma.inverse_cdf(u)
+    }
+}
+
+impl StudentT {
+    pub fn new(df: f64) -> Result<StudentT, ParamError> {
+        Ok(StudentT {
+            chi_square: ChiSquare::new(df)?,
+            df,
+        })
+    }
+}
+
+impl Distribution<f64> for StudentT {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        let z = standard_normal(rng);
+        z / (self.chi_square.sample(rng) / self.df).sqrt()
+    }
+}
+
+impl Continuous for StudentT {
+    fn cdf(&self, t: f64) -> f64 {
+        if t.is_infinite() {
+            return if t > 0.0 { 1.0 } else { 0.0 };
+        }
+        let tail = if t * t < self.df {
+            // near the center, work with t^2 / (df + t^2) to keep precision
+            0.5 - 0.5 * beta_i(0.5, self.df / 2.0, t * t / (self.df + t * t))
+        } else {
+            0.5 * beta_i(self.df / 2.0, 0.5, self.df / (self.df + t * t))
+        };
+        if t < 0.0 {
+            tail
+        } else {
+            1.0 - tail
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            f64::NEG_INFINITY
+        } else if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            invert_cdf(|t| self.cdf(t), u, -1.0, 1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::{ks_statistic, moments};
+
+    #[test]
+    fn test_chi_square() {
+        for &df in &[1.0, 3.0, 10.0] {
+            let (mean, variance) = moments(&ChiSquare::new(df).unwrap(), 20000);
+            assert!((mean - df).abs() < 0.05 * df);
+            assert!((variance - 2.0 * df).abs() < 0.1 * 2.0 * df);
+            assert!(ks_statistic(&ChiSquare::new(df).unwrap(), 20000) < 1.95 / 20000f64.sqrt());
+        }
+    }
+
+    #[test]
+    fn test_student_t() {
+        let (mean, variance) = moments(&StudentT::new(10.0).unwrap(), 20000);
+        assert!(mean.abs() < 0.05);
+        assert!((variance - 1.25).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_cdf() {
+        let chi_square = ChiSquare::new(2.0).unwrap();
+        assert!((chi_square.cdf(3.0) - (1.0 - (-1.5f64).exp())).abs() < 1e-12);
+        assert!((chi_square.inverse_cdf(0.5) - 2.0 * 2f64.ln()).abs() < 1e-9);
+
+        // with one degree of freedom, Student's t is the Cauchy distribution
+        let student_t = StudentT::new(1.0).unwrap();
+        for &t in &[-10.0, -1.0, 0.0, 0.5, 3.0] {
+            let expected = 0.5 + f64::atan(t) / std::f64::consts::PI;
+            assert!((student_t.cdf(t) - expected).abs() < 1e-12);
+            assert!((student_t.inverse_cdf(expected) - t).abs() < 1e-9);
+        }
+        assert_eq!(student_t.inverse_cdf(0.0), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(ChiSquare::new(0.0).is_err());
+        assert!(StudentT::new(-1.0).is_err());
+        assert!(StudentT::new(f64::INFINITY).is_err());
+    }
+}
@@ -0,0 +1,142 @@
+use super::{Continuous, Distribution, ParamError};
+use crate::{bounded_usize, unit_f64};
+use rand_core::RngCore;
+
+/// How an Empirical distribution turns observations into samples
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmpiricalMode {
+    /// Resample the observed values themselves, each equally likely
+    Resample,
+    /// Interpolate linearly between sorted observations, giving a
+    /// continuous distribution between the smallest and largest of them
+    Interpolate,
+}
+
+/// The distribution of a set of observed values, such as recorded
+/// latencies or request sizes
+#[derive(Clone, Debug)]
+pub struct Empirical {
+    sorted: Vec<f64>,
+    mode: EmpiricalMode,
+}
+
+impl Empirical {
+    /// Builds a resampling distribution from observations, which must be
+    /// non-empty and contain no NaNs.
+    pub fn from_samples(samples: &[f64]) -> Result<Empirical, ParamError> {
+        if samples.is_empty() {
+            return Err(ParamError("empirical distribution needs samples"));
+        }
+        if samples.iter().any(|x| x.is_nan()) {
+            return Err(ParamError("empirical samples must not be NaN"));
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Ok(Empirical {
+            sorted,
+            mode: EmpiricalMode::Resample,
+        })
+    }
+
+    /// Switches between resampling and interpolation.
+    pub fn with_mode(mut self, mode: EmpiricalMode) -> Empirical {
+        self.mode = mode;
+        self
+    }
+
+    pub fn mode(&self) -> EmpiricalMode {
+        self.mode
+    }
+
+    /// The observations, in ascending order.
+    pub fn samples(&self) -> &[f64] {
+        &self.sorted
+    }
+}
+
+impl Distribution<f64> for Empirical {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self.mode {
+            EmpiricalMode::Resample => self.sorted[bounded_usize(rng, self.sorted.len())],
+            EmpiricalMode::Interpolate => self.inverse_cdf(unit_f64(rng)),
+        }
+    }
+}
+
+impl Continuous for Empirical {
+    fn cdf(&self, x: f64) -> f64 {
+        let n = self.sorted.len();
+        let below = self.sorted.partition_point(|&s| s <= x);
+        match self.mode {
+            EmpiricalMode::Resample => below as f64 / n as f64,
+            EmpiricalMode::Interpolate => {
+                if below == 0 {
+                    0.0
+                } else if below == n {
+                    1.0
+                } else {
+                    let (a, b) = (self.sorted[below - 1], self.sorted[below]);
+                    let fraction = (x - a) / (b - a);
+                    (below - 1) as f64 / (n - 1) as f64 + fraction / (n - 1) as f64
+                }
+            }
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        let n = self.sorted.len();
+        let u = u.clamp(0.0, 1.0);
+        match self.mode {
+            EmpiricalMode::Resample => self.sorted[((u * n as f64) as usize).min(n - 1)],
+            EmpiricalMode::Interpolate => {
+                let position = u * (n - 1) as f64;
+                let i = (position as usize).min(n - 1);
+                let fraction = position - i as f64;
+                match self.sorted.get(i + 1) {
+                    Some(next) => self.sorted[i] + fraction * (next - self.sorted[i]),
+                    None => self.sorted[i],
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::moments;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_resample() {
+        let empirical = Empirical::from_samples(&[3.0, 1.0, 2.0, 2.0]).unwrap();
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..100 {
+            assert!([1.0, 2.0, 3.0].contains(&empirical.sample(&mut pcg)));
+        }
+        let (mean, _) = moments(&empirical, 20000);
+        assert!((mean - 2.0).abs() < 0.05);
+        assert_eq!(empirical.cdf(2.0), 0.75);
+        assert_eq!(empirical.inverse_cdf(0.3), 2.0);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let empirical = Empirical::from_samples(&[0.0, 10.0, 20.0])
+            .unwrap()
+            .with_mode(EmpiricalMode::Interpolate);
+        assert_eq!(empirical.inverse_cdf(0.25), 5.0);
+        assert_eq!(empirical.inverse_cdf(1.0), 20.0);
+        assert_eq!(empirical.cdf(15.0), 0.75);
+        assert_eq!(empirical.cdf(-1.0), 0.0);
+        let (mean, _) = moments(&empirical, 20000);
+        assert!((mean - 10.0).abs() < 0.2);
+    }
+
+    #[test]
+    fn test_invalid_samples() {
+        assert!(Empirical::from_samples(&[]).is_err());
+        assert!(Empirical::from_samples(&[1.0, f64::NAN]).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use super::special::{beta_i, gamma_p, invert_cdf};
+use super::{open_unit_f64, standard_normal, Continuous, Distribution, ParamError};
+use rand_core::RngCore;
+
+/// The gamma distribution with the given shape and scale, sampled with
+/// the Marsaglia-Tsang method
+#[derive(Clone, Copy, Debug)]
+pub struct Gamma {
+    shape: f64,
+    scale: f64,
+}
+
+/// The beta distribution on [0, 1], sampled as a ratio of gamma variates
+#[derive(Clone, Copy, Debug)]
+pub struct Beta {
+    a: Gamma,
+    b: Gamma,
+}
+
+impl Gamma {
+    pub fn new(shape: f64, scale: f64) -> Result<Gamma, ParamError> {
+        if !(shape > 0.0 && shape.is_finite()) {
+            return Err(ParamError("gamma shape must be positive and finite"));
+        }
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(ParamError("gamma scale must be positive and finite"));
+        }
+        Ok(Gamma { shape, scale })
+    }
+
+    pub fn shape(&self) -> f64 {
+        self.shape
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// Marsaglia-Tsang for shape >= 1, with unit scale
+fn marsaglia_tsang<R: RngCore + ?Sized>(rng: &mut R, shape: f64) -> f64 {
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = 1.0 + c * x;
+        if v <= 0.0 {
+            continue;
+        }
+        let v = v * v * v;
+        let u = open_unit_f64(rng);
+        let x2 = x * x;
+        if u < 1.0 - 0.0331 * x2 * x2 || u.ln() < 0.5 * x2 + d * (1.0 - v + v.ln()) {
+            return d * v;
+        }
+    }
+}
+
+impl Distribution<f64> for Gamma {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        if self.shape < 1.0 {
+            // boost to shape + 1, then scale back down by u^(1/shape)
+            let x = marsaglia_tsang(rng, self.shape + 1.0);
+            x * open_unit_f64(rng).powf(1.0 / self.shape) * self.scale
+        } else {
+            marsaglia_tsang(rng, self.shape) * self.scale
+        }
+    }
+}
+
+impl Continuous for Gamma {
+    fn cdf(&self, x: f64) -> f64 {
+        gamma_p(self.shape, x / self.scale)
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            0.0
+        } else if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            invert_cdf(|x| self.cdf(x), u, 0.0, self.shape * self.scale)
+        }
+    }
+}
+
+impl Beta {
+    pub fn new(a: f64, b: f64) -> Result<Beta, ParamError> {
+        if !(a > 0.0 && a.is_finite() && b > 0.0 && b.is_finite()) {
+            return Err(ParamError("beta parameters must be positive and finite"));
+        }
+        Ok(Beta {
+            a: Gamma::new(a, 1.0)?,
+            b: Gamma::new(b, 1.0)?,
+        })
+    }
+}
+
+impl Distribution<f64> for Beta {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        let x = self.a.sample(rng);
+        let y = self.b.sample(rng);
+        x / (x + y)
+    }
+}
+
+impl Continuous for Beta {
+    fn cdf(&self, x: f64) -> f64 {
+        beta_i(self.a.shape, self.b.shape, x)
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            0.0
+        } else if u >= 1.0 {
+            1.0
+        } else {
+            invert_cdf(|x| self.cdf(x), u, 0.0, 1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::{ks_statistic, moments};
+
+    #[test]
+    fn test_gamma() {
+        for &(shape, scale) in &[(0.5, 2.0), (1.0, 1.0), (2.5, 0.5), (9.0, 3.0)] {
+            let (mean, variance) = moments(&Gamma::new(shape, scale).unwrap(), 20000);
+            let expected_variance = shape * scale * scale;
+            assert!((mean - shape * scale).abs() < 0.05 * shape * scale + 0.02);
+            assert!((variance - expected_variance).abs() < 0.1 * expected_variance);
+        }
+    }
+
+    #[test]
+    fn test_beta() {
+        for &(a, b) in &[(0.5, 0.5), (2.0, 5.0), (10.0, 1.0)] {
+            let (mean, variance) = moments(&Beta::new(a, b).unwrap(), 20000);
+            let expected_variance = a * b / ((a + b) * (a + b) * (a + b + 1.0));
+            assert!((mean - a / (a + b)).abs() < 0.01);
+            assert!((variance - expected_variance).abs() < 0.1 * expected_variance);
+        }
+    }
+
+    #[test]
+    fn test_shape() {
+        // the whole distribution, on both sides of the shape 1 boost
+        let critical = 1.95 / 20000f64.sqrt();
+        for &(shape, scale) in &[(0.3, 1.0), (1.0, 2.0), (4.5, 0.5)] {
+            assert!(ks_statistic(&Gamma::new(shape, scale).unwrap(), 20000) < critical);
+        }
+        for &(a, b) in &[(0.5, 0.5), (2.0, 5.0)] {
+            assert!(ks_statistic(&Beta::new(a, b).unwrap(), 20000) < critical);
+        }
+    }
+
+    #[test]
+    fn test_gamma_cdf() {
+        let gamma = Gamma::new(2.0, 3.0).unwrap();
+        let x: f64 = 4.5;
+        let expected = 1.0 - (1.0 + x / 3.0) * (-x / 3.0).exp();
+        assert!((gamma.cdf(x) - expected).abs() < 1e-12);
+        assert!((gamma.inverse_cdf(expected) - x).abs() < 1e-9);
+        assert_eq!(gamma.inverse_cdf(0.0), 0.0);
+        assert_eq!(gamma.inverse_cdf(1.0), f64::INFINITY);
+        let small = Gamma::new(0.1, 1.0).unwrap();
+        assert!((small.cdf(small.inverse_cdf(0.3)) - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_beta_cdf() {
+        let beta = Beta::new(2.0, 1.0).unwrap();
+        assert!((beta.cdf(0.5) - 0.25).abs() < 1e-12);
+        assert!((beta.inverse_cdf(0.25) - 0.5).abs() < 1e-12);
+        assert_eq!(beta.inverse_cdf(0.0), 0.0);
+        assert_eq!(beta.inverse_cdf(1.0), 1.0);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Gamma::new(0.0, 1.0).is_err());
+        assert!(Gamma::new(1.0, -1.0).is_err());
+        assert!(Gamma::new(f64::NAN, 1.0).is_err());
+        assert!(Beta::new(1.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,308 @@
+use super::{open_unit_f64, Continuous, Distribution, ParamError};
+use crate::unit_f64;
+use rand_core::RngCore;
+use std::f64::consts::PI;
+
+/// The Pareto distribution: a power-law tail from `scale` upwards, with
+/// density proportional to x^-(shape + 1). The mean is infinite for
+/// shapes up to 1 and the variance for shapes up to 2.
+#[derive(Clone, Copy, Debug)]
+pub struct Pareto {
+    scale: f64,
+    shape: f64,
+}
+
+impl Pareto {
+    pub fn new(scale: f64, shape: f64) -> Result<Pareto, ParamError> {
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(ParamError("Pareto scale must be positive and finite"));
+        }
+        if !(shape > 0.0 && shape.is_finite()) {
+            return Err(ParamError("Pareto shape must be positive"));
+        }
+        Ok(Pareto { scale, shape })
+    }
+}
+
+impl Distribution<f64> for Pareto {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.scale / open_unit_f64(rng).powf(1.0 / self.shape)
+    }
+}
+
+impl Continuous for Pareto {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= self.scale {
+            0.0
+        } else {
+            1.0 - (self.scale / x).powf(self.shape)
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            self.scale / (1.0 - u.max(0.0)).powf(1.0 / self.shape)
+        }
+    }
+}
+
+/// The Cauchy distribution centered on `location`, with half its mass
+/// within `scale` of it. Its tails are so heavy that it has no mean: the
+/// average of many draws is no closer to the location than one draw.
+#[derive(Clone, Copy, Debug)]
+pub struct Cauchy {
+    location: f64,
+    scale: f64,
+}
+
+impl Cauchy {
+    pub fn new(location: f64, scale: f64) -> Result<Cauchy, ParamError> {
+        if !location.is_finite() {
+            return Err(ParamError("Cauchy location must be finite"));
+        }
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(ParamError("Cauchy scale must be positive and finite"));
+        }
+        Ok(Cauchy { location, scale })
+    }
+}
+
+impl Distribution<f64> for Cauchy {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.location + self.scale * (PI * (open_unit_f64(rng) - 0.5)).tan()
+    }
+}
+
+impl Continuous for Cauchy {
+    fn cdf(&self, x: f64) -> f64 {
+        0.5 + ((x - self.location) / self.scale).atan() / PI
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            f64::NEG_INFINITY
+        } else if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            self.location + self.scale * (PI * (u - 0.5)).tan()
+        }
+    }
+}
+
+/// The Pareto distribution capped at a maximum value: a power-law tail
+/// from `scale` to `max`, with density proportional to x^-(shape + 1)
+#[derive(Clone, Copy, Debug)]
+pub struct BoundedPareto {
+    scale: f64,
+    shape: f64,
+    max: f64,
+    /// The mass a Pareto without the cap would place at or below max
+    mass: f64,
+}
+
+impl BoundedPareto {
+    /// Parameters must be finite with 0 < scale < max and shape > 0.
+    pub fn new(scale: f64, shape: f64, max: f64) -> Result<BoundedPareto, ParamError> {
+        if !(scale > 0.0 && max.is_finite() && scale < max) {
+            return Err(ParamError("Pareto bounds must satisfy 0 < scale < max"));
+        }
+        if !(shape > 0.0 && shape.is_finite()) {
+            return Err(ParamError("Pareto shape must be positive"));
+        }
+        Ok(BoundedPareto {
+            scale,
+            shape,
+            max,
+            mass: -(shape * (scale / max).ln()).exp_m1(),
+        })
+    }
+}
+
+impl Distribution<f64> for BoundedPareto {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.inverse_cdf(unit_f64(rng))
+    }
+}
+
+impl Continuous for BoundedPareto {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= self.scale {
+            0.0
+        } else if x >= self.max {
+            1.0
+        } else {
+            (1.0 - (self.scale / x).powf(self.shape)) / self.mass
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        let x = self.scale / (1.0 - u * self.mass).powf(1.0 / self.shape);
+        x.clamp(self.scale, self.max)
+    }
+}
+
+/// The Zipf distribution over the ranks 1 to n, where rank k has
+/// probability proportional to k^-exponent, such as key popularity in a
+/// cache or database workload
+#[derive(Clone, Copy, Debug)]
+pub struct Zipf {
+    n: f64,
+    exponent: f64,
+    h_integral_x1: f64,
+    h_integral_n: f64,
+    s: f64,
+}
+
+impl Zipf {
+    /// n must be at least 1 and the exponent positive.
+    pub fn new(n: u64, exponent: f64) -> Result<Zipf, ParamError> {
+        if n == 0 {
+            return Err(ParamError("Zipf needs at least one rank"));
+        }
+        if !(exponent > 0.0 && exponent.is_finite()) {
+            return Err(ParamError("Zipf exponent must be positive"));
+        }
+        let mut zipf = Zipf {
+            n: n as f64,
+            exponent,
+            h_integral_x1: 0.0,
+            h_integral_n: 0.0,
+            s: 0.0,
+        };
+        zipf.h_integral_x1 = zipf.h_integral(1.5) - 1.0;
+        zipf.h_integral_n = zipf.h_integral(n as f64 + 0.5);
+        zipf.s = 2.0 - zipf.h_integral_inverse(zipf.h_integral(2.5) - zipf.h(2.0));
+        Ok(zipf)
+    }
+
+    fn h(&self, x: f64) -> f64 {
+        (-self.exponent * x.ln()).exp()
+    }
+
+    /// An antiderivative of h
+    fn h_integral(&self, x: f64) -> f64 {
+        let ln_x = x.ln();
+        expm1_over_x((1.0 - self.exponent) * ln_x) * ln_x
+    }
+
+    fn h_integral_inverse(&self, x: f64) -> f64 {
+        let t = (x * (1.0 - self.exponent)).max(-1.0);
+        (ln1p_over_x(t) * x).exp()
+    }
+}
+
+/// (e^x - 1) / x, continued to 1 at 0
+fn expm1_over_x(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.exp_m1() / x
+    } else {
+        1.0 + x / 2.0
+    }
+}
+
+/// ln(1 + x) / x, continued to 1 at 0
+fn ln1p_over_x(x: f64) -> f64 {
+    if x.abs() > 1e-8 {
+        x.ln_1p() / x
+    } else {
+        1.0 - x / 2.0
+    }
+}
+
+impl Distribution<u64> for Zipf {
+    /// Rejection-inversion sampling (Hörmann and Derflinger, 1996), which
+    /// takes constant expected time however many ranks there are.
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> u64 {
+        loop {
+            let u = self.h_integral_n + unit_f64(rng) * (self.h_integral_x1 - self.h_integral_n);
+            let x = self.h_integral_inverse(u);
+            let k = (x + 0.5).clamp(1.0, self.n).floor();
+            if k - x <= self.s || u >= self.h_integral(k + 0.5) - self.h(k) {
+                return k as u64;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::{ks_statistic, moments};
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_pareto() {
+        let critical = 1.95 / 20000f64.sqrt();
+        for &(scale, shape) in &[(1.0, 0.5), (2.0, 3.0)] {
+            assert!(ks_statistic(&Pareto::new(scale, shape).unwrap(), 20000) < critical);
+        }
+        // mean = shape scale / (shape - 1)
+        let (mean, _) = moments(&Pareto::new(2.0, 3.0).unwrap(), 100000);
+        assert!((mean - 3.0).abs() < 0.05);
+        let pareto = Pareto::new(1.0, 2.0).unwrap();
+        assert_eq!(pareto.cdf(0.5), 0.0);
+        assert!((pareto.cdf(2.0) - 0.75).abs() < 1e-12);
+        assert!((pareto.inverse_cdf(0.75) - 2.0).abs() < 1e-12);
+        assert_eq!(pareto.inverse_cdf(1.0), f64::INFINITY);
+        assert!(Pareto::new(0.0, 1.0).is_err());
+        assert!(Pareto::new(1.0, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_cauchy() {
+        let cauchy = Cauchy::new(3.0, 2.0).unwrap();
+        assert!(ks_statistic(&cauchy, 20000) < 1.95 / 20000f64.sqrt());
+        assert_eq!(cauchy.cdf(3.0), 0.5);
+        assert!((cauchy.cdf(5.0) - 0.75).abs() < 1e-12);
+        assert!((cauchy.inverse_cdf(0.25) - 1.0).abs() < 1e-12);
+        assert_eq!(cauchy.inverse_cdf(0.0), f64::NEG_INFINITY);
+        assert!(Cauchy::new(f64::INFINITY, 1.0).is_err());
+        assert!(Cauchy::new(0.0, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_bounded_pareto() {
+        let pareto = BoundedPareto::new(1.0, 1.5, 100.0).unwrap();
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..1000 {
+            assert!((1.0..=100.0).contains(&pareto.sample(&mut pcg)));
+        }
+        assert_eq!(pareto.cdf(100.0), 1.0);
+        assert!((pareto.inverse_cdf(pareto.cdf(7.0)) - 7.0).abs() < 1e-9);
+        // mean = a L^a (L^(1-a) - H^(1-a)) / ((a - 1) (1 - (L/H)^a))
+        let (l, h, a) = (1.0f64, 100.0f64, 1.5f64);
+        let expected = a * l.powf(a) * (l.powf(1.0 - a) - h.powf(1.0 - a))
+            / ((a - 1.0) * (1.0 - (l / h).powf(a)));
+        let (mean, _) = moments(&pareto, 100000);
+        assert!((mean / expected - 1.0).abs() < 0.03);
+        assert!(BoundedPareto::new(1.0, 1.5, 1.0).is_err());
+        assert!(BoundedPareto::new(1.0, 0.0, 2.0).is_err());
+    }
+
+    #[test]
+    fn test_zipf() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        for &exponent in &[0.5, 1.0, 2.0] {
+            let zipf = Zipf::new(10, exponent).unwrap();
+            let norm: f64 = (1..=10).map(|k| (k as f64).powf(-exponent)).sum();
+            let mut counts = [0u32; 11];
+            let n = 100000;
+            for _ in 0..n {
+                counts[zipf.sample(&mut pcg) as usize] += 1;
+            }
+            assert_eq!(counts[0], 0);
+            for (k, &count) in counts.iter().enumerate().skip(1) {
+                let expected = (k as f64).powf(-exponent) / norm;
+                assert!((count as f64 / n as f64 - expected).abs() < 0.01);
+            }
+        }
+        let single = Zipf::new(1, 1.2).unwrap();
+        assert_eq!(single.sample(&mut pcg), 1);
+        let huge = Zipf::new(u64::MAX, 1.1).unwrap();
+        assert!(huge.sample(&mut pcg) >= 1);
+        assert!(Zipf::new(0, 1.0).is_err());
+    }
+}
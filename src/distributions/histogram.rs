@@ -0,0 +1,139 @@
+use super::{Continuous, Distribution, ParamError};
+use crate::{bounded_u64, unit_f64};
+use rand_core::RngCore;
+
+/// Samples from binned counts, such as a telemetry histogram: a bin is
+/// picked with probability proportional to its count, and the value is
+/// uniform within the bin.
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    edges: Vec<f64>,
+    /// Running totals of the counts
+    cumulative: Vec<u64>,
+}
+
+impl Histogram {
+    /// Bin i covers [edges[i], edges[i + 1]), so there must be one more
+    /// edge than counts. Edges must be finite and strictly increasing.
+    pub fn new(edges: &[f64], counts: &[u64]) -> Result<Histogram, ParamError> {
+        if counts.is_empty() || edges.len() != counts.len() + 1 {
+            return Err(ParamError("histogram needs one more edge than counts"));
+        }
+        if edges.iter().any(|x| !x.is_finite()) || edges.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(ParamError("histogram edges must be finite and increasing"));
+        }
+        let mut total: u64 = 0;
+        let mut cumulative = Vec::with_capacity(counts.len());
+        for &count in counts {
+            total = total
+                .checked_add(count)
+                .ok_or(ParamError("histogram counts overflow"))?;
+            cumulative.push(total);
+        }
+        if total == 0 {
+            return Err(ParamError("histogram counts must not all be zero"));
+        }
+        Ok(Histogram {
+            edges: edges.to_vec(),
+            cumulative,
+        })
+    }
+
+    /// Picks a bin with probability proportional to its count.
+    pub fn choose_bin<R: RngCore + ?Sized>(&self, rng: &mut R) -> usize {
+        let target = bounded_u64(rng, self.total());
+        self.cumulative.partition_point(|&c| c <= target)
+    }
+
+    fn total(&self) -> u64 {
+        *self.cumulative.last().unwrap()
+    }
+
+    fn count(&self, bin: usize) -> u64 {
+        self.cumulative[bin]
+            - if bin == 0 {
+                0
+            } else {
+                self.cumulative[bin - 1]
+            }
+    }
+}
+
+impl Distribution<f64> for Histogram {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        let bin = self.choose_bin(rng);
+        let (lo, hi) = (self.edges[bin], self.edges[bin + 1]);
+        lo + (hi - lo) * unit_f64(rng)
+    }
+}
+
+impl Continuous for Histogram {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= self.edges[0] {
+            return 0.0;
+        }
+        let bin = self.edges.partition_point(|&e| e <= x) - 1;
+        if bin >= self.cumulative.len() {
+            return 1.0;
+        }
+        let before = self.cumulative[bin] - self.count(bin);
+        let fraction = (x - self.edges[bin]) / (self.edges[bin + 1] - self.edges[bin]);
+        (before as f64 + fraction * self.count(bin) as f64) / self.total() as f64
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        let target = u.clamp(0.0, 1.0) * self.total() as f64;
+        let bin = self
+            .cumulative
+            .partition_point(|&c| (c as f64) < target)
+            .min(self.cumulative.len() - 1);
+        let before = (self.cumulative[bin] - self.count(bin)) as f64;
+        let fraction = match self.count(bin) {
+            0 => 0.0,
+            count => ((target - before) / count as f64).clamp(0.0, 1.0),
+        };
+        self.edges[bin] + fraction * (self.edges[bin + 1] - self.edges[bin])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::moments;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::new(&[0.0, 10.0, 20.0, 50.0], &[1, 0, 3]).unwrap();
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..1000 {
+            let x = histogram.sample(&mut pcg);
+            assert!((0.0..10.0).contains(&x) || (20.0..50.0).contains(&x));
+        }
+        let (mean, _) = moments(&histogram, 20000);
+        assert!((mean - (5.0 + 3.0 * 35.0) / 4.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_cdf() {
+        let histogram = Histogram::new(&[0.0, 10.0, 20.0, 50.0], &[1, 0, 3]).unwrap();
+        assert_eq!(histogram.cdf(-1.0), 0.0);
+        assert_eq!(histogram.cdf(5.0), 0.125);
+        assert_eq!(histogram.cdf(15.0), 0.25);
+        assert_eq!(histogram.cdf(35.0), 0.625);
+        assert_eq!(histogram.cdf(60.0), 1.0);
+        assert_eq!(histogram.inverse_cdf(0.125), 5.0);
+        assert_eq!(histogram.inverse_cdf(0.625), 35.0);
+        assert_eq!(histogram.inverse_cdf(1.0), 50.0);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Histogram::new(&[0.0, 1.0], &[]).is_err());
+        assert!(Histogram::new(&[0.0, 1.0], &[1, 1]).is_err());
+        assert!(Histogram::new(&[1.0, 0.0], &[1]).is_err());
+        assert!(Histogram::new(&[0.0, 1.0], &[0]).is_err());
+        assert!(Histogram::new(&[0.0, 1.0, 2.0], &[u64::MAX, 1]).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use super::{Continuous, Distribution, ParamError};
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// The log-uniform (reciprocal) distribution between lo and hi, whose
+/// logarithm is uniform: each order of magnitude is equally likely
+#[derive(Clone, Copy, Debug)]
+pub struct LogUniform {
+    lo: f64,
+    hi: f64,
+    ln_lo: f64,
+    ln_hi: f64,
+}
+
+impl LogUniform {
+    /// Bounds must be finite with 0 < lo < hi.
+    pub fn new(lo: f64, hi: f64) -> Result<LogUniform, ParamError> {
+        if !(lo > 0.0 && hi.is_finite() && lo < hi) {
+            return Err(ParamError("log-uniform bounds must satisfy 0 < lo < hi"));
+        }
+        Ok(LogUniform {
+            lo,
+            hi,
+            ln_lo: lo.ln(),
+            ln_hi: hi.ln(),
+        })
+    }
+}
+
+impl Distribution<f64> for LogUniform {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.inverse_cdf(unit_f64(rng))
+    }
+}
+
+impl Continuous for LogUniform {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= self.lo {
+            0.0
+        } else if x >= self.hi {
+            1.0
+        } else {
+            (x.ln() - self.ln_lo) / (self.ln_hi - self.ln_lo)
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        // exp can round just outside the bounds
+        (self.ln_lo + u * (self.ln_hi - self.ln_lo))
+            .exp()
+            .clamp(self.lo, self.hi)
+    }
+}
+
+/// Types `gen_log_uniform` can produce
+pub trait LogUniformRange: Sized {
+    fn sample_log_uniform<R: RngCore + ?Sized>(rng: &mut R, lo: Self, hi: Self) -> Self;
+}
+
+/// Generates a value between lo and hi, both inclusive, uniformly in log
+/// space. Integers must satisfy 1 <= lo <= hi, and each integer k is drawn
+/// with probability proportional to ln((k + 1) / k); floats must satisfy
+/// 0 < lo <= hi.
+pub fn gen_log_uniform<T, R>(rng: &mut R, lo: T, hi: T) -> T
+where
+    T: LogUniformRange,
+    R: RngCore + ?Sized,
+{
+    T::sample_log_uniform(rng, lo, hi)
+}
+
+macro_rules! log_uniform_int {
+    ($($t:ty),*) => {$(
+        impl LogUniformRange for $t {
+            fn sample_log_uniform<R: RngCore + ?Sized>(rng: &mut R, lo: $t, hi: $t) -> $t {
+                assert!(1 <= lo && lo <= hi, "bounds must satisfy 1 <= lo <= hi");
+                // floor a continuous draw over [lo, hi + 1)
+                let (ln_lo, ln_hi) = ((lo as f64).ln(), (hi as f64 + 1.0).ln());
+                let x = (ln_lo + unit_f64(rng) * (ln_hi - ln_lo)).exp();
+                (x as $t).clamp(lo, hi)
+            }
+        }
+    )*};
+}
+
+log_uniform_int!(u8, u16, u32, u64, usize);
+
+macro_rules! log_uniform_float {
+    ($($t:ty),*) => {$(
+        impl LogUniformRange for $t {
+            fn sample_log_uniform<R: RngCore + ?Sized>(rng: &mut R, lo: $t, hi: $t) -> $t {
+                assert!(0.0 < lo && lo <= hi && hi.is_finite(), "bounds must satisfy 0 < lo <= hi");
+                let (ln_lo, ln_hi) = ((lo as f64).ln(), (hi as f64).ln());
+                let x = (ln_lo + unit_f64(rng) * (ln_hi - ln_lo)).exp();
+                (x as $t).clamp(lo, hi)
+            }
+        }
+    )*};
+}
+
+log_uniform_float!(f32, f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::moments;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_log_uniform() {
+        let dist = LogUniform::new(1.0, 1000.0).unwrap();
+        assert!((dist.cdf(10.0) - 1.0 / 3.0).abs() < 1e-12);
+        assert!((dist.inverse_cdf(2.0 / 3.0) - 100.0).abs() < 1e-9);
+        // the mean is (hi - lo) / ln(hi / lo)
+        let (mean, _) = moments(&dist, 50000);
+        assert!((mean / (999.0 / 1000f64.ln()) - 1.0).abs() < 0.03);
+        assert!(LogUniform::new(0.0, 1.0).is_err());
+        assert!(LogUniform::new(2.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_gen_log_uniform_int() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut decades = [0; 3];
+        for _ in 0..30000 {
+            let x: u32 = gen_log_uniform(&mut pcg, 1, 999);
+            assert!((1..=999).contains(&x));
+            decades[(x as f64).log10() as usize] += 1;
+        }
+        assert!(decades.iter().all(|&c| (9500..10500).contains(&c)));
+        assert_eq!(gen_log_uniform(&mut pcg, 7u64, 7), 7);
+        let x = gen_log_uniform(&mut pcg, 1, u64::MAX);
+        assert!(x >= 1);
+    }
+
+    #[test]
+    fn test_gen_log_uniform_float() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..1000 {
+            let lr: f64 = gen_log_uniform(&mut pcg, 1e-5, 1e-1);
+            assert!((1e-5..=1e-1).contains(&lr));
+            let x: f32 = gen_log_uniform(&mut pcg, 0.5, 2.0);
+            assert!((0.5..=2.0).contains(&x));
+        }
+    }
+}
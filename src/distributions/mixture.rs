@@ -0,0 +1,171 @@
+use super::special::invert_cdf;
+use super::{Continuous, Distribution, ParamError};
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// A weighted mixture of component distributions: each sample picks a
+/// component with probability proportional to its weight, then samples it.
+///
+/// Components of different types can be mixed by boxing them as
+/// `Box<dyn DynDistribution<T>>`.
+#[derive(Clone, Debug)]
+pub struct Mixture<D> {
+    components: Vec<D>,
+    /// Running totals of the normalized weights, ending at 1
+    cumulative: Vec<f64>,
+}
+
+impl<D> Mixture<D> {
+    pub fn new(components: Vec<(f64, D)>) -> Result<Mixture<D>, ParamError> {
+        if components.is_empty() {
+            return Err(ParamError("mixture needs at least one component"));
+        }
+        if components
+            .iter()
+            .any(|(weight, _)| !(*weight >= 0.0 && weight.is_finite()))
+        {
+            return Err(ParamError(
+                "mixture weights must be non-negative and finite",
+            ));
+        }
+        let total: f64 = components.iter().map(|(weight, _)| weight).sum();
+        if total <= 0.0 {
+            return Err(ParamError("mixture weights must not all be zero"));
+        }
+        let mut sum = 0.0;
+        let mut cumulative = Vec::with_capacity(components.len());
+        for (weight, _) in &components {
+            sum += weight / total;
+            cumulative.push(sum);
+        }
+        Ok(Mixture {
+            components: components.into_iter().map(|(_, dist)| dist).collect(),
+            cumulative,
+        })
+    }
+
+    /// Picks the index of a component according to the weights.
+    pub fn choose_component<R: RngCore + ?Sized>(&self, rng: &mut R) -> usize {
+        let u = unit_f64(rng);
+        let i = self.cumulative.partition_point(|&c| c <= u);
+        // guard against rounding leaving the last total just below 1,
+        // and skip any zero-weight components at the end
+        i.min(self.last_weighted())
+    }
+
+    fn last_weighted(&self) -> usize {
+        let mut i = self.cumulative.len() - 1;
+        while i > 0 && self.cumulative[i] == self.cumulative[i - 1] {
+            i -= 1;
+        }
+        i
+    }
+
+    fn weight(&self, i: usize) -> f64 {
+        if i == 0 {
+            self.cumulative[0]
+        } else {
+            self.cumulative[i] - self.cumulative[i - 1]
+        }
+    }
+}
+
+impl<T, D: Distribution<T>> Distribution<T> for Mixture<D> {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> T {
+        let i = self.choose_component(rng);
+        self.components[i].sample(rng)
+    }
+}
+
+impl<D: Continuous> Continuous for Mixture<D> {
+    fn cdf(&self, x: f64) -> f64 {
+        let mut sum = 0.0;
+        for (i, component) in self.components.iter().enumerate() {
+            sum += self.weight(i) * component.cdf(x);
+        }
+        sum.min(1.0)
+    }
+
+    /// Computed numerically; the result lies between the smallest and
+    /// largest of the weighted components' quantiles at `u`.
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        let mut lo = f64::INFINITY;
+        let mut hi = f64::NEG_INFINITY;
+        for (i, component) in self.components.iter().enumerate() {
+            if self.weight(i) > 0.0 {
+                let x = component.inverse_cdf(u);
+                lo = lo.min(x);
+                hi = hi.max(x);
+            }
+        }
+        if lo >= hi || lo.is_infinite() || hi.is_infinite() {
+            return if u <= 0.5 { lo } else { hi };
+        }
+        invert_cdf(|x| self.cdf(x), u, lo, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::moments;
+    use crate::distributions::{DynDistribution, Gamma, StudentT};
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_mixture() {
+        let mixture = Mixture::new(vec![
+            (3.0, Gamma::new(1.0, 1.0).unwrap()),
+            (1.0, Gamma::new(100.0, 1.0).unwrap()),
+        ])
+        .unwrap();
+        let (mean, _) = moments(&mixture, 20000);
+        assert!((mean - 25.75).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_cdf() {
+        let mixture = Mixture::new(vec![
+            (1.0, Gamma::new(1.0, 1.0).unwrap()),
+            (1.0, Gamma::new(1.0, 2.0).unwrap()),
+        ])
+        .unwrap();
+        let x: f64 = 1.5;
+        let expected = 1.0 - 0.5 * (-x).exp() - 0.5 * (-x / 2.0).exp();
+        assert!((mixture.cdf(x) - expected).abs() < 1e-12);
+        assert!((mixture.inverse_cdf(expected) - x).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_boxed_components() {
+        let components: Vec<(f64, Box<dyn DynDistribution<f64>>)> = vec![
+            (1.0, Box::new(Gamma::new(1.0, 1.0).unwrap())),
+            (1.0, Box::new(StudentT::new(5.0).unwrap())),
+        ];
+        let mixture = Mixture::new(components).unwrap();
+        let (mean, _) = moments(&mixture, 20000);
+        assert!((mean - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_zero_weights() {
+        let mixture = Mixture::new(vec![
+            (0.0, Gamma::new(1.0, 1.0).unwrap()),
+            (1.0, Gamma::new(1.0, 1.0).unwrap()),
+            (0.0, Gamma::new(1.0, 1.0).unwrap()),
+        ])
+        .unwrap();
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..1000 {
+            assert_eq!(mixture.choose_component(&mut pcg), 1);
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Mixture::<Gamma>::new(vec![]).is_err());
+        assert!(Mixture::new(vec![(0.0, Gamma::new(1.0, 1.0).unwrap())]).is_err());
+        assert!(Mixture::new(vec![(-1.0, Gamma::new(1.0, 1.0).unwrap())]).is_err());
+    }
+}
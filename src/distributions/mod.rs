@@ -0,0 +1,196 @@
+/*! Sampling from probability distributions, usable directly with Pcg
+ *  (or any other `RngCore`) without depending on rand_distr.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::distributions::{Distribution, Gamma};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let gamma = Gamma::new(2.0, 3.0).unwrap();
+ *  let x = gamma.sample(&mut pcg);
+ *
+ *  assert!(x > 0.0);
+ *  ```
+ */
+use crate::unit_f64;
+use rand_core::RngCore;
+use std::error::Error;
+use std::fmt;
+
+mod bernoulli;
+mod chi_square;
+mod empirical;
+mod gamma;
+mod heavy_tail;
+mod histogram;
+mod log_uniform;
+mod mixture;
+mod multivariate;
+mod normal;
+mod poisson;
+mod special;
+mod truncated;
+mod weibull;
+mod weighted;
+
+pub use bernoulli::Bernoulli;
+pub use chi_square::{ChiSquare, StudentT};
+pub use empirical::{Empirical, EmpiricalMode};
+pub use gamma::{Beta, Gamma};
+pub use heavy_tail::{BoundedPareto, Cauchy, Pareto, Zipf};
+pub use histogram::Histogram;
+pub use log_uniform::{gen_log_uniform, LogUniform, LogUniformRange};
+pub use mixture::Mixture;
+pub use multivariate::{GaussianCopula, MultivariateNormal};
+pub use normal::{LogNormal, Normal};
+pub use poisson::{Exponential, Poisson};
+pub use truncated::Truncated;
+pub use weibull::Weibull;
+pub use weighted::WeightedIndex;
+
+pub(crate) use multivariate::cholesky;
+
+/// Types that can produce random values of type T
+pub trait Distribution<T> {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> T;
+}
+
+/// An object-safe form of Distribution, so that distributions of different
+/// types can be boxed and combined, e.g. as components of a Mixture.
+/// It is implemented for every Distribution.
+pub trait DynDistribution<T> {
+    fn sample_dyn(&self, rng: &mut dyn RngCore) -> T;
+}
+
+impl<T, D: Distribution<T>> DynDistribution<T> for D {
+    fn sample_dyn(&self, rng: &mut dyn RngCore) -> T {
+        self.sample(rng)
+    }
+}
+
+impl<T> Distribution<T> for Box<dyn DynDistribution<T>> {
+    fn sample<R: RngCore + ?Sized>(&self, mut rng: &mut R) -> T {
+        self.as_ref().sample_dyn(&mut rng)
+    }
+}
+
+/// One-dimensional continuous distributions, with their CDF and quantile
+/// function. The quantile function maps a uniform value in [0, 1] through
+/// the distribution, e.g. for quasi-Monte-Carlo with low-discrepancy points.
+/// Distributions without a closed form compute it numerically, which is
+/// considerably slower than sampling.
+pub trait Continuous: Distribution<f64> {
+    /// The cumulative distribution function at `x`.
+    fn cdf(&self, x: f64) -> f64;
+
+    /// The quantile function at `u` in [0, 1].
+    fn inverse_cdf(&self, u: f64) -> f64;
+}
+
+/// The standard normal distribution, with mean 0 and standard deviation 1
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StandardNormal;
+
+impl Distribution<f64> for StandardNormal {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        standard_normal(rng)
+    }
+}
+
+impl Continuous for StandardNormal {
+    fn cdf(&self, x: f64) -> f64 {
+        special::normal_cdf(x)
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            f64::NEG_INFINITY
+        } else if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            special::invert_cdf(special::normal_cdf, u, -1.0, 1.0)
+        }
+    }
+}
+
+/// Returned when a distribution is constructed with invalid parameters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamError(pub(crate) &'static str);
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid distribution parameter: {}", self.0)
+    }
+}
+
+impl Error for ParamError {}
+
+/// Generates a standard normal value with the Marsaglia polar method.
+pub(crate) fn standard_normal<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    loop {
+        let x = 2.0 * unit_f64(rng) - 1.0;
+        let y = 2.0 * unit_f64(rng) - 1.0;
+        let s = x * x + y * y;
+        if s < 1.0 && s > 0.0 {
+            return x * (-2.0 * s.ln() / s).sqrt();
+        }
+    }
+}
+
+/// Generates an f64 in (0, 1), for use where zero would be degenerate.
+pub(crate) fn open_unit_f64<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    ((rng.next_u64() >> 12) as f64 + 0.5) * (1.0 / (1u64 << 52) as f64)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    /// Returns the sample mean and variance of n draws from a distribution.
+    /// A fixed seed keeps the statistical tests from failing at random.
+    pub(crate) fn moments<D: Distribution<f64>>(dist: &D, n: usize) -> (f64, f64) {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let samples: Vec<f64> = (0..n).map(|_| dist.sample(&mut pcg)).collect();
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+        (mean, variance)
+    }
+
+    /// Returns the Kolmogorov-Smirnov statistic of n draws from a
+    /// distribution against its own CDF, the largest gap between the two.
+    /// Above 1.95 / sqrt(n), the sample fails at the 0.1% level.
+    pub(crate) fn ks_statistic<D: Continuous>(dist: &D, n: usize) -> f64 {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut samples: Vec<f64> = (0..n).map(|_| dist.sample(&mut pcg)).collect();
+        samples.sort_by(f64::total_cmp);
+        samples
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let cdf = dist.cdf(x);
+                (cdf - i as f64 / n as f64).max((i + 1) as f64 / n as f64 - cdf)
+            })
+            .fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_standard_normal() {
+        let (mean, variance) = moments(&StandardNormal, 20000);
+        assert!(mean.abs() < 0.05);
+        assert!((variance - 1.0).abs() < 0.05);
+        assert!((StandardNormal.inverse_cdf(0.975) - 1.959963984540054).abs() < 1e-9);
+        assert!(ks_statistic(&StandardNormal, 20000) < 1.95 / 20000f64.sqrt());
+    }
+
+    #[test]
+    fn test_open_unit_f64() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..1000 {
+            let u = open_unit_f64(&mut pcg);
+            assert!(u > 0.0 && u < 1.0);
+        }
+    }
+}
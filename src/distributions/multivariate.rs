@@ -0,0 +1,225 @@
+use super::special::normal_cdf;
+use super::{standard_normal, Distribution, ParamError};
+use rand_core::RngCore;
+
+/// The multivariate normal distribution, producing correlated vectors.
+/// The covariance matrix is factorized once, on construction.
+#[derive(Clone, Debug)]
+pub struct MultivariateNormal {
+    mean: Vec<f64>,
+    /// Lower triangular Cholesky factor of the covariance, row-major
+    factor: Vec<f64>,
+}
+
+/// A Gaussian copula, producing tuples of uniform values in [0, 1] that
+/// are dependent according to a correlation matrix. Mapping each value
+/// through a distribution's `inverse_cdf` gives correlated samples with
+/// arbitrary marginals.
+///
+/// ```
+/// # use pcg::Pcg;
+/// # use pcg::distributions::{Continuous, Distribution, Gamma, GaussianCopula};
+/// # use rand_core::SeedableRng;
+/// let mut pcg = Pcg::seed_from_u64(12345);
+/// let copula = GaussianCopula::new(&[1.0, 0.8, 0.8, 1.0]).unwrap();
+/// let gamma = Gamma::new(2.0, 1.0).unwrap();
+///
+/// let u = copula.sample(&mut pcg);
+/// let (loss, delay) = (gamma.inverse_cdf(u[0]), 10.0 * u[1]);
+/// assert!(loss >= 0.0 && delay < 10.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GaussianCopula {
+    dimension: usize,
+    factor: Vec<f64>,
+}
+
+/// Computes the lower triangular L with L * L^T = matrix, for a symmetric
+/// positive definite n x n matrix in row-major order.
+pub(crate) fn cholesky(matrix: &[f64], n: usize) -> Result<Vec<f64>, ParamError> {
+    if matrix.len() != n * n {
+        return Err(ParamError("matrix must be n x n"));
+    }
+    for i in 0..n {
+        for j in 0..i {
+            if (matrix[i * n + j] - matrix[j * n + i]).abs()
+                > 1e-12 * (1.0 + matrix[i * n + j].abs())
+            {
+                return Err(ParamError("matrix must be symmetric"));
+            }
+        }
+    }
+    let mut factor = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let mut sum = matrix[i * n + j];
+            for k in 0..j {
+                sum -= factor[i * n + k] * factor[j * n + k];
+            }
+            if i == j {
+                if sum.is_nan() || sum <= 0.0 {
+                    return Err(ParamError("matrix must be positive definite"));
+                }
+                factor[i * n + i] = sum.sqrt();
+            } else {
+                factor[i * n + j] = sum / factor[j * n + j];
+            }
+        }
+    }
+    Ok(factor)
+}
+
+/// Fills `out` with L * z for the lower triangular `factor` and a vector
+/// of independent standard normal draws z.
+pub(crate) fn correlated_normals<R: RngCore + ?Sized>(
+    rng: &mut R,
+    factor: &[f64],
+    out: &mut [f64],
+) {
+    let n = out.len();
+    let z: Vec<f64> = (0..n).map(|_| standard_normal(rng)).collect();
+    for i in 0..n {
+        out[i] = (0..=i).map(|k| factor[i * n + k] * z[k]).sum();
+    }
+}
+
+impl MultivariateNormal {
+    /// `covariance` is an n x n symmetric positive definite matrix in
+    /// row-major order, where n is the length of `mean`.
+    pub fn new(mean: &[f64], covariance: &[f64]) -> Result<MultivariateNormal, ParamError> {
+        if mean.is_empty() || mean.iter().any(|x| !x.is_finite()) {
+            return Err(ParamError("mean must be non-empty and finite"));
+        }
+        Ok(MultivariateNormal {
+            mean: mean.to_vec(),
+            factor: cholesky(covariance, mean.len())?,
+        })
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.mean.len()
+    }
+
+    /// Samples into `out`, which must have the distribution's dimension.
+    pub fn sample_into<R: RngCore + ?Sized>(&self, rng: &mut R, out: &mut [f64]) {
+        assert_eq!(out.len(), self.dimension(), "output has wrong dimension");
+        correlated_normals(rng, &self.factor, out);
+        for (x, mean) in out.iter_mut().zip(&self.mean) {
+            *x += mean;
+        }
+    }
+}
+
+impl Distribution<Vec<f64>> for MultivariateNormal {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> Vec<f64> {
+        let mut out = vec![0.0; self.dimension()];
+        self.sample_into(rng, &mut out);
+        out
+    }
+}
+
+impl GaussianCopula {
+    /// `correlation` is an n x n positive definite correlation matrix in
+    /// row-major order, with ones on the diagonal.
+    pub fn new(correlation: &[f64]) -> Result<GaussianCopula, ParamError> {
+        let dimension = (correlation.len() as f64).sqrt() as usize;
+        if dimension == 0 || dimension * dimension != correlation.len() {
+            return Err(ParamError("correlation matrix must be n x n"));
+        }
+        if (0..dimension).any(|i| correlation[i * dimension + i] != 1.0) {
+            return Err(ParamError("correlation matrix must have a unit diagonal"));
+        }
+        Ok(GaussianCopula {
+            dimension,
+            factor: cholesky(correlation, dimension)?,
+        })
+    }
+
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Samples into `out`, which must have the copula's dimension.
+    pub fn sample_into<R: RngCore + ?Sized>(&self, rng: &mut R, out: &mut [f64]) {
+        assert_eq!(out.len(), self.dimension, "output has wrong dimension");
+        correlated_normals(rng, &self.factor, out);
+        for x in out.iter_mut() {
+            *x = normal_cdf(*x);
+        }
+    }
+}
+
+impl Distribution<Vec<f64>> for GaussianCopula {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> Vec<f64> {
+        let mut out = vec![0.0; self.dimension];
+        self.sample_into(rng, &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_cholesky() {
+        let matrix = [4.0, 2.0, 2.0, 3.0];
+        let factor = cholesky(&matrix, 2).unwrap();
+        assert_eq!(factor, vec![2.0, 0.0, 1.0, 2f64.sqrt()]);
+        assert!(cholesky(&[1.0, 2.0, 2.0, 1.0], 2).is_err());
+        assert!(cholesky(&[1.0, 0.5, 0.0, 1.0], 2).is_err());
+        assert!(cholesky(&[1.0, 0.0, 0.0], 2).is_err());
+    }
+
+    #[test]
+    fn test_covariance() {
+        let mean = [1.0, -2.0];
+        let covariance = [2.0, -1.2, -1.2, 1.0];
+        let mvn = MultivariateNormal::new(&mean, &covariance).unwrap();
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let n = 20000;
+        let samples: Vec<Vec<f64>> = (0..n).map(|_| mvn.sample(&mut pcg)).collect();
+        for i in 0..2 {
+            let sample_mean = samples.iter().map(|s| s[i]).sum::<f64>() / n as f64;
+            assert!((sample_mean - mean[i]).abs() < 0.05);
+        }
+        for i in 0..2 {
+            for j in 0..2 {
+                let sample_covariance = samples
+                    .iter()
+                    .map(|s| (s[i] - mean[i]) * (s[j] - mean[j]))
+                    .sum::<f64>()
+                    / n as f64;
+                assert!((sample_covariance - covariance[i * 2 + j]).abs() < 0.1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_copula() {
+        let copula = GaussianCopula::new(&[1.0, 0.9, 0.9, 1.0]).unwrap();
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let n = 20000;
+        let mut products = 0.0;
+        for _ in 0..n {
+            let u = copula.sample(&mut pcg);
+            assert!(u.iter().all(|x| (0.0..=1.0).contains(x)));
+            products += (u[0] - 0.5) * (u[1] - 0.5);
+        }
+        // Spearman's rho of the Gaussian copula is 6/pi * asin(rho/2)
+        let rho = 12.0 * products / n as f64;
+        let expected = 6.0 / std::f64::consts::PI * (0.45f64).asin();
+        assert!((rho - expected).abs() < 0.02);
+        assert!(GaussianCopula::new(&[2.0, 0.0, 0.0, 1.0]).is_err());
+        assert!(GaussianCopula::new(&[1.0, 0.0, 0.0]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(MultivariateNormal::new(&[], &[]).is_err());
+        assert!(MultivariateNormal::new(&[0.0, 0.0], &[1.0]).is_err());
+        assert!(MultivariateNormal::new(&[0.0, 0.0], &[1.0, 2.0, 2.0, 1.0]).is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use super::special::normal_cdf;
+use super::{open_unit_f64, Continuous, Distribution, ParamError, StandardNormal};
+use rand_core::RngCore;
+use std::sync::OnceLock;
+
+/// Layers in the ziggurat
+const LAYERS: usize = 256;
+/// Where the tail begins, for 256 layers
+const TAIL_START: f64 = 3.654_152_885_361_009;
+/// The area of each layer, and of the base strip with the tail
+const LAYER_AREA: f64 = 4.928_673_233_99e-3;
+
+/// The normal distribution with the given mean and standard deviation,
+/// sampled with Marsaglia and Tsang's ziggurat method, which usually
+/// costs one u64 and a multiply. `StandardNormal` keeps the polar method,
+/// so the streams of the distributions built on it are unchanged.
+#[derive(Clone, Copy, Debug)]
+pub struct Normal {
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Normal {
+    pub fn new(mean: f64, std_dev: f64) -> Result<Normal, ParamError> {
+        if !mean.is_finite() {
+            return Err(ParamError("normal mean must be finite"));
+        }
+        if !(std_dev > 0.0 && std_dev.is_finite()) {
+            return Err(ParamError(
+                "normal standard deviation must be positive and finite",
+            ));
+        }
+        Ok(Normal { mean, std_dev })
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.std_dev
+    }
+}
+
+/// The log-normal distribution, whose logarithm is normal with mean `mu`
+/// and standard deviation `sigma`: long right tails, as in latencies and
+/// times to failure. Sampled through the same ziggurat as `Normal`.
+#[derive(Clone, Copy, Debug)]
+pub struct LogNormal {
+    normal: Normal,
+}
+
+impl LogNormal {
+    pub fn new(mu: f64, sigma: f64) -> Result<LogNormal, ParamError> {
+        Ok(LogNormal {
+            normal: Normal::new(mu, sigma)?,
+        })
+    }
+
+    /// Fits the distribution with the given mean and standard deviation,
+    /// of the values themselves rather than their logarithms.
+    pub fn from_mean_std_dev(mean: f64, std_dev: f64) -> Result<LogNormal, ParamError> {
+        if !(mean > 0.0 && mean.is_finite()) {
+            return Err(ParamError("log-normal mean must be positive and finite"));
+        }
+        let sigma_sq = (std_dev / mean).powi(2).ln_1p();
+        LogNormal::new(mean.ln() - sigma_sq / 2.0, sigma_sq.sqrt())
+    }
+
+    pub fn mu(&self) -> f64 {
+        self.normal.mean
+    }
+
+    pub fn sigma(&self) -> f64 {
+        self.normal.std_dev
+    }
+}
+
+impl Distribution<f64> for LogNormal {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.normal.sample(rng).exp()
+    }
+}
+
+impl Continuous for LogNormal {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= 0.0 {
+            0.0
+        } else {
+            self.normal.cdf(x.ln())
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        self.normal.inverse_cdf(u).exp()
+    }
+}
+
+/// The unnormalized density, exp(-x^2 / 2)
+fn density(x: f64) -> f64 {
+    (-0.5 * x * x).exp()
+}
+
+/// The right edges of the layers, from the base strip's notional width
+/// down to zero, and the density at each
+struct Tables {
+    x: [f64; LAYERS + 1],
+    f: [f64; LAYERS + 1],
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut x = [0.0; LAYERS + 1];
+        x[0] = LAYER_AREA / density(TAIL_START);
+        x[1] = TAIL_START;
+        for i in 1..LAYERS - 1 {
+            x[i + 1] = (-2.0 * (LAYER_AREA / x[i] + density(x[i])).ln()).sqrt();
+        }
+        let mut f = [0.0; LAYERS + 1];
+        for (f, &x) in f.iter_mut().zip(x.iter()) {
+            *f = density(x);
+        }
+        Tables { x, f }
+    })
+}
+
+/// Generates a standard normal value with the ziggurat method.
+fn ziggurat<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    let Tables { x, f } = tables();
+    loop {
+        // the low 8 bits pick a layer and the top 53 a signed position
+        let bits = rng.next_u64();
+        let i = (bits & 0xFF) as usize;
+        let u = (bits >> 11) as f64 * (2.0 / (1u64 << 53) as f64) - 1.0;
+        let z = u * x[i];
+        if z.abs() < x[i + 1] {
+            return z;
+        }
+        if i == 0 {
+            // the tail beyond TAIL_START, by Marsaglia's method
+            loop {
+                let t = -open_unit_f64(rng).ln() / TAIL_START;
+                let y = -open_unit_f64(rng).ln();
+                if 2.0 * y >= t * t {
+                    return if u < 0.0 {
+                        -(TAIL_START + t)
+                    } else {
+                        TAIL_START + t
+                    };
+                }
+            }
+        }
+        // the sliver of the layer outside the next one up
+        if f[i + 1] + (f[i] - f[i + 1]) * open_unit_f64(rng) < density(z) {
+            return z;
+        }
+    }
+}
+
+impl Distribution<f64> for Normal {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.mean + self.std_dev * ziggurat(rng)
+    }
+}
+
+impl Continuous for Normal {
+    fn cdf(&self, x: f64) -> f64 {
+        normal_cdf((x - self.mean) / self.std_dev)
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        self.mean + self.std_dev * StandardNormal.inverse_cdf(u)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::{ks_statistic, moments};
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_tables() {
+        let Tables { x, f } = tables();
+        assert!(x.windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(x[LAYERS], 0.0);
+        // every layer has the same area, including the top one
+        assert!((x[LAYERS - 1] * (1.0 - f[LAYERS - 1]) - LAYER_AREA).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_normal() {
+        for &(mean, std_dev) in &[(0.0, 1.0), (-3.0, 0.5), (100.0, 20.0)] {
+            let (sample_mean, variance) = moments(&Normal::new(mean, std_dev).unwrap(), 50000);
+            assert!((sample_mean - mean).abs() < 0.02 * std_dev);
+            assert!((variance / (std_dev * std_dev) - 1.0).abs() < 0.03);
+        }
+    }
+
+    #[test]
+    fn test_shape() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let n = 400_000;
+        let (mut below_one, mut beyond_two, mut tail) = (0, 0, 0);
+        for _ in 0..n {
+            let z = normal.sample(&mut pcg);
+            below_one += (z < 1.0) as usize;
+            beyond_two += (z.abs() > 2.0) as usize;
+            tail += (z.abs() > TAIL_START) as usize;
+        }
+        let fraction = |count: usize| count as f64 / n as f64;
+        assert!((fraction(below_one) - normal.cdf(1.0)).abs() < 0.003);
+        assert!((fraction(beyond_two) - 2.0 * normal.cdf(-2.0)).abs() < 0.002);
+        // about 103 draws in the tails
+        assert!((60..150).contains(&tail));
+    }
+
+    #[test]
+    fn test_log_normal() {
+        let log_normal = LogNormal::new(1.0, 0.5).unwrap();
+        assert!(ks_statistic(&log_normal, 20000) < 1.95 / 20000f64.sqrt());
+        assert!((log_normal.cdf(1f64.exp()) - 0.5).abs() < 1e-12);
+        assert!((log_normal.inverse_cdf(0.5) - 1f64.exp()).abs() < 1e-9);
+        assert_eq!(log_normal.cdf(-1.0), 0.0);
+
+        let fitted = LogNormal::from_mean_std_dev(100.0, 40.0).unwrap();
+        let (mean, variance) = moments(&fitted, 50000);
+        assert!((mean - 100.0).abs() < 1.0);
+        assert!((variance.sqrt() - 40.0).abs() < 1.5);
+        assert!(LogNormal::new(0.0, 0.0).is_err());
+        assert!(LogNormal::from_mean_std_dev(-1.0, 1.0).is_err());
+        assert!(LogNormal::from_mean_std_dev(1.0, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        let normal = Normal::new(5.0, 2.0).unwrap();
+        assert!((normal.cdf(5.0) - 0.5).abs() < 1e-12);
+        assert!((normal.inverse_cdf(normal.cdf(8.0)) - 8.0).abs() < 1e-6);
+        assert!(Normal::new(0.0, 0.0).is_err());
+        assert!(Normal::new(f64::NAN, 1.0).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use super::special::ln_gamma;
+use super::{open_unit_f64, Continuous, Distribution, ParamError};
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// Below this mean, Poisson draws use Knuth's multiplication method
+const KNUTH_LIMIT: f64 = 10.0;
+
+/// The exponential distribution with rate `lambda`, sampled by
+/// inverting its CDF
+#[derive(Clone, Copy, Debug)]
+pub struct Exponential {
+    lambda: f64,
+}
+
+/// The Poisson distribution with mean `lambda`, sampled with Knuth's
+/// method for small means and Hörmann's PTRS (transformed rejection with
+/// squeeze) above that, in constant expected time
+#[derive(Clone, Copy, Debug)]
+pub struct Poisson {
+    lambda: f64,
+}
+
+impl Exponential {
+    pub fn new(lambda: f64) -> Result<Exponential, ParamError> {
+        if !(lambda > 0.0 && lambda.is_finite()) {
+            return Err(ParamError("exponential rate must be positive and finite"));
+        }
+        Ok(Exponential { lambda })
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+}
+
+impl Distribution<f64> for Exponential {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        -open_unit_f64(rng).ln() / self.lambda
+    }
+}
+
+impl Continuous for Exponential {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= 0.0 {
+            0.0
+        } else {
+            -(-self.lambda * x).exp_m1()
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            0.0
+        } else if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            -(-u).ln_1p() / self.lambda
+        }
+    }
+}
+
+impl Poisson {
+    /// Means up to about 1e15 are accepted; beyond that, draws no longer
+    /// fit the mantissa of an f64.
+    pub fn new(lambda: f64) -> Result<Poisson, ParamError> {
+        if !(lambda > 0.0 && lambda < 1e15) {
+            return Err(ParamError("Poisson mean must be positive and below 1e15"));
+        }
+        Ok(Poisson { lambda })
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+
+    fn knuth<R: RngCore + ?Sized>(&self, rng: &mut R) -> u64 {
+        let limit = (-self.lambda).exp();
+        let mut product = unit_f64(rng);
+        let mut k = 0;
+        while product > limit {
+            product *= unit_f64(rng);
+            k += 1;
+        }
+        k
+    }
+
+    fn ptrs<R: RngCore + ?Sized>(&self, rng: &mut R) -> u64 {
+        let lambda = self.lambda;
+        let ln_lambda = lambda.ln();
+        let b = 0.931 + 2.53 * lambda.sqrt();
+        let a = -0.059 + 0.02483 * b;
+        let inv_alpha = 1.1239 + 1.1328 / (b - 3.4);
+        let v_r = 0.9277 - 3.6224 / (b - 2.0);
+        loop {
+            let u = unit_f64(rng) - 0.5;
+            let v = open_unit_f64(rng);
+            let us = 0.5 - u.abs();
+            let k = ((2.0 * a / us + b) * u + lambda + 0.43).floor();
+            if us >= 0.07 && v <= v_r {
+                return k as u64;
+            }
+            if k < 0.0 || (us < 0.013 && v > us) {
+                continue;
+            }
+            let lhs = v.ln() + inv_alpha.ln() - (a / (us * us) + b).ln();
+            if lhs <= -lambda + k * ln_lambda - ln_gamma(k + 1.0) {
+                return k as u64;
+            }
+        }
+    }
+}
+
+impl Distribution<u64> for Poisson {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> u64 {
+        if self.lambda < KNUTH_LIMIT {
+            self.knuth(rng)
+        } else {
+            self.ptrs(rng)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::moments;
+    use crate::{Pcg, Pcg64};
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_exponential() {
+        for &lambda in &[0.1, 1.0, 25.0] {
+            let (mean, variance) = moments(&Exponential::new(lambda).unwrap(), 20000);
+            assert!((mean * lambda - 1.0).abs() < 0.03);
+            assert!((variance * lambda * lambda - 1.0).abs() < 0.1);
+        }
+        let exponential = Exponential::new(2.0).unwrap();
+        assert!((exponential.cdf(0.5) - (1.0 - (-1.0f64).exp())).abs() < 1e-15);
+        assert!((exponential.inverse_cdf(exponential.cdf(0.7)) - 0.7).abs() < 1e-12);
+        assert_eq!(exponential.cdf(-1.0), 0.0);
+        assert_eq!(exponential.inverse_cdf(1.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_poisson() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        // both sides of the switch between methods
+        for &lambda in &[0.5, 4.0, 9.9, 10.0, 55.0, 1e6] {
+            let poisson = Poisson::new(lambda).unwrap();
+            let n = 20000;
+            let draws: Vec<f64> = (0..n).map(|_| poisson.sample(&mut pcg) as f64).collect();
+            let mean = draws.iter().sum::<f64>() / n as f64;
+            let variance = draws.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+            assert!((mean - lambda).abs() < 0.03 * lambda.max(1.0).sqrt());
+            assert!((variance / lambda - 1.0).abs() < 0.06);
+        }
+    }
+
+    #[test]
+    fn test_poisson_probabilities() {
+        // PTRS pairs consecutive draws, which Pcg's multiplier leaves
+        // slightly correlated, enough to skew single probabilities by
+        // about 0.006; Pcg64 shows the method itself is exact
+        let mut rng = Pcg64::seed_from_u64(0x2545F4914F6CDD1D);
+        let poisson = Poisson::new(20.0).unwrap();
+        let n = 100_000;
+        let mut counts = [0usize; 40];
+        for _ in 0..n {
+            let k = poisson.sample(&mut rng) as usize;
+            if k < counts.len() {
+                counts[k] += 1;
+            }
+        }
+        for (k, &count) in counts.iter().enumerate().take(30).skip(10) {
+            let expected = (-20.0 + k as f64 * 20f64.ln() - ln_gamma(k as f64 + 1.0)).exp();
+            assert!((count as f64 / n as f64 - expected).abs() < 0.003);
+        }
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Exponential::new(0.0).is_err());
+        assert!(Exponential::new(f64::INFINITY).is_err());
+        assert!(Poisson::new(-1.0).is_err());
+        assert!(Poisson::new(f64::NAN).is_err());
+        assert!(Poisson::new(1e16).is_err());
+    }
+}
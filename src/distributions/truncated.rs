@@ -0,0 +1,118 @@
+use super::{Continuous, Distribution, ParamError};
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// Restricts a continuous distribution to the interval [lo, hi].
+///
+/// When at least half of the distribution's mass lies in range, samples
+/// are drawn by rejection, taking two attempts on average. Otherwise they
+/// are drawn by inverting the CDF, which costs one draw however little
+/// mass lies in range.
+#[derive(Clone, Copy, Debug)]
+pub struct Truncated<D> {
+    dist: D,
+    lo: f64,
+    hi: f64,
+    cdf_lo: f64,
+    cdf_hi: f64,
+}
+
+impl<D: Continuous> Truncated<D> {
+    pub fn new(dist: D, lo: f64, hi: f64) -> Result<Truncated<D>, ParamError> {
+        if lo.is_nan() || hi.is_nan() || lo >= hi {
+            return Err(ParamError("truncation bounds must satisfy lo < hi"));
+        }
+        let cdf_lo = dist.cdf(lo);
+        let cdf_hi = dist.cdf(hi);
+        if cdf_lo >= cdf_hi {
+            return Err(ParamError("truncation range has no probability mass"));
+        }
+        Ok(Truncated {
+            dist,
+            lo,
+            hi,
+            cdf_lo,
+            cdf_hi,
+        })
+    }
+
+    /// Whether samples are drawn by inversion rather than rejection.
+    pub fn uses_inversion(&self) -> bool {
+        self.cdf_hi - self.cdf_lo < 0.5
+    }
+}
+
+impl<D: Continuous> Distribution<f64> for Truncated<D> {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        if self.uses_inversion() {
+            self.inverse_cdf(unit_f64(rng))
+        } else {
+            loop {
+                let x = self.dist.sample(rng);
+                if (self.lo..=self.hi).contains(&x) {
+                    return x;
+                }
+            }
+        }
+    }
+}
+
+impl<D: Continuous> Continuous for Truncated<D> {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= self.lo {
+            0.0
+        } else if x >= self.hi {
+            1.0
+        } else {
+            (self.dist.cdf(x) - self.cdf_lo) / (self.cdf_hi - self.cdf_lo)
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        let x = self
+            .dist
+            .inverse_cdf(self.cdf_lo + (self.cdf_hi - self.cdf_lo) * u);
+        x.clamp(self.lo, self.hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::tests::moments;
+    use crate::distributions::Gamma;
+
+    #[test]
+    fn test_inversion() {
+        let truncated = Truncated::new(Gamma::new(1.0, 1.0).unwrap(), 20.0, 21.0).unwrap();
+        assert!(truncated.uses_inversion());
+        let (mean, _) = moments(&truncated, 1000);
+        assert!((20.0..=21.0).contains(&mean));
+        // the memoryless exponential, restricted to [20, 21], has this mean
+        let expected = 20.0 + 1.0 - 1.0 / (1.0f64.exp() - 1.0);
+        assert!((mean - expected).abs() < 0.05);
+        assert_eq!(truncated.cdf(20.0), 0.0);
+        assert_eq!(truncated.cdf(22.0), 1.0);
+    }
+
+    #[test]
+    fn test_rejection() {
+        let gamma = Gamma::new(2.0, 1.0).unwrap();
+        let truncated = Truncated::new(gamma, 1.0, 3.0).unwrap();
+        assert!(!truncated.uses_inversion());
+        let (mean, variance) = moments(&truncated, 2000);
+        assert!((1.0..=3.0).contains(&mean));
+        assert!(variance < 1.0);
+        let expected = (gamma.cdf(2.0) - gamma.cdf(1.0)) / (gamma.cdf(3.0) - gamma.cdf(1.0));
+        assert!((truncated.cdf(2.0) - expected).abs() < 1e-12);
+        assert!((truncated.inverse_cdf(expected) - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let gamma = Gamma::new(1.0, 1.0).unwrap();
+        assert!(Truncated::new(gamma, 2.0, 1.0).is_err());
+        assert!(Truncated::new(gamma, -2.0, -1.0).is_err());
+        assert!(Truncated::new(gamma, f64::NAN, 1.0).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use super::{open_unit_f64, Continuous, Distribution, ParamError};
+use rand_core::RngCore;
+
+/// The Weibull distribution with the given shape and scale, sampled by
+/// inverting its CDF. Shapes below 1 model failure rates that fall with
+/// age, 1 gives the exponential distribution, and above 1 wear-out.
+#[derive(Clone, Copy, Debug)]
+pub struct Weibull {
+    shape: f64,
+    scale: f64,
+}
+
+impl Weibull {
+    pub fn new(shape: f64, scale: f64) -> Result<Weibull, ParamError> {
+        if !(shape > 0.0 && shape.is_finite()) {
+            return Err(ParamError("Weibull shape must be positive and finite"));
+        }
+        if !(scale > 0.0 && scale.is_finite()) {
+            return Err(ParamError("Weibull scale must be positive and finite"));
+        }
+        Ok(Weibull { shape, scale })
+    }
+
+    pub fn shape(&self) -> f64 {
+        self.shape
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+impl Distribution<f64> for Weibull {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.scale * (-open_unit_f64(rng).ln()).powf(1.0 / self.shape)
+    }
+}
+
+impl Continuous for Weibull {
+    fn cdf(&self, x: f64) -> f64 {
+        if x <= 0.0 {
+            0.0
+        } else {
+            -(-(x / self.scale).powf(self.shape)).exp_m1()
+        }
+    }
+
+    fn inverse_cdf(&self, u: f64) -> f64 {
+        if u <= 0.0 {
+            0.0
+        } else if u >= 1.0 {
+            f64::INFINITY
+        } else {
+            self.scale * (-(-u).ln_1p()).powf(1.0 / self.shape)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::special::ln_gamma;
+    use crate::distributions::tests::{ks_statistic, moments};
+
+    #[test]
+    fn test_weibull() {
+        for &(shape, scale) in &[(0.5, 1.0), (1.0, 3.0), (2.0, 10.0)] {
+            let weibull = Weibull::new(shape, scale).unwrap();
+            assert!(ks_statistic(&weibull, 20000) < 1.95 / 20000f64.sqrt());
+            // mean = scale Γ(1 + 1/shape)
+            let (mean, _) = moments(&weibull, 50000);
+            let expected = scale * ln_gamma(1.0 + 1.0 / shape).exp();
+            assert!((mean / expected - 1.0).abs() < 0.03);
+        }
+    }
+
+    #[test]
+    fn test_cdf() {
+        let weibull = Weibull::new(2.0, 3.0).unwrap();
+        assert!((weibull.cdf(3.0) - (1.0 - (-1.0f64).exp())).abs() < 1e-15);
+        assert!((weibull.inverse_cdf(weibull.cdf(4.2)) - 4.2).abs() < 1e-12);
+        assert_eq!(weibull.cdf(-1.0), 0.0);
+        assert_eq!(weibull.inverse_cdf(1.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(Weibull::new(0.0, 1.0).is_err());
+        assert!(Weibull::new(1.0, f64::INFINITY).is_err());
+        assert!(Weibull::new(f64::NAN, 1.0).is_err());
+    }
+}
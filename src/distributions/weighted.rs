@@ -0,0 +1,120 @@
+use super::{Distribution, ParamError};
+use crate::bounded_usize;
+use rand_core::RngCore;
+
+/// Picks indices into a list of weights, each with probability in
+/// proportion to its weight, in constant time per draw by Walker's alias
+/// method (in Vose's construction). Building the table takes linear time.
+#[derive(Clone, Debug)]
+pub struct WeightedIndex {
+    /// For each column, next_u32() below this keeps the column's own index,
+    /// up to 2^32 for columns that are never aliased
+    thresholds: Vec<u64>,
+    aliases: Vec<usize>,
+}
+
+impl WeightedIndex {
+    /// Weights must be finite and non-negative, with a positive total.
+    pub fn new(weights: &[f64]) -> Result<WeightedIndex, ParamError> {
+        if weights.iter().any(|&w| w.is_nan() || w < 0.0) {
+            return Err(ParamError("weights must not be negative"));
+        }
+        let total: f64 = weights.iter().sum();
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(ParamError("weights must have a positive, finite total"));
+        }
+        let n = weights.len();
+        // each weight as a multiple of the mean, so a full column is 1
+        let mut scaled: Vec<f64> = weights.iter().map(|&w| w * n as f64 / total).collect();
+        let mut aliases: Vec<usize> = (0..n).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            aliases[s] = l;
+            scaled[l] -= 1.0 - scaled[s];
+            if scaled[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // whatever is left over is full, up to rounding
+        for i in small.into_iter().chain(large) {
+            scaled[i] = 1.0;
+        }
+        let thresholds = scaled
+            .iter()
+            .map(|&p| (p * 4294967296.0).round() as u64)
+            .collect();
+        Ok(WeightedIndex {
+            thresholds,
+            aliases,
+        })
+    }
+
+    /// The number of weights, and so of possible indices.
+    pub fn len(&self) -> usize {
+        self.thresholds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.thresholds.is_empty()
+    }
+}
+
+impl Distribution<usize> for WeightedIndex {
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> usize {
+        let column = bounded_usize(rng, self.len());
+        if (rng.next_u32() as u64) < self.thresholds[column] {
+            column
+        } else {
+            self.aliases[column]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg32;
+    use rand_core::SeedableRng;
+
+    fn frequencies<R: RngCore>(rng: &mut R, weights: &[f64], n: usize) -> Vec<f64> {
+        let index = WeightedIndex::new(weights).unwrap();
+        let mut counts = vec![0; weights.len()];
+        for _ in 0..n {
+            counts[index.sample(rng)] += 1;
+        }
+        counts.iter().map(|&c| c as f64 / n as f64).collect()
+    }
+
+    #[test]
+    fn test_weighted_index() {
+        // with Pcg32, since Pcg's correlated pairs of draws skew the coin
+        // toss between a column and its alias
+        let mut rng = Pcg32::seed_from_u64(0x2545F4914F6CDD1D);
+        let weights = [1.0, 0.0, 5.0, 2.5, 0.5, 1.0];
+        let total: f64 = weights.iter().sum();
+        for (f, &w) in frequencies(&mut rng, &weights, 200_000)
+            .iter()
+            .zip(&weights)
+        {
+            assert!((f - w / total).abs() < 0.004);
+        }
+        assert_eq!(
+            frequencies(&mut rng, &[0.0, 3.0, 0.0], 1000),
+            vec![0.0, 1.0, 0.0]
+        );
+        assert_eq!(frequencies(&mut rng, &[2.0], 10), vec![1.0]);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        assert!(WeightedIndex::new(&[]).is_err());
+        assert!(WeightedIndex::new(&[0.0, 0.0]).is_err());
+        assert!(WeightedIndex::new(&[1.0, -1.0]).is_err());
+        assert!(WeightedIndex::new(&[1.0, f64::NAN]).is_err());
+        assert!(WeightedIndex::new(&[1.0, f64::INFINITY]).is_err());
+        assert!(WeightedIndex::new(&[f64::MAX, f64::MAX]).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+/*! Noise for audio and other signal processing: dither and random
+ *  impulses, so that a pipeline can draw all of its noise from one
+ *  seeded generator.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::dsp::{dither_tpdf, impulse_train};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let lsb = 1.0 / 32768.0; // one step of 16-bit audio
+ *
+ *  let mut buffer = vec![0.25f32; 512];
+ *  dither_tpdf(&mut pcg, &mut buffer, lsb);
+ *  assert!(buffer.iter().all(|&x| (x - 0.25).abs() <= lsb));
+ *
+ *  let mut clicks = vec![0.0f32; 48000];
+ *  impulse_train(&mut pcg, &mut clicks, 0.001, 0.8);
+ *  ```
+ */
+use crate::distributions::{open_unit_f64, standard_normal};
+use rand_core::RngCore;
+
+/// Adds triangular-PDF dither of up to one `lsb` either way to each
+/// sample, the sum of two uniform draws, which decorrelates quantization
+/// error from the signal before reducing bit depth.
+pub fn dither_tpdf<R: RngCore + ?Sized>(rng: &mut R, samples: &mut [f32], lsb: f32) {
+    for x in samples {
+        // both uniforms come from one output, whose halves are less
+        // correlated than consecutive outputs
+        let bits = rng.next_u32();
+        let (a, b) = ((bits >> 16) as f32, (bits & 0xFFFF) as f32);
+        *x += (a - b) * (lsb / 65536.0);
+    }
+}
+
+/// Adds Gaussian dither with standard deviation `sigma` to each sample.
+pub fn dither_gaussian<R: RngCore + ?Sized>(rng: &mut R, samples: &mut [f32], sigma: f32) {
+    for x in samples {
+        *x += standard_normal(rng) as f32 * sigma;
+    }
+}
+
+/// Adds impulses to a buffer: each sample independently receives an
+/// impulse of ±`amplitude`, with a random sign, with probability
+/// `density`. The gaps between impulses are drawn directly, so sparse
+/// trains cost time in proportion to their impulses, not their length.
+pub fn impulse_train<R: RngCore + ?Sized>(
+    rng: &mut R,
+    samples: &mut [f32],
+    density: f64,
+    amplitude: f32,
+) {
+    assert!(
+        (0.0..=1.0).contains(&density),
+        "density must be a probability"
+    );
+    if density == 0.0 {
+        return;
+    }
+    let ln_miss = (-density).ln_1p();
+    let mut i = 0;
+    loop {
+        // geometric gap: the number of samples before the next impulse
+        let gap = if density == 1.0 {
+            0.0
+        } else {
+            (open_unit_f64(rng).ln() / ln_miss).floor()
+        };
+        if gap >= (samples.len() - i) as f64 {
+            return;
+        }
+        i += gap as usize;
+        let sign = if rng.next_u32() & 1 == 0 { 1.0 } else { -1.0 };
+        samples[i] += sign * amplitude;
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_dither_tpdf() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut buffer = vec![0.0f32; 100000];
+        dither_tpdf(&mut pcg, &mut buffer, 2.0);
+        assert!(buffer.iter().all(|x| x.abs() <= 2.0));
+        // the triangular distribution on [-2, 2] has variance 4/6 and puts
+        // a quarter of its mass beyond ±1
+        let variance = buffer.iter().map(|x| (x * x) as f64).sum::<f64>() / 100000.0;
+        assert!((variance - 4.0 / 6.0).abs() < 0.02);
+        let tails = buffer.iter().filter(|x| x.abs() > 1.0).count();
+        assert!((24000..26000).contains(&tails));
+    }
+
+    #[test]
+    fn test_dither_gaussian() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut buffer = vec![1.0f32; 100000];
+        dither_gaussian(&mut pcg, &mut buffer, 0.5);
+        let mean = buffer.iter().map(|&x| x as f64).sum::<f64>() / 100000.0;
+        let variance = buffer
+            .iter()
+            .map(|&x| (x as f64 - mean).powi(2))
+            .sum::<f64>()
+            / 100000.0;
+        assert!((mean - 1.0).abs() < 0.01);
+        assert!((variance - 0.25).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_impulse_train() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut buffer = vec![0.0f32; 1000000];
+        impulse_train(&mut pcg, &mut buffer, 0.001, 0.5);
+        let impulses: Vec<f32> = buffer.iter().cloned().filter(|&x| x != 0.0).collect();
+        assert!((900..1100).contains(&impulses.len()));
+        assert!(impulses.iter().all(|x| x.abs() == 0.5));
+        let positive = impulses.iter().filter(|&&x| x > 0.0).count();
+        assert!(positive > impulses.len() / 3 && positive < 2 * impulses.len() / 3);
+
+        let mut buffer = vec![0.0f32; 100];
+        impulse_train(&mut pcg, &mut buffer, 1.0, 1.0);
+        assert!(buffer.iter().all(|x| x.abs() == 1.0));
+        impulse_train(&mut pcg, &mut [], 0.5, 1.0);
+    }
+}
@@ -0,0 +1,364 @@
+/*! PCG-DXSM 128/64 (LCG), the generator behind NumPy's `PCG64DXSM`.
+ *
+ *  The state advances by a 64-bit "cheap" multiplier, and the DXSM
+ *  output permutation (double xorshift multiply) hides the weaker
+ *  multiplier's structure better than XSL-RR does when many streams run
+ *  side by side. Seeding with `seed_from_u64` follows NumPy's
+ *  `SeedSequence` and `pcg64_set_seed`, so `Pcg64Dxsm::seed_from_u64(s)`
+ *  produces the same 64-bit outputs as `PCG64DXSM(s).random_raw()`. A
+ *  generator can also be rebuilt from the `state` and `inc` of a NumPy bit
+ *  generator's `state` dictionary with `from_state`.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg64Dxsm;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = Pcg64Dxsm::seed_from_u64(12345);
+ *  let x = pcg.next_u64();
+ *
+ *  pcg.skip(-1);
+ *  assert_eq!(pcg.next_u64(), x);
+ *  ```
+ */
+use rand_core::{impls, Error, RngCore, SeedableRng};
+use std::num::Wrapping;
+
+/// The multiplier NumPy steps the state by, which also mixes the output
+const CHEAP_MULTIPLIER: u64 = 0xDA942042E4DD58B5;
+/// The full 128-bit multiplier, used only while seeding, as NumPy does
+const SEED_MULTIPLIER: u128 = 0x2360ED051FC65DA44385DF649FCCF645;
+
+/// A seed: the initial state then the stream, both little-endian
+#[derive(Default)]
+pub struct Pcg64DxsmSeed(pub [u8; 32]);
+
+impl AsMut<[u8]> for Pcg64DxsmSeed {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pcg64Dxsm {
+    state: u128,
+    increment: u128,
+}
+
+impl Pcg64Dxsm {
+    /// Creates a generator on the given stream, seeded as NumPy seeds it
+    /// from the words of its seed sequence.
+    pub fn new(seed: u128, stream: u128) -> Pcg64Dxsm {
+        let increment = (stream << 1) | 1;
+        let step = |state: u128| state.wrapping_mul(SEED_MULTIPLIER).wrapping_add(increment);
+        Pcg64Dxsm {
+            state: step(step(0).wrapping_add(seed)),
+            increment,
+        }
+    }
+
+    /// Rebuilds a generator from the `state` and `inc` values of a NumPy
+    /// `PCG64DXSM` bit generator's state dictionary.
+    pub fn from_state(state: u128, inc: u128) -> Pcg64Dxsm {
+        Pcg64Dxsm {
+            state,
+            increment: inc | 1,
+        }
+    }
+
+    /// Advances the state by n steps, as if calling next_u64() n times,
+    /// or rewinds it for negative n, in O(log n) time.
+    pub fn skip(&mut self, n: i64) {
+        // Brown's algorithm, as for Pcg32; the period is 2^128, so a
+        // negative n is a long way forward
+        let mut delta = n as u128;
+        let (mut mult, mut plus) = (Wrapping(CHEAP_MULTIPLIER as u128), Wrapping(self.increment));
+        let (mut acc_mult, mut acc_plus) = (Wrapping(1u128), Wrapping(0u128));
+        while delta > 0 {
+            if delta & 1 == 1 {
+                acc_mult *= mult;
+                acc_plus = acc_plus * mult + plus;
+            }
+            plus = (mult + Wrapping(1)) * plus;
+            mult *= mult;
+            delta >>= 1;
+        }
+        self.state = (acc_mult * Wrapping(self.state) + acc_plus).0;
+    }
+}
+
+impl RngCore for Pcg64Dxsm {
+    /// Generate a random u32 from the low half of a u64, advancing the
+    /// state one step. NumPy instead keeps the high half for its next
+    /// 32-bit draw, so only 64-bit outputs line up.
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    /// Generate a random u64, advancing the state one step.
+    fn next_u64(&mut self) -> u64 {
+        let mut hi = (self.state >> 64) as u64;
+        let lo = self.state as u64 | 1;
+        hi ^= hi >> 32;
+        hi = hi.wrapping_mul(CHEAP_MULTIPLIER);
+        hi ^= hi >> 48;
+        hi = hi.wrapping_mul(lo);
+        self.state = self
+            .state
+            .wrapping_mul(CHEAP_MULTIPLIER as u128)
+            .wrapping_add(self.increment);
+        hi
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Pcg64Dxsm {
+    type Seed = Pcg64DxsmSeed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 16];
+        let mut stream = [0; 16];
+        state.copy_from_slice(&seed.0[..16]);
+        stream.copy_from_slice(&seed.0[16..]);
+        Pcg64Dxsm::new(u128::from_le_bytes(state), u128::from_le_bytes(stream))
+    }
+
+    /// Seeds the generator as NumPy's `PCG64DXSM(seed)` does.
+    fn seed_from_u64(seed: u64) -> Self {
+        let words = seed_sequence(seed);
+        Pcg64Dxsm::new(
+            (words[0] as u128) << 64 | words[1] as u128,
+            (words[2] as u128) << 64 | words[3] as u128,
+        )
+    }
+}
+
+/// The four u64 words NumPy's `SeedSequence(seed).generate_state(4,
+/// np.uint64)` produces, taking the seed as NumPy coerces an integer:
+/// little-endian u32 words, dropping a zero high word.
+fn seed_sequence(seed: u64) -> [u64; 4] {
+    let entropy = if seed >> 32 == 0 {
+        vec![seed as u32]
+    } else {
+        vec![seed as u32, (seed >> 32) as u32]
+    };
+    let words = generate_state(&entropy, 8);
+    let mut out = [0u64; 4];
+    for (i, word) in out.iter_mut().enumerate() {
+        *word = words[2 * i] as u64 | (words[2 * i + 1] as u64) << 32;
+    }
+    out
+}
+
+/// The `n_words` u32 words NumPy's `SeedSequence(entropy).generate_state`
+/// produces, from its default pool of four u32 words.
+fn generate_state(entropy: &[u32], n_words: usize) -> Vec<u32> {
+    const POOL_SIZE: usize = 4;
+    const INIT_A: u32 = 0x43B0D7E5;
+    const MULT_A: u32 = 0x931E8875;
+    const INIT_B: u32 = 0x8B51F9DD;
+    const MULT_B: u32 = 0x58F38DED;
+    const MIX_MULT_L: u32 = 0xCA01F9DD;
+    const MIX_MULT_R: u32 = 0x4973F715;
+
+    let mut hash_const = INIT_A;
+    let mut hashmix = |value: u32| {
+        let mut value = value ^ hash_const;
+        hash_const = hash_const.wrapping_mul(MULT_A);
+        value = value.wrapping_mul(hash_const);
+        value ^ (value >> 16)
+    };
+    let mix = |x: u32, y: u32| {
+        let result = MIX_MULT_L
+            .wrapping_mul(x)
+            .wrapping_sub(MIX_MULT_R.wrapping_mul(y));
+        result ^ (result >> 16)
+    };
+
+    let mut pool = [0u32; POOL_SIZE];
+    for (i, word) in pool.iter_mut().enumerate() {
+        *word = hashmix(entropy.get(i).cloned().unwrap_or(0));
+    }
+    for src in 0..POOL_SIZE {
+        for dst in 0..POOL_SIZE {
+            if src != dst {
+                let hashed = hashmix(pool[src]);
+                pool[dst] = mix(pool[dst], hashed);
+            }
+        }
+    }
+    // entropy beyond the pool is folded into every word of it
+    for &word in entropy.iter().skip(POOL_SIZE) {
+        for dst in pool.iter_mut() {
+            let hashed = hashmix(word);
+            *dst = mix(*dst, hashed);
+        }
+    }
+
+    let mut hash_const = INIT_B;
+    (0..n_words)
+        .map(|i| {
+            let mut value = pool[i % POOL_SIZE] ^ hash_const;
+            hash_const = hash_const.wrapping_mul(MULT_B);
+            value = value.wrapping_mul(hash_const);
+            value ^ (value >> 16)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_u64() {
+        let mut pcg = Pcg64Dxsm::from_state(rand::random::<u128>(), rand::random::<u128>());
+        let (state, increment) = (pcg.state, pcg.increment);
+        let mut hi = (state >> 64) as u64;
+        hi ^= hi >> 32;
+        hi = hi.wrapping_mul(CHEAP_MULTIPLIER);
+        hi ^= hi >> 48;
+        hi = hi.wrapping_mul(state as u64 | 1);
+
+        assert_eq!(pcg.next_u64(), hi);
+        let stepped = state
+            .wrapping_mul(CHEAP_MULTIPLIER as u128)
+            .wrapping_add(increment);
+        assert_eq!(pcg.state, stepped);
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut pcg = Pcg64Dxsm::new(rand::random::<u128>(), rand::random::<u128>());
+        let start = pcg.clone();
+        let steps = rand::random::<u16>();
+        let mut stepped = pcg.clone();
+        for _ in 0..steps {
+            stepped.next_u64();
+        }
+        pcg.skip(steps as i64);
+        assert_eq!(pcg.state, stepped.state);
+        pcg.skip(-(steps as i64));
+        assert_eq!(pcg.state, start.state);
+    }
+
+    #[test]
+    fn test_seeding() {
+        let seed = rand::random::<u64>();
+        let mut a = Pcg64Dxsm::seed_from_u64(seed);
+        let mut b = Pcg64Dxsm::seed_from_u64(seed ^ 1);
+        assert_ne!(a.next_u64(), b.next_u64());
+        assert_eq!(seed_sequence(seed), seed_sequence(seed));
+        // small seeds still spread across all four words
+        assert!(seed_sequence(0).iter().all(|&w| w != 0));
+
+        let mut bytes = [0; 32];
+        bytes[..16].copy_from_slice(&42u128.to_le_bytes());
+        bytes[16..].copy_from_slice(&54u128.to_le_bytes());
+        let pcg = Pcg64Dxsm::from_seed(Pcg64DxsmSeed(bytes));
+        assert_eq!(pcg.increment, 109);
+        assert_eq!(pcg.state, Pcg64Dxsm::new(42, 54).state);
+    }
+
+    #[test]
+    fn test_generate_state() {
+        // the reference output of the C++ seed_seq_fe128 that NumPy's
+        // test_seed_sequence.py checks SeedSequence against
+        let entropy = [3735928559, 195939070, 229505742, 305419896];
+        assert_eq!(
+            generate_state(&entropy, 4),
+            [3914649087, 576849849, 3593928901, 2229911004]
+        );
+        // words are drawn from the same pool, so a longer request extends
+        // a shorter one
+        assert_eq!(
+            generate_state(&entropy, 8)[..4],
+            generate_state(&entropy, 4)[..]
+        );
+        let seed = 0xDEADBEEF12345678u64;
+        let words = generate_state(&[0x12345678, 0xDEADBEEF], 8);
+        assert_eq!(
+            seed_sequence(seed)[1],
+            words[2] as u64 | (words[3] as u64) << 32
+        );
+        assert_eq!(seed_sequence(5), seed_sequence(5));
+    }
+
+    #[test]
+    fn test_numpy_outputs() {
+        // the seeds of NumPy's pcg64dxsm-testset-1.csv and -2.csv, the
+        // first of which opens with 0xdf1ddcf1e22521fe
+        let testsets: [(u64, [u64; 10]); 2] = [
+            (
+                0xdeadbeaf,
+                [
+                    0xdf1ddcf1e22521fe,
+                    0xc71b2f9c706cf151,
+                    0x6922a8cc24ad96b2,
+                    0x82738c549beccc30,
+                    0x5e8415cdb1f17580,
+                    0x064c54ad0c09cb43,
+                    0x361a17a607dce278,
+                    0x4346f6afb7acad68,
+                    0x6e9f14d4f6398d6b,
+                    0xf818d4343f8ed822,
+                ],
+            ),
+            (
+                0,
+                [
+                    0xd97e4a147f788a70,
+                    0x8dfa7bce56e3a253,
+                    0x13556ed9f53d3c10,
+                    0x55dbf1c241341e98,
+                    0xa2cd98f722eb0e0a,
+                    0x083dfc407203ade8,
+                    0xeaa083df518f030d,
+                    0x44968c87e432852b,
+                    0x573107b9cb8d9ecc,
+                    0x9eedd1da50b9daca,
+                ],
+            ),
+        ];
+        for (seed, expected) in testsets.iter() {
+            let mut pcg = Pcg64Dxsm::seed_from_u64(*seed);
+            let outputs: Vec<u64> = (0..10).map(|_| pcg.next_u64()).collect();
+            assert_eq!(outputs, expected);
+        }
+
+        // the state dictionary of PCG64DXSM(0xdeadbeaf)
+        let mut pcg = Pcg64Dxsm::from_state(
+            293423549397873319808950108715539645841,
+            147554048582284838184775483557818863797,
+        );
+        assert_eq!(pcg.next_u64(), 0xdf1ddcf1e22521fe);
+    }
+
+    #[test]
+    fn test_from_state() {
+        // pcg-cpp's cm_setseq_dxsm_128_64(42, 54), which seeds by stepping
+        // with the cheap multiplier rather than the 128-bit one
+        let increment = 109;
+        let state = (42 + increment) * CHEAP_MULTIPLIER as u128 + increment;
+        let mut pcg = Pcg64Dxsm::from_state(state, increment);
+        let outputs: Vec<u64> = (0..6).map(|_| pcg.next_u64()).collect();
+        assert_eq!(
+            outputs,
+            [
+                17331114245835578256,
+                10267467544499227306,
+                9726600296081716989,
+                10165951391103677450,
+                12131334649314727261,
+                10134094537930450875,
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+/*! Exact probabilities of combined independent events, such as "at
+ *  least one of these three procs fires", so that gameplay code can
+ *  display and sample them without hand-written probability math.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::distributions::Distribution;
+ *  # use pcg::events::{at_least_one_of, exactly_k_of, Event};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let crits = [Event::new(0.1).unwrap(), Event::new(0.2).unwrap()];
+ *
+ *  let any_crit = at_least_one_of(&crits);
+ *  assert!((any_crit.probability() - 0.28).abs() < 1e-12);
+ *  assert!((exactly_k_of(&crits, 2).probability() - 0.02).abs() < 1e-12);
+ *
+ *  let critted: bool = any_crit.sample(&mut pcg);
+ *  ```
+ */
+use crate::distributions::{Distribution, ParamError};
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// An event occurring with a fixed probability, independently of others
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    probability: f64,
+}
+
+impl Event {
+    /// An event with the given probability, which must lie in [0, 1].
+    pub fn new(probability: f64) -> Result<Event, ParamError> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(ParamError("event probability must lie in [0, 1]"));
+        }
+        Ok(Event { probability })
+    }
+
+    pub fn probability(&self) -> f64 {
+        self.probability
+    }
+
+    /// The event that this one and an independent `other` both occur.
+    pub fn and(&self, other: Event) -> Event {
+        all_of(&[*self, other])
+    }
+
+    /// The event that this one, an independent `other`, or both occur.
+    pub fn or(&self, other: Event) -> Event {
+        at_least_one_of(&[*self, other])
+    }
+
+    /// The event that this one does not occur.
+    pub fn not(&self) -> Event {
+        Event {
+            probability: 1.0 - self.probability,
+        }
+    }
+}
+
+impl Distribution<bool> for Event {
+    /// Whether the event occurs, using one draw.
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> bool {
+        unit_f64(rng) < self.probability
+    }
+}
+
+/// The event that every one of `events` occurs. With no events, it is
+/// certain.
+pub fn all_of(events: &[Event]) -> Event {
+    Event {
+        probability: events.iter().map(|e| e.probability).product(),
+    }
+}
+
+/// The event that one or more of `events` occur. With no events, it is
+/// impossible.
+pub fn at_least_one_of(events: &[Event]) -> Event {
+    // 1 - Π(1 - p), in a form that stays accurate for tiny probabilities
+    let log_none: f64 = events.iter().map(|e| (-e.probability).ln_1p()).sum();
+    Event {
+        probability: -log_none.exp_m1(),
+    }
+}
+
+/// The event that exactly k of `events` occur.
+pub fn exactly_k_of(events: &[Event], k: usize) -> Event {
+    if k > events.len() {
+        return Event { probability: 0.0 };
+    }
+    // ways[j]: the probability that exactly j of the events so far occur,
+    // tracking only j <= k
+    let mut ways = vec![0.0; k + 1];
+    ways[0] = 1.0;
+    for event in events {
+        let p = event.probability;
+        for j in (0..=k).rev() {
+            let occurred = if j > 0 { ways[j - 1] * p } else { 0.0 };
+            ways[j] = ways[j] * (1.0 - p) + occurred;
+        }
+    }
+    Event {
+        probability: ways[k].clamp(0.0, 1.0),
+    }
+}
+
+/// Samples each event in turn, with one draw apiece, reporting which
+/// occurred. Combining the outcomes agrees in distribution with sampling
+/// the combined event directly.
+pub fn sample_each<R: RngCore + ?Sized>(rng: &mut R, events: &[Event]) -> Vec<bool> {
+    events.iter().map(|e| e.sample(rng)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    fn events(probabilities: &[f64]) -> Vec<Event> {
+        probabilities
+            .iter()
+            .map(|&p| Event::new(p).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Event::new(0.0).is_ok());
+        assert!(Event::new(1.0).is_ok());
+        assert!(Event::new(-0.1).is_err());
+        assert!(Event::new(1.5).is_err());
+        assert!(Event::new(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_combinators() {
+        let e = events(&[0.5, 0.25, 0.1]);
+        assert!((all_of(&e).probability() - 0.0125).abs() < 1e-12);
+        assert!((at_least_one_of(&e).probability() - (1.0 - 0.5 * 0.75 * 0.9)).abs() < 1e-12);
+        assert!((e[0].and(e[1]).probability() - 0.125).abs() < 1e-12);
+        assert!((e[0].or(e[1]).probability() - 0.625).abs() < 1e-12);
+        assert!((e[2].not().probability() - 0.9).abs() < 1e-12);
+
+        let total: f64 = (0..=3).map(|k| exactly_k_of(&e, k).probability()).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+        let none = exactly_k_of(&e, 0).probability();
+        assert!((none - at_least_one_of(&e).not().probability()).abs() < 1e-12);
+        assert!((exactly_k_of(&e, 3).probability() - all_of(&e).probability()).abs() < 1e-12);
+        assert_eq!(exactly_k_of(&e, 4).probability(), 0.0);
+
+        assert_eq!(all_of(&[]).probability(), 1.0);
+        assert_eq!(at_least_one_of(&[]).probability(), 0.0);
+        let tiny = at_least_one_of(&events(&[1e-20, 1e-20])).probability();
+        assert!((tiny - 2e-20).abs() < 1e-30);
+    }
+
+    #[test]
+    fn test_sampling_agrees() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let e = events(&[0.3, 0.6, 0.2]);
+        let two = exactly_k_of(&e, 2);
+        let n = 100000;
+        let direct = (0..n).filter(|_| two.sample(&mut pcg)).count();
+        let combined = (0..n)
+            .filter(|_| sample_each(&mut pcg, &e).iter().filter(|&&x| x).count() == 2)
+            .count();
+        let expected = two.probability() * n as f64;
+        assert!((direct as f64 - expected).abs() < 0.03 * expected);
+        assert!((combined as f64 - expected).abs() < 0.03 * expected);
+
+        let certain = Event::new(1.0).unwrap();
+        let impossible = Event::new(0.0).unwrap();
+        for _ in 0..100 {
+            assert!(certain.sample(&mut pcg));
+            assert!(!impossible.sample(&mut pcg));
+        }
+    }
+}
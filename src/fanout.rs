@@ -0,0 +1,204 @@
+/*! A fixed set of derived streams whose consumption is kept in step.
+ *
+ *  Subsystems that run in lockstep, such as the physics and the AI of a
+ *  simulation, each get their own stream so one cannot shift the other's
+ *  draws. A `Fanout` owns those streams and checks that each has consumed
+ *  randomness at its configured rate relative to the others, which
+ *  catches a subsystem that quietly starts drawing more than it used to
+ *  and would otherwise only show up as a replay diverging much later.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::fanout::Fanout;
+ *  # use rand_core::RngCore;
+ *  // stream 1 draws twice per tick, stream 0 once
+ *  let mut fanout = Fanout::new(12345, 2).with_ratios(vec![1.0, 2.0]);
+ *
+ *  for _ in 0..100 {
+ *      fanout.next_from(0);
+ *      fanout.stream(1).next_u32();
+ *      fanout.next_from(1);
+ *      fanout.assert_balanced();
+ *  }
+ *
+ *  fanout.next_from(0);
+ *  assert!(fanout.check_balance().is_err());
+ *  ```
+ */
+use crate::Pcg;
+use rand_core::{RngCore, SeedableRng};
+use std::error::Error;
+use std::fmt;
+
+/// Owns `k` streams derived from one seed and tracks their draws
+#[derive(Clone)]
+pub struct Fanout {
+    streams: Vec<Pcg>,
+    ratios: Vec<f64>,
+    tolerance: f64,
+}
+
+/// The pair of streams furthest out of step, with their draw counts
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Imbalance {
+    pub ahead: usize,
+    pub ahead_draws: u64,
+    pub behind: usize,
+    pub behind_draws: u64,
+}
+
+impl fmt::Display for Imbalance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "stream {} has made {} draws, out of step with stream {} at {}",
+            self.ahead, self.ahead_draws, self.behind, self.behind_draws
+        )
+    }
+}
+
+impl Error for Imbalance {}
+
+impl Fanout {
+    /// Derives `k` streams from `seed`. Stream i is the same generator as
+    /// `Pcg::seed_from_u64(seed).substream(i)`. Streams start out expected
+    /// to draw in exact lockstep.
+    pub fn new(seed: u64, k: usize) -> Fanout {
+        assert!(k > 0, "a fanout needs at least one stream");
+        let root = Pcg::seed_from_u64(seed);
+        Fanout {
+            streams: (0..k as u64).map(|i| root.substream(i)).collect(),
+            ratios: vec![1.0; k],
+            tolerance: 0.0,
+        }
+    }
+
+    /// Sets the relative rate at which each stream is expected to draw.
+    pub fn with_ratios(mut self, ratios: Vec<f64>) -> Fanout {
+        assert_eq!(ratios.len(), self.streams.len(), "one ratio per stream");
+        assert!(
+            ratios.iter().all(|&r| r > 0.0 && r.is_finite()),
+            "ratios must be positive and finite"
+        );
+        self.ratios = ratios;
+        self
+    }
+
+    /// Sets how far apart, in draws scaled by their ratios, two streams
+    /// may drift before they count as out of step.
+    pub fn with_tolerance(mut self, tolerance: f64) -> Fanout {
+        assert!(tolerance >= 0.0, "tolerance must not be negative");
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.streams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty()
+    }
+
+    /// Draws the next u32 from stream `i`.
+    pub fn next_from(&mut self, i: usize) -> u32 {
+        self.streams[i].next_u32()
+    }
+
+    /// Borrows stream `i`, for drawing anything other than a u32. Its
+    /// draws count towards the balance all the same.
+    pub fn stream(&mut self, i: usize) -> &mut Pcg {
+        &mut self.streams[i]
+    }
+
+    /// The number of steps stream `i` has taken, as Pcg::draw_count counts.
+    pub fn draws(&self, i: usize) -> u64 {
+        self.streams[i].draw_count()
+    }
+
+    /// Checks that every stream has drawn at its configured rate, to within
+    /// the tolerance, and reports the worst pair otherwise.
+    pub fn check_balance(&self) -> Result<(), Imbalance> {
+        let scaled = |i: usize| self.draws(i) as f64 / self.ratios[i];
+        let by_scaled = |&a: &usize, &b: &usize| scaled(a).total_cmp(&scaled(b));
+        let ahead = (0..self.len()).max_by(by_scaled).unwrap();
+        let behind = (0..self.len()).min_by(by_scaled).unwrap();
+        if scaled(ahead) - scaled(behind) <= self.tolerance {
+            Ok(())
+        } else {
+            Err(Imbalance {
+                ahead,
+                ahead_draws: self.draws(ahead),
+                behind,
+                behind_draws: self.draws(behind),
+            })
+        }
+    }
+
+    /// Panics with the offending streams unless check_balance passes.
+    pub fn assert_balanced(&self) {
+        if let Err(imbalance) = self.check_balance() {
+            panic!("{}", imbalance);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams() {
+        let mut fanout = Fanout::new(42, 3);
+        let root = Pcg::seed_from_u64(42);
+        for i in 0..3 {
+            let mut expected = root.substream(i as u64);
+            assert_eq!(fanout.next_from(i), expected.next_u32());
+        }
+        assert_ne!(fanout.next_from(0), fanout.next_from(1));
+        assert_eq!(fanout.len(), 3);
+    }
+
+    #[test]
+    fn test_balance() {
+        let mut fanout = Fanout::new(42, 3);
+        fanout.assert_balanced();
+        fanout.next_from(0);
+        fanout.next_from(0);
+        fanout.next_from(1);
+        let imbalance = fanout.check_balance().unwrap_err();
+        assert_eq!((imbalance.ahead, imbalance.behind), (0, 2));
+        assert_eq!((imbalance.ahead_draws, imbalance.behind_draws), (2, 0));
+        // a u64 is two steps
+        fanout.stream(2).next_u64();
+        assert_eq!(fanout.check_balance().unwrap_err().behind, 1);
+        fanout.next_from(1);
+        fanout.assert_balanced();
+    }
+
+    #[test]
+    fn test_ratios_and_tolerance() {
+        let mut fanout = Fanout::new(7, 2)
+            .with_ratios(vec![1.0, 3.0])
+            .with_tolerance(1.0);
+        for _ in 0..10 {
+            fanout.next_from(0);
+            for _ in 0..3 {
+                fanout.next_from(1);
+            }
+            fanout.assert_balanced();
+        }
+        fanout.next_from(0);
+        fanout.assert_balanced();
+        fanout.next_from(0);
+        assert_eq!(fanout.check_balance().unwrap_err().ahead, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "stream 1 has made 1 draws")]
+    fn test_assert_balanced() {
+        let mut fanout = Fanout::new(7, 2);
+        fanout.next_from(1);
+        fanout.assert_balanced();
+    }
+}
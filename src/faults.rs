@@ -0,0 +1,312 @@
+/*! Seeded fault timelines for partition testing.
+ *
+ *  A `FailureSchedule` describes a cluster and how it should misbehave:
+ *  how often faults strike, how long outages last, how many nodes may be
+ *  down or cut off at once, and how long a node must have been up before
+ *  it fails again. `generate` turns it into a timeline of crashes,
+ *  recoveries, partitions and heals, the same for the same seed, so a
+ *  failing test run can be replayed exactly. Every fault is undone by the
+ *  end of the timeline, leaving the cluster whole for final checks.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::faults::{Fault, FailureSchedule};
+ *  # use std::time::Duration;
+ *  let schedule = FailureSchedule::new(5, Duration::from_secs(600))
+ *      .with_max_concurrent(2)
+ *      .with_min_uptime(Duration::from_secs(30))
+ *      .with_partition_probability(0.3);
+ *
+ *  for event in schedule.generate(12345) {
+ *      match event.fault {
+ *          Fault::Crash(node) => println!("{:?}: kill node {}", event.at, node),
+ *          Fault::Recover(node) => println!("{:?}: restart node {}", event.at, node),
+ *          Fault::Partition(nodes) => println!("{:?}: isolate {:?}", event.at, nodes),
+ *          Fault::Heal => println!("{:?}: heal the network", event.at),
+ *      }
+ *  }
+ *  ```
+ */
+use crate::distributions::open_unit_f64;
+use crate::permutation::shuffle;
+use crate::{bounded_usize, unit_f64, Pcg};
+use rand_core::{RngCore, SeedableRng};
+use std::time::Duration;
+
+/// A change to the cluster
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    Crash(usize),
+    Recover(usize),
+    /// Cuts the listed nodes, always a minority, off from the rest
+    Partition(Vec<usize>),
+    /// Ends the current partition
+    Heal,
+}
+
+/// A fault and when it happens, from the start of the test
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FaultEvent {
+    pub at: Duration,
+    pub fault: Fault,
+}
+
+/// The constraints a fault timeline is generated within
+#[derive(Clone, Debug)]
+pub struct FailureSchedule {
+    nodes: usize,
+    length: u64,
+    mean_gap: f64,
+    mean_outage: f64,
+    max_concurrent: usize,
+    min_uptime: u64,
+    partition_probability: f64,
+}
+
+impl FailureSchedule {
+    /// A timeline of `length` for `nodes` nodes, by default with faults
+    /// every 10 seconds and outages of 5 seconds on average, one node
+    /// failed at a time, and crashes only.
+    pub fn new(nodes: usize, length: Duration) -> FailureSchedule {
+        assert!(nodes > 0, "the cluster needs at least one node");
+        FailureSchedule {
+            nodes,
+            length: nanos(length),
+            mean_gap: 10e9,
+            mean_outage: 5e9,
+            max_concurrent: 1,
+            min_uptime: 0,
+            partition_probability: 0.0,
+        }
+    }
+
+    /// Sets the mean time between attempts to inject a fault, which are
+    /// skipped when the constraints allow none.
+    pub fn with_mean_gap(mut self, mean_gap: Duration) -> FailureSchedule {
+        assert!(mean_gap > Duration::ZERO, "mean gap must be positive");
+        self.mean_gap = nanos(mean_gap) as f64;
+        self
+    }
+
+    /// Sets the mean time a node stays down or a partition lasts.
+    pub fn with_mean_outage(mut self, mean_outage: Duration) -> FailureSchedule {
+        assert!(mean_outage > Duration::ZERO, "mean outage must be positive");
+        self.mean_outage = nanos(mean_outage) as f64;
+        self
+    }
+
+    /// Sets how many nodes may be crashed or partitioned off at once.
+    pub fn with_max_concurrent(mut self, max_concurrent: usize) -> FailureSchedule {
+        self.max_concurrent = max_concurrent;
+        self
+    }
+
+    /// Sets how long a node must have been up, counting from the start or
+    /// its last recovery, before it can fail.
+    pub fn with_min_uptime(mut self, min_uptime: Duration) -> FailureSchedule {
+        self.min_uptime = nanos(min_uptime);
+        self
+    }
+
+    /// Sets the chance that a fault is a partition rather than a crash.
+    /// Partitions need at least three nodes, and one at a time is active.
+    pub fn with_partition_probability(mut self, p: f64) -> FailureSchedule {
+        assert!((0.0..=1.0).contains(&p), "probability must lie in [0, 1]");
+        self.partition_probability = p;
+        self
+    }
+
+    /// The timeline for `seed`, in time order.
+    pub fn generate(&self, seed: u64) -> Vec<FaultEvent> {
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut events = Vec::new();
+        // recoveries and heals not yet reached
+        let mut pending: Vec<(u64, Fault)> = Vec::new();
+        let mut failed = vec![false; self.nodes];
+        let mut up_since = vec![0; self.nodes];
+        // the nodes cut off by the current partition, if any
+        let mut isolated: Vec<usize> = Vec::new();
+        let mut next_fault = self.exponential(&mut pcg, self.mean_gap);
+
+        loop {
+            let fault_due = next_fault < self.length;
+            match (0..pending.len()).min_by_key(|&i| pending[i].0) {
+                Some(i) if !fault_due || pending[i].0 <= next_fault => {
+                    // undo a fault, no later than the end of the timeline
+                    let (t, fault) = pending.swap_remove(i);
+                    let t = t.min(self.length);
+                    let restored = match &fault {
+                        Fault::Recover(node) => vec![*node],
+                        _ => std::mem::take(&mut isolated),
+                    };
+                    for node in restored {
+                        failed[node] = false;
+                        up_since[node] = t;
+                    }
+                    events.push(FaultEvent {
+                        at: Duration::from_nanos(t),
+                        fault,
+                    });
+                }
+                _ if fault_due => {
+                    let t = next_fault;
+                    next_fault = t.saturating_add(self.exponential(&mut pcg, self.mean_gap));
+                    let partitioned = !isolated.is_empty();
+                    if let Some(fault) = self.inject(&mut pcg, t, &failed, &up_since, partitioned) {
+                        let end = t.saturating_add(self.exponential(&mut pcg, self.mean_outage));
+                        match &fault {
+                            Fault::Crash(node) => {
+                                failed[*node] = true;
+                                pending.push((end, Fault::Recover(*node)));
+                            }
+                            Fault::Partition(nodes) => {
+                                for &node in nodes {
+                                    failed[node] = true;
+                                }
+                                isolated = nodes.clone();
+                                pending.push((end, Fault::Heal));
+                            }
+                            _ => unreachable!(),
+                        }
+                        events.push(FaultEvent {
+                            at: Duration::from_nanos(t),
+                            fault,
+                        });
+                    }
+                }
+                _ => break,
+            }
+        }
+        events
+    }
+
+    /// Picks a crash or partition at time t, if the constraints allow one.
+    fn inject(
+        &self,
+        pcg: &mut Pcg,
+        t: u64,
+        failed: &[bool],
+        up_since: &[u64],
+        partitioned: bool,
+    ) -> Option<Fault> {
+        let down = failed.iter().filter(|&&f| f).count();
+        let budget = self.max_concurrent.saturating_sub(down);
+        let mut eligible: Vec<usize> = (0..self.nodes)
+            .filter(|&node| !failed[node] && t - up_since[node] >= self.min_uptime)
+            .collect();
+        if budget == 0 || eligible.is_empty() {
+            return None;
+        }
+        let minority = (self.nodes - 1) / 2;
+        if !partitioned && minority > 0 && unit_f64(pcg) < self.partition_probability {
+            let size = 1 + bounded_usize(pcg, budget.min(minority).min(eligible.len()));
+            shuffle(pcg, &mut eligible);
+            eligible.truncate(size);
+            eligible.sort_unstable();
+            return Some(Fault::Partition(eligible));
+        }
+        Some(Fault::Crash(eligible[bounded_usize(pcg, eligible.len())]))
+    }
+
+    fn exponential<R: RngCore + ?Sized>(&self, rng: &mut R, mean: f64) -> u64 {
+        (-mean * open_unit_f64(rng).ln()) as u64
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replays a timeline, checking it against the schedule's constraints
+    fn check(schedule: &FailureSchedule, events: &[FaultEvent]) {
+        let mut failed = vec![false; schedule.nodes];
+        let mut up_since = vec![Duration::ZERO; schedule.nodes];
+        let mut isolated: Option<Vec<usize>> = None;
+        let min_uptime = Duration::from_nanos(schedule.min_uptime);
+        let mut last = Duration::ZERO;
+        for event in events {
+            assert!(event.at >= last);
+            assert!(event.at <= Duration::from_nanos(schedule.length));
+            last = event.at;
+            match &event.fault {
+                Fault::Crash(node) => {
+                    assert!(!failed[*node]);
+                    assert!(event.at - up_since[*node] >= min_uptime);
+                    failed[*node] = true;
+                }
+                Fault::Recover(node) => {
+                    assert!(failed[*node]);
+                    failed[*node] = false;
+                    up_since[*node] = event.at;
+                }
+                Fault::Partition(nodes) => {
+                    assert!(isolated.is_none());
+                    assert!(!nodes.is_empty() && nodes.len() * 2 < schedule.nodes);
+                    for &node in nodes {
+                        assert!(!failed[node]);
+                        assert!(event.at - up_since[node] >= min_uptime);
+                        failed[node] = true;
+                    }
+                    isolated = Some(nodes.clone());
+                }
+                Fault::Heal => {
+                    for node in isolated.take().unwrap() {
+                        failed[node] = false;
+                        up_since[node] = event.at;
+                    }
+                }
+            }
+            assert!(failed.iter().filter(|&&f| f).count() <= schedule.max_concurrent);
+        }
+        assert!(failed.iter().all(|&f| !f));
+        assert!(isolated.is_none());
+    }
+
+    #[test]
+    fn test_constraints() {
+        let schedule = FailureSchedule::new(7, Duration::from_secs(3600))
+            .with_mean_gap(Duration::from_secs(5))
+            .with_mean_outage(Duration::from_secs(20))
+            .with_max_concurrent(3)
+            .with_min_uptime(Duration::from_secs(15))
+            .with_partition_probability(0.4);
+        for _ in 0..20 {
+            let events = schedule.generate(rand::random::<u64>());
+            check(&schedule, &events);
+            assert!(events
+                .iter()
+                .any(|e| matches!(e.fault, Fault::Partition(_))));
+            assert!(events.iter().any(|e| matches!(e.fault, Fault::Crash(_))));
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let schedule = FailureSchedule::new(3, Duration::from_secs(1000));
+        let events = schedule.generate(rand::random::<u64>());
+        check(&schedule, &events);
+        // faults are tried every 10 seconds but skipped while a node is
+        // down, a third of the time, so about 67 land, each recovered
+        assert!((90..180).contains(&events.len()));
+        assert!(events
+            .iter()
+            .all(|e| matches!(e.fault, Fault::Crash(_) | Fault::Recover(_))));
+
+        // a single node can crash, but never be partitioned off
+        let schedule =
+            FailureSchedule::new(1, Duration::from_secs(100)).with_partition_probability(1.0);
+        check(&schedule, &schedule.generate(rand::random::<u64>()));
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let schedule = FailureSchedule::new(5, Duration::from_secs(300)).with_max_concurrent(2);
+        let seed = rand::random::<u64>();
+        assert_eq!(schedule.generate(seed), schedule.generate(seed));
+        assert_ne!(schedule.generate(seed), schedule.generate(seed ^ 1));
+    }
+}
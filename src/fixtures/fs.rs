@@ -0,0 +1,239 @@
+/*! Random directory trees, for testing tools such as backup and sync
+ *  against reproducible file layouts.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::fixtures::fs::TreeSpec;
+ *  let spec = TreeSpec::new().with_depth(3).with_file_size(1024);
+ *  let tree = spec.build(12345).unwrap();
+ *
+ *  for file in tree.files() {
+ *      assert!(tree.root().join(file).is_file());
+ *  }
+ *  // the tree is deleted when dropped
+ *  ```
+ */
+use crate::{bounded_usize, Pcg};
+use rand_core::{RngCore, SeedableRng};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const DEFAULT_CHARSET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-_";
+const MAX_NAME_LEN: usize = 12;
+
+/// Counts trees built by this process, to keep temp dirs apart
+static TREES: AtomicUsize = AtomicUsize::new(0);
+
+/// The shape of a random directory tree. Each directory holds up to
+/// `dirs` subdirectories and up to `files` files, nesting no deeper than
+/// `depth`; file sizes are uniform up to `file_size` bytes.
+#[derive(Clone, Debug)]
+pub struct TreeSpec {
+    depth: usize,
+    dirs: usize,
+    files: usize,
+    file_size: usize,
+    charset: Vec<char>,
+}
+
+impl TreeSpec {
+    /// A small tree: depth 2, up to 3 subdirectories and 5 files of
+    /// 4 KiB per directory, with alphanumeric names.
+    pub fn new() -> TreeSpec {
+        TreeSpec {
+            depth: 2,
+            dirs: 3,
+            files: 5,
+            file_size: 4096,
+            charset: DEFAULT_CHARSET.chars().collect(),
+        }
+    }
+
+    /// Sets the maximum nesting depth below the root.
+    pub fn with_depth(mut self, depth: usize) -> TreeSpec {
+        self.depth = depth;
+        self
+    }
+
+    /// Sets the maximum number of subdirectories per directory.
+    pub fn with_dirs(mut self, dirs: usize) -> TreeSpec {
+        self.dirs = dirs;
+        self
+    }
+
+    /// Sets the maximum number of files per directory.
+    pub fn with_files(mut self, files: usize) -> TreeSpec {
+        self.files = files;
+        self
+    }
+
+    /// Sets the maximum file size in bytes.
+    pub fn with_file_size(mut self, file_size: usize) -> TreeSpec {
+        self.file_size = file_size;
+        self
+    }
+
+    /// Sets the characters names are drawn from. Path separators and
+    /// nulls are rejected when the tree is built.
+    pub fn with_charset(mut self, charset: &str) -> TreeSpec {
+        self.charset = charset.chars().collect();
+        self
+    }
+
+    /// Builds the tree in a fresh directory under the system temp dir.
+    pub fn build(&self, seed: u64) -> io::Result<FileTree> {
+        let root = std::env::temp_dir().join(format!(
+            "pcg-tree-{:016x}-{}-{}",
+            seed,
+            process::id(),
+            TREES.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir(&root)?;
+        match self.build_in(seed, &root) {
+            Ok(files) => Ok(FileTree { root, files }),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&root);
+                Err(e)
+            }
+        }
+    }
+
+    /// Builds the tree inside an existing directory, which is left in
+    /// place afterwards. Returns the files created, relative to `dir`,
+    /// in the order they were written.
+    pub fn build_in(&self, seed: u64, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        if self.charset.is_empty()
+            || self
+                .charset
+                .iter()
+                .any(|&c| c == '\0' || c == '/' || c == '\\')
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "charset must be non-empty, without separators or nulls",
+            ));
+        }
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut files = Vec::new();
+        self.fill(&mut pcg, dir, Path::new(""), 0, &mut files)?;
+        Ok(files)
+    }
+
+    fn fill(
+        &self,
+        pcg: &mut Pcg,
+        root: &Path,
+        relative: &Path,
+        depth: usize,
+        files: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
+        let mut names = HashSet::new();
+        for _ in 0..bounded_usize(pcg, self.files + 1) {
+            let path = relative.join(self.name(pcg, &mut names));
+            let mut contents = vec![0; bounded_usize(pcg, self.file_size + 1)];
+            pcg.fill_bytes(&mut contents);
+            fs::write(root.join(&path), contents)?;
+            files.push(path);
+        }
+        if depth < self.depth {
+            for _ in 0..bounded_usize(pcg, self.dirs + 1) {
+                let path = relative.join(self.name(pcg, &mut names));
+                fs::create_dir(root.join(&path))?;
+                self.fill(pcg, root, &path, depth + 1, files)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A random name not already used in the directory, nor "." or ".."
+    fn name(&self, pcg: &mut Pcg, used: &mut HashSet<String>) -> String {
+        let len = 1 + bounded_usize(pcg, MAX_NAME_LEN);
+        let mut name: String = (0..len)
+            .map(|_| self.charset[bounded_usize(pcg, self.charset.len())])
+            .collect();
+        // a suffix keeps small charsets from looping forever
+        let mut suffix = used.len();
+        while name == "." || name == ".." || used.contains(&name) {
+            name = format!("{}-{}", name, suffix);
+            suffix += 1;
+        }
+        used.insert(name.clone());
+        name
+    }
+}
+
+impl Default for TreeSpec {
+    fn default() -> TreeSpec {
+        TreeSpec::new()
+    }
+}
+
+/// A generated tree in the temp dir, removed when dropped
+#[derive(Debug)]
+pub struct FileTree {
+    root: PathBuf,
+    files: Vec<PathBuf>,
+}
+
+impl FileTree {
+    /// The directory holding the tree.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The files in the tree, relative to the root.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+}
+
+impl Drop for FileTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree() {
+        let spec = TreeSpec::new()
+            .with_depth(3)
+            .with_files(4)
+            .with_file_size(100);
+        let tree = spec.build(rand::random::<u64>()).unwrap();
+        for file in tree.files() {
+            assert!(file.components().count() <= 4);
+            let len = fs::metadata(tree.root().join(file)).unwrap().len();
+            assert!(len <= 100);
+        }
+        let root = tree.root().to_path_buf();
+        drop(tree);
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seed = rand::random::<u64>();
+        let spec = TreeSpec::new().with_charset("ab");
+        let (a, b) = (spec.build(seed).unwrap(), spec.build(seed).unwrap());
+        assert_ne!(a.root(), b.root());
+        assert_eq!(a.files(), b.files());
+        for file in a.files() {
+            let contents = fs::read(a.root().join(file)).unwrap();
+            assert_eq!(contents, fs::read(b.root().join(file)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_invalid_charset() {
+        assert!(TreeSpec::new().with_charset("").build(1).is_err());
+        assert!(TreeSpec::new().with_charset("a/b").build(1).is_err());
+    }
+}
@@ -0,0 +1,4 @@
+/*! Reproducible test fixtures built from a seed.
+ */
+#[cfg(feature = "fs")]
+pub mod fs;
@@ -0,0 +1,136 @@
+/*! A free-function API in the style of fastrand, backed by a
+ *  thread-local generator. Each thread's generator is seeded from the
+ *  standard library's per-process random hash keys, and can be reseeded
+ *  with `seed` for reproducible runs.
+ *
+ *  These functions are re-exported at the crate root, so projects using
+ *  fastrand can migrate by swapping the import.
+ *
+ *  # Example use
+ *  ```
+ *  pcg::seed(12345);
+ *  let die = pcg::u32(1..=6);
+ *  let mut deck: Vec<u8> = (0..52).collect();
+ *  pcg::shuffle(&mut deck);
+ *
+ *  pcg::seed(12345);
+ *  assert_eq!(pcg::u32(1..=6), die);
+ *  ```
+ */
+use crate::{permutation, uniform, unit_f32, unit_f64, Pcg};
+use rand_core::{RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::ops::RangeBounds;
+
+thread_local! {
+    static PCG: RefCell<Pcg> = RefCell::new(Pcg::seed_from_u64(
+        RandomState::new().build_hasher().finish(),
+    ));
+}
+
+fn with<T, F: FnOnce(&mut Pcg) -> T>(f: F) -> T {
+    PCG.with(|pcg| f(&mut pcg.borrow_mut()))
+}
+
+macro_rules! range_fn {
+    ($name:ident, $t:ty) => {
+        #[doc = concat!("Generates a random `", stringify!($t), "` in the given range.")]
+        pub fn $name(range: impl RangeBounds<$t>) -> $t {
+            with(|pcg| uniform::gen_range(pcg, range))
+        }
+    };
+}
+
+range_fn!(u8, u8);
+range_fn!(u16, u16);
+range_fn!(u32, u32);
+range_fn!(u64, u64);
+range_fn!(usize, usize);
+range_fn!(i8, i8);
+range_fn!(i16, i16);
+range_fn!(i32, i32);
+range_fn!(i64, i64);
+range_fn!(isize, isize);
+
+/// Reseeds the current thread's generator.
+pub fn seed(seed: u64) {
+    with(|pcg| *pcg = Pcg::seed_from_u64(seed))
+}
+
+/// Generates a random bool.
+pub fn bool() -> bool {
+    with(|pcg| pcg.next_u32() >> 31 == 1)
+}
+
+/// Generates a random f32 in [0, 1).
+pub fn f32() -> f32 {
+    with(unit_f32)
+}
+
+/// Generates a random f64 in [0, 1).
+pub fn f64() -> f64 {
+    with(unit_f64)
+}
+
+/// Shuffles a slice in place.
+pub fn shuffle<T>(slice: &mut [T]) {
+    with(|pcg| permutation::shuffle(pcg, slice))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed() {
+        let seed = rand::random::<u64>();
+        super::seed(seed);
+        let x = super::u64(..);
+        let mut pcg = Pcg::seed_from_u64(seed);
+        assert_eq!(x, pcg.next_u64());
+
+        super::seed(seed);
+        assert_eq!(super::u64(..), x);
+    }
+
+    #[test]
+    fn test_ranges() {
+        for _ in 0..1000 {
+            assert!((1..=6).contains(&super::u32(1..=6)));
+            assert!((-5..5).contains(&super::i8(-5..5)));
+            assert!((10..).contains(&super::usize(10..)));
+            assert_eq!(super::i64(-3..=-3), -3);
+            let f = super::f64();
+            assert!((0.0..1.0).contains(&f));
+            let f = super::f32();
+            assert!((0.0..1.0).contains(&f));
+        }
+        let mut seen = [false; 256];
+        for _ in 0..10000 {
+            seen[(super::i8(..) as i16 + 128) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_range() {
+        super::u32(5..5);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let mut deck: Vec<u32> = (0..52).collect();
+        super::shuffle(&mut deck);
+        deck.sort_unstable();
+        assert_eq!(deck, (0..52).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_bool() {
+        let trues = (0..1000).filter(|_| super::bool()).count();
+        assert!(trues > 400 && trues < 600);
+    }
+}
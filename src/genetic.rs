@@ -0,0 +1,147 @@
+/*! Crossover and mutation operators for genetic algorithms.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::genetic::*;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut a = vec![0.0; 8];
+ *  let mut b = vec![1.0; 8];
+ *
+ *  two_point_crossover(&mut pcg, &mut a, &mut b);
+ *  mutate_gaussian(&mut pcg, &mut a, 0.1, 0.5);
+ *  ```
+ */
+use crate::distributions::{Distribution, StandardNormal};
+use crate::{bounded_usize, unit_f64};
+use rand_core::RngCore;
+
+/// Swaps the tails of two genomes after a random cut point, so that each
+/// keeps at least its first gene. Genomes must have equal length.
+pub fn single_point_crossover<T, R: RngCore + ?Sized>(rng: &mut R, a: &mut [T], b: &mut [T]) {
+    assert_eq!(a.len(), b.len(), "genomes differ in length");
+    if a.len() < 2 {
+        return;
+    }
+    let cut = 1 + bounded_usize(rng, a.len() - 1);
+    a[cut..].swap_with_slice(&mut b[cut..]);
+}
+
+/// Swaps the segment between two random cut points of two genomes,
+/// which must have equal length.
+pub fn two_point_crossover<T, R: RngCore + ?Sized>(rng: &mut R, a: &mut [T], b: &mut [T]) {
+    assert_eq!(a.len(), b.len(), "genomes differ in length");
+    if a.len() < 2 {
+        return;
+    }
+    let first = bounded_usize(rng, a.len() + 1);
+    let second = bounded_usize(rng, a.len() + 1);
+    let (start, end) = if first <= second {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    a[start..end].swap_with_slice(&mut b[start..end]);
+}
+
+/// Replaces each gene with a random byte with probability `rate`.
+/// Returns the number of genes mutated.
+pub fn mutate_bytes<R: RngCore + ?Sized>(rng: &mut R, genome: &mut [u8], rate: f64) -> usize {
+    let mut mutated = 0;
+    for gene in genome.iter_mut() {
+        if unit_f64(rng) < rate {
+            *gene = (rng.next_u32() >> 24) as u8;
+            mutated += 1;
+        }
+    }
+    mutated
+}
+
+/// Flips each bit of the genome with probability `rate`.
+/// Returns the number of bits flipped.
+pub fn mutate_bits<R: RngCore + ?Sized>(rng: &mut R, genome: &mut [u8], rate: f64) -> usize {
+    let mut flipped = 0;
+    for gene in genome.iter_mut() {
+        for bit in 0..8 {
+            if unit_f64(rng) < rate {
+                *gene ^= 1 << bit;
+                flipped += 1;
+            }
+        }
+    }
+    flipped
+}
+
+/// Adds normal noise with standard deviation `sigma` to each gene with
+/// probability `rate`. Returns the number of genes mutated.
+pub fn mutate_gaussian<R: RngCore + ?Sized>(
+    rng: &mut R,
+    genome: &mut [f64],
+    rate: f64,
+    sigma: f64,
+) -> usize {
+    let mut mutated = 0;
+    for gene in genome.iter_mut() {
+        if unit_f64(rng) < rate {
+            *gene += sigma * StandardNormal.sample(rng);
+            mutated += 1;
+        }
+    }
+    mutated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_single_point_crossover() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..100 {
+            let mut a = vec![0u8; 10];
+            let mut b = vec![1u8; 10];
+            single_point_crossover(&mut pcg, &mut a, &mut b);
+            let cut = a.iter().position(|&g| g == 1).unwrap();
+            assert!(cut >= 1);
+            assert!(a[cut..].iter().all(|&g| g == 1));
+            assert!(b[..cut].iter().all(|&g| g == 1));
+            assert!(b[cut..].iter().all(|&g| g == 0));
+        }
+    }
+
+    #[test]
+    fn test_two_point_crossover() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..100 {
+            let mut a = vec![0.0; 10];
+            let mut b = vec![1.0; 10];
+            two_point_crossover(&mut pcg, &mut a, &mut b);
+            for (x, y) in a.iter().zip(&b) {
+                assert_eq!(x + y, 1.0);
+            }
+            // the swapped genes form one contiguous segment
+            let changes = a.windows(2).filter(|w| w[0] != w[1]).count();
+            assert!(changes <= 2);
+        }
+    }
+
+    #[test]
+    fn test_mutation_rates() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut bytes = vec![0u8; 10000];
+        assert_eq!(mutate_bytes(&mut pcg, &mut bytes, 0.0), 0);
+        let flipped = mutate_bits(&mut pcg, &mut bytes, 0.1);
+        let ones: u32 = bytes.iter().map(|b| b.count_ones()).sum();
+        assert_eq!(ones as usize, flipped);
+        assert!((flipped as f64 / 80000.0 - 0.1).abs() < 0.01);
+
+        let mut genome = vec![0.0; 10000];
+        let mutated = mutate_gaussian(&mut pcg, &mut genome, 0.5, 2.0);
+        assert_eq!(genome.iter().filter(|&&g| g != 0.0).count(), mutated);
+        assert!((mutated as f64 / 10000.0 - 0.5).abs() < 0.03);
+        assert_eq!(mutate_bytes(&mut pcg, &mut bytes, 1.0), 10000);
+    }
+}
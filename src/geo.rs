@@ -0,0 +1,149 @@
+/*! Random geographic coordinates, uniform over the surface of a sphere.
+ *
+ *  Drawing latitude uniformly in degrees would crowd points near the
+ *  poles, where meridians converge; these functions weight latitude by
+ *  its circumference instead, so equal areas get equal numbers of points.
+ *  Coordinates are in degrees, latitude first.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::geo::{gen_in_bbox, gen_lat_lon, BoundingBox};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let (lat, lon) = gen_lat_lon(&mut pcg);
+ *  assert!(lat.abs() <= 90.0 && lon.abs() <= 180.0);
+ *
+ *  let alps = BoundingBox::new(45.5, 5.5, 48.0, 16.5);
+ *  let (lat, lon) = gen_in_bbox(&mut pcg, alps);
+ *  assert!(alps.contains(lat, lon));
+ *  ```
+ */
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// A latitude/longitude rectangle, in degrees. If `west` is greater than
+/// `east`, the box crosses the antimeridian.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub south: f64,
+    pub west: f64,
+    pub north: f64,
+    pub east: f64,
+}
+
+impl BoundingBox {
+    /// Latitudes must satisfy -90 <= south <= north <= 90, and longitudes
+    /// lie in [-180, 180].
+    pub fn new(south: f64, west: f64, north: f64, east: f64) -> BoundingBox {
+        assert!(
+            -90.0 <= south && south <= north && north <= 90.0,
+            "latitudes out of order or range"
+        );
+        assert!(
+            (-180.0..=180.0).contains(&west) && (-180.0..=180.0).contains(&east),
+            "longitudes out of range"
+        );
+        BoundingBox {
+            south,
+            west,
+            north,
+            east,
+        }
+    }
+
+    /// Whether the box contains the point.
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        let in_lon = if self.west <= self.east {
+            self.west <= lon && lon <= self.east
+        } else {
+            lon >= self.west || lon <= self.east
+        };
+        self.south <= lat && lat <= self.north && in_lon
+    }
+}
+
+/// Generates a point uniformly distributed over the whole sphere.
+pub fn gen_lat_lon<R: RngCore + ?Sized>(rng: &mut R) -> (f64, f64) {
+    gen_in_bbox(rng, BoundingBox::new(-90.0, -180.0, 90.0, 180.0))
+}
+
+/// Generates a point uniformly distributed, by area, within the box.
+pub fn gen_in_bbox<R: RngCore + ?Sized>(rng: &mut R, bounds: BoundingBox) -> (f64, f64) {
+    // the area between two latitudes is proportional to the difference of
+    // their sines, so draw the sine uniformly
+    let (lo, hi) = (
+        bounds.south.to_radians().sin(),
+        bounds.north.to_radians().sin(),
+    );
+    let lat = (lo + unit_f64(rng) * (hi - lo)).asin().to_degrees();
+    let width = if bounds.west <= bounds.east {
+        bounds.east - bounds.west
+    } else {
+        bounds.east - bounds.west + 360.0
+    };
+    let mut lon = bounds.west + unit_f64(rng) * width;
+    if lon > 180.0 {
+        lon -= 360.0;
+    }
+    // asin and to_degrees can round just past the edges
+    (lat.clamp(bounds.south, bounds.north), lon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_uniform_on_sphere() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let n = 100000;
+        // the caps above 30 degrees north and south each hold a quarter of
+        // the area, and the band between them half
+        let mut bands = [0; 3];
+        for _ in 0..n {
+            let (lat, lon) = gen_lat_lon(&mut pcg);
+            assert!((-180.0..=180.0).contains(&lon));
+            bands[if lat < -30.0 {
+                0
+            } else if lat <= 30.0 {
+                1
+            } else {
+                2
+            }] += 1;
+        }
+        assert!((24000..26000).contains(&bands[0]));
+        assert!((48500..51500).contains(&bands[1]));
+        assert!((24000..26000).contains(&bands[2]));
+    }
+
+    #[test]
+    fn test_bbox() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let boxes = [
+            BoundingBox::new(45.5, 5.5, 48.0, 16.5),
+            BoundingBox::new(-20.0, 170.0, -10.0, -170.0),
+            BoundingBox::new(89.0, -180.0, 90.0, 180.0),
+            BoundingBox::new(10.0, 20.0, 10.0, 20.0),
+        ];
+        for bounds in &boxes {
+            for _ in 0..1000 {
+                let (lat, lon) = gen_in_bbox(&mut pcg, *bounds);
+                assert!(
+                    bounds.contains(lat, lon),
+                    "{:?} outside {:?}",
+                    (lat, lon),
+                    bounds
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_bbox() {
+        BoundingBox::new(50.0, 0.0, 40.0, 10.0);
+    }
+}
@@ -0,0 +1,271 @@
+/*! Deterministic derivation of hashing parameters from a single seed,
+ *  and consistent per-key decisions, experiment buckets and shard
+ *  assignment built on them.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::hashing::{decide, salt_provider};
+ *  let salts = salt_provider(12345);
+ *  let bloom = salts.salts("bloom-filter", 3);
+ *  let partitioner = salts.salt("partitioner");
+ *
+ *  assert_eq!(salt_provider(12345).salt("partitioner"), partitioner);
+ *  assert!(!bloom.contains(&partitioner));
+ *
+ *  let user_id = 42;
+ *  let traced = decide(12345, user_id, 0.01);
+ *  assert_eq!(decide(12345, user_id, 0.01), traced);
+ *  ```
+ */
+use crate::{hash_label, mix64, Pcg};
+use rand_core::{RngCore, SeedableRng};
+
+/// Derives per-purpose salts from a master seed by label. A given seed
+/// and label always yield the same salt, across processes and for as long
+/// as the stream format version is unchanged.
+#[derive(Clone)]
+pub struct SaltProvider {
+    root: Pcg,
+}
+
+/// Creates a SaltProvider for the given master seed.
+pub fn salt_provider(master_seed: u64) -> SaltProvider {
+    SaltProvider {
+        root: Pcg::seed_from_u64(master_seed),
+    }
+}
+
+impl SaltProvider {
+    /// The salt for the purpose named by `label`.
+    pub fn salt(&self, label: &str) -> u64 {
+        self.root.substream(hash_label(label)).next_u64()
+    }
+
+    /// n distinct salts for one purpose, e.g. one per bloom filter hash.
+    pub fn salts(&self, label: &str, n: usize) -> Vec<u64> {
+        let mut pcg = self.root.substream(hash_label(label));
+        let mut salts: Vec<u64> = Vec::with_capacity(n);
+        while salts.len() < n {
+            let salt = pcg.next_u64();
+            if !salts.contains(&salt) {
+                salts.push(salt);
+            }
+        }
+        salts
+    }
+}
+
+/// Decides whether to sample `key`, with the given probability, the same
+/// way in every process sharing `seed`. Decisions are monotone: a key
+/// sampled at some probability is sampled at every higher one, so raising
+/// a sampling rate only adds keys.
+pub fn decide(seed: u64, key: u64, probability: f64) -> bool {
+    below(mix64(seed ^ mix64(key)), probability)
+}
+
+/// Whether a uniform u64 falls in the lowest `probability` of its range
+fn below(x: u64, probability: f64) -> bool {
+    if probability >= 1.0 {
+        return true;
+    }
+    if probability.is_nan() || probability <= 0.0 {
+        return false;
+    }
+    // exact to within 2^-64, since scaling by 2^64 only changes the exponent
+    let threshold = (probability * 18446744073709551616.0) as u64;
+    x < threshold
+}
+
+/// Assigns a unit, such as a user, to a variant of an experiment, the same
+/// way in every process and run. `ramp[i]` is the fraction of all units
+/// exposed to variant i, at most 1 / ramp.len(); the rest get None, for
+/// the control. Each variant draws from its own equal share of units, so
+/// ramping one up only adds units to it: no unit ever moves between
+/// variants, or out of one, while ramps only grow. Experiments with
+/// different salts assign independently.
+pub fn assign_bucket(unit_id: u64, experiment_salt: u64, ramp: &[f64]) -> Option<usize> {
+    let n = ramp.len();
+    assert!(
+        ramp.iter().all(|&r| r * n as f64 <= 1.0 + 1e-9),
+        "a variant's ramp cannot exceed its share of units"
+    );
+    if n == 0 {
+        return None;
+    }
+    // the high word picks the variant's share, the low one the position in it
+    let wide = mix64(experiment_salt ^ mix64(unit_id)) as u128 * n as u128;
+    let variant = (wide >> 64) as usize;
+    if below(wide as u64, ramp[variant] * n as f64) {
+        Some(variant)
+    } else {
+        None
+    }
+}
+
+/// Picks the owner of `key` among weighted `(id, weight)` nodes by
+/// weighted rendezvous (highest-random-weight) hashing. Each node owns a
+/// share of keys proportional to its weight, and adding or removing a node
+/// only moves the keys it gains or loses. Nodes without positive weight
+/// own nothing; returns None if no node has positive weight.
+pub fn pick_owner(key: u64, nodes: &[(u64, f64)]) -> Option<u64> {
+    let key = mix64(key);
+    let mut owner = None;
+    let mut best = 0.0;
+    for &(id, weight) in nodes {
+        if weight.is_nan() || weight <= 0.0 {
+            continue;
+        }
+        // a uniform draw in (0, 1) for this key and node
+        let u = ((mix64(key ^ id) >> 11) as f64 + 0.5) / (1u64 << 53) as f64;
+        let score = weight / -u.ln();
+        if owner.is_none() || score > best {
+            owner = Some(id);
+            best = score;
+        }
+    }
+    owner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_salts() {
+        let seed = rand::random::<u64>();
+        let provider = salt_provider(seed);
+        assert_eq!(provider.salt("a"), salt_provider(seed).salt("a"));
+        assert_ne!(provider.salt("a"), provider.salt("b"));
+        assert_ne!(provider.salt("a"), salt_provider(!seed).salt("a"));
+
+        let salts = provider.salts("a", 4);
+        assert_eq!(salts[0], provider.salt("a"));
+        assert_eq!(salts, salt_provider(seed).salts("a", 4));
+    }
+
+    #[test]
+    fn test_salts_are_stable() {
+        let provider = salt_provider(12345);
+        assert_eq!(provider.salt("bloom-filter"), STABLE_SALT);
+    }
+
+    #[test]
+    fn test_decide() {
+        let seed = rand::random::<u64>();
+        let sampled = (0..100000).filter(|&key| decide(seed, key, 0.1)).count();
+        assert!((9500..10500).contains(&sampled));
+        for key in 0..1000 {
+            assert_eq!(decide(seed, key, 0.3), decide(seed, key, 0.3));
+            assert!(!decide(seed, key, 0.2) || decide(seed, key, 0.4));
+            assert!(decide(seed, key, 1.0));
+            assert!(!decide(seed, key, 0.0));
+            assert!(!decide(seed, key, f64::NAN));
+        }
+        let other = (0..1000).filter(|&key| decide(seed, key, 0.5) != decide(!seed, key, 0.5));
+        assert!(other.count() > 400);
+    }
+
+    #[test]
+    fn test_decide_is_stable() {
+        let decisions: Vec<bool> = (0..8).map(|key| decide(12345, key, 0.5)).collect();
+        assert_eq!(decisions, STABLE_DECISIONS);
+    }
+
+    #[test]
+    fn test_assign_bucket() {
+        let salt = rand::random::<u64>();
+        let mut counts = [0; 4];
+        for unit in 0..40000 {
+            match assign_bucket(unit, salt, &[0.3, 0.1, 0.2]) {
+                Some(variant) => counts[variant] += 1,
+                None => counts[3] += 1,
+            }
+        }
+        assert!((11500..12500).contains(&counts[0]));
+        assert!((3700..4300).contains(&counts[1]));
+        assert!((7600..8400).contains(&counts[2]));
+        assert!((15500..16500).contains(&counts[3]));
+
+        // ramping up only adds units, and a full ramp covers everyone
+        for unit in 0..1000 {
+            let early = assign_bucket(unit, salt, &[0.05, 0.05]);
+            let late = assign_bucket(unit, salt, &[0.2, 0.5]);
+            assert!(early.is_none() || early == late);
+            assert!(assign_bucket(unit, salt, &[0.5, 0.5]).is_some());
+            assert_eq!(assign_bucket(unit, salt, &[0.0, 0.0]), None);
+        }
+        let other = (0..1000).filter(|&unit| {
+            assign_bucket(unit, salt, &[0.5, 0.5]) != assign_bucket(unit, !salt, &[0.5, 0.5])
+        });
+        assert!(other.count() > 400);
+        assert_eq!(assign_bucket(1, salt, &[]), None);
+        assert!(assign_bucket(1, salt, &[1.0 / 3.0; 3]).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot exceed its share")]
+    fn test_assign_bucket_overfull() {
+        assign_bucket(1, 2, &[0.6, 0.1]);
+    }
+
+    #[test]
+    fn test_assign_bucket_is_stable() {
+        let buckets: Vec<Option<usize>> = (0..8)
+            .map(|unit| assign_bucket(unit, 12345, &[0.4, 0.4]))
+            .collect();
+        assert_eq!(buckets, STABLE_BUCKETS);
+    }
+
+    #[test]
+    fn test_pick_owner() {
+        let nodes = [(1, 1.0), (2, 2.0), (3, 1.0), (4, 0.0)];
+        let mut owned = [0; 5];
+        let base = rand::random::<u64>();
+        for key in base..base + 40000 {
+            owned[pick_owner(key, &nodes).unwrap() as usize] += 1;
+        }
+        assert_eq!(owned[4], 0);
+        assert!((9500..10500).contains(&owned[1]));
+        assert!((19000..21000).contains(&owned[2]));
+        assert!((9500..10500).contains(&owned[3]));
+
+        // removing a node only moves its own keys
+        for key in base..base + 1000 {
+            let owner = pick_owner(key, &nodes).unwrap();
+            if owner != 2 {
+                assert_eq!(pick_owner(key, &[nodes[0], nodes[2]]), Some(owner));
+            }
+        }
+        assert_eq!(pick_owner(base, &[]), None);
+        assert_eq!(pick_owner(base, &[(9, 0.0)]), None);
+    }
+
+    #[test]
+    fn test_pick_owner_is_stable() {
+        let nodes = [(1, 1.0), (2, 1.0), (3, 1.0)];
+        let owners: Vec<u64> = (0..8).map(|key| pick_owner(key, &nodes).unwrap()).collect();
+        assert_eq!(owners, STABLE_OWNERS);
+    }
+
+    /// Like STABLE_SALT, for assignments shared across processes
+    const STABLE_OWNERS: [u64; 8] = [2, 2, 2, 1, 1, 2, 1, 1];
+
+    /// Like STABLE_SALT, for experiment assignments shared across processes
+    const STABLE_BUCKETS: [Option<usize>; 8] = [
+        None,
+        Some(0),
+        Some(0),
+        Some(0),
+        Some(0),
+        None,
+        Some(1),
+        Some(0),
+    ];
+
+    /// Like STABLE_SALT, for decisions shared across processes
+    const STABLE_DECISIONS: [bool; 8] = [false, true, true, true, true, true, false, true];
+
+    /// Changing this value breaks every salt derived by users; it may only
+    /// change together with the stream format version.
+    const STABLE_SALT: u64 = 0xDD7EF00889877D01;
+}
@@ -0,0 +1,213 @@
+/*! Skewed key generation for database and cache benchmarks.
+ *
+ *  A key range is split into equal buckets, such as shards, partitions or
+ *  cache lines, and each draw picks a bucket according to a skew, then a
+ *  key uniformly within it. Reporting the bucket alongside the key lets a
+ *  benchmark check how the load actually spread.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::distributions::Distribution;
+ *  # use pcg::keys::{Bucketed, Skew};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let hotspot = Skew::Hotspot {
+ *      hot_buckets: 0.1,
+ *      hot_draws: 0.9,
+ *  };
+ *  let keys = Bucketed::new(0..1_000_000, 100, hotspot).unwrap();
+ *
+ *  let (key, bucket) = keys.sample(&mut pcg);
+ *  assert!(keys.bucket_range(bucket).contains(&key));
+ *  ```
+ */
+use crate::distributions::{Distribution, ParamError, Zipf};
+use crate::{bounded_u64, bounded_usize, unit_f64};
+use rand_core::RngCore;
+use std::ops::Range;
+
+/// How draws are spread over buckets
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Skew {
+    /// Every bucket equally likely
+    Uniform,
+    /// Bucket k, counting from 0, with probability proportional to
+    /// (k + 1)^-exponent
+    Zipfian(f64),
+    /// The first `hot_buckets` fraction of buckets, at least one, receives
+    /// a `hot_draws` fraction of the draws, uniformly among them
+    Hotspot { hot_buckets: f64, hot_draws: f64 },
+}
+
+#[derive(Clone, Debug)]
+enum Picker {
+    Uniform,
+    Zipfian(Zipf),
+    Hotspot { hot: usize, hot_draws: f64 },
+}
+
+/// Draws (key, bucket) pairs from a key range split into buckets
+#[derive(Clone, Debug)]
+pub struct Bucketed {
+    range: Range<u64>,
+    n_buckets: usize,
+    picker: Picker,
+}
+
+impl Bucketed {
+    /// Splits `range` into `n_buckets` buckets of equal size, give or take
+    /// one key. The range must hold at least one key per bucket.
+    pub fn new(range: Range<u64>, n_buckets: usize, skew: Skew) -> Result<Bucketed, ParamError> {
+        if n_buckets == 0 {
+            return Err(ParamError("there must be at least one bucket"));
+        }
+        if range.end <= range.start || range.end - range.start < n_buckets as u64 {
+            return Err(ParamError("range must hold at least one key per bucket"));
+        }
+        let picker = match skew {
+            Skew::Uniform => Picker::Uniform,
+            Skew::Zipfian(exponent) => Picker::Zipfian(Zipf::new(n_buckets as u64, exponent)?),
+            Skew::Hotspot {
+                hot_buckets,
+                hot_draws,
+            } => {
+                if !(0.0..=1.0).contains(&hot_buckets) || !(0.0..=1.0).contains(&hot_draws) {
+                    return Err(ParamError("hotspot fractions must lie in [0, 1]"));
+                }
+                let hot = ((hot_buckets * n_buckets as f64).round() as usize).clamp(1, n_buckets);
+                Picker::Hotspot { hot, hot_draws }
+            }
+        };
+        Ok(Bucketed {
+            range,
+            n_buckets,
+            picker,
+        })
+    }
+
+    pub fn n_buckets(&self) -> usize {
+        self.n_buckets
+    }
+
+    /// The keys in `bucket`.
+    pub fn bucket_range(&self, bucket: usize) -> Range<u64> {
+        assert!(bucket < self.n_buckets, "bucket out of range");
+        self.bucket_start(bucket)..self.bucket_start(bucket + 1)
+    }
+
+    fn bucket_start(&self, bucket: usize) -> u64 {
+        let len = (self.range.end - self.range.start) as u128;
+        self.range.start + (len * bucket as u128 / self.n_buckets as u128) as u64
+    }
+
+    fn pick_bucket<R: RngCore + ?Sized>(&self, rng: &mut R) -> usize {
+        match &self.picker {
+            Picker::Uniform => bounded_usize(rng, self.n_buckets),
+            Picker::Zipfian(zipf) => zipf.sample(rng) as usize - 1,
+            Picker::Hotspot { hot, hot_draws } => {
+                let cold = self.n_buckets - hot;
+                if cold == 0 || unit_f64(rng) < *hot_draws {
+                    bounded_usize(rng, *hot)
+                } else {
+                    hot + bounded_usize(rng, cold)
+                }
+            }
+        }
+    }
+}
+
+impl Distribution<(u64, usize)> for Bucketed {
+    /// Draws a key and the bucket it belongs to.
+    fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> (u64, usize) {
+        let bucket = self.pick_bucket(rng);
+        let keys = self.bucket_range(bucket);
+        (keys.start + bounded_u64(rng, keys.end - keys.start), bucket)
+    }
+}
+
+/// Draws one (key, bucket) pair. To draw many, build a `Bucketed` once
+/// and sample it repeatedly, which avoids setting up the skew each time.
+pub fn gen_bucketed<R: RngCore + ?Sized>(
+    rng: &mut R,
+    range: Range<u64>,
+    n_buckets: usize,
+    skew: Skew,
+) -> Result<(u64, usize), ParamError> {
+    Ok(Bucketed::new(range, n_buckets, skew)?.sample(rng))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    fn bucket_counts(keys: &Bucketed, n: usize) -> Vec<usize> {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut counts = vec![0; keys.n_buckets()];
+        for _ in 0..n {
+            let (key, bucket) = keys.sample(&mut pcg);
+            assert!(keys.bucket_range(bucket).contains(&key));
+            counts[bucket] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_buckets_partition_range() {
+        let keys = Bucketed::new(10..1010, 7, Skew::Uniform).unwrap();
+        assert_eq!(keys.bucket_range(0).start, 10);
+        assert_eq!(keys.bucket_range(6).end, 1010);
+        for b in 1..7 {
+            assert_eq!(keys.bucket_range(b - 1).end, keys.bucket_range(b).start);
+            assert!((142..=143).contains(&keys.bucket_range(b).count()));
+        }
+        let full = Bucketed::new(0..u64::MAX, 3, Skew::Uniform).unwrap();
+        assert_eq!(full.bucket_range(2).end, u64::MAX);
+    }
+
+    #[test]
+    fn test_uniform() {
+        let keys = Bucketed::new(0..1000, 4, Skew::Uniform).unwrap();
+        let counts = bucket_counts(&keys, 40000);
+        assert!(counts.iter().all(|&c| (9500..10500).contains(&c)));
+    }
+
+    #[test]
+    fn test_zipfian() {
+        let keys = Bucketed::new(0..1000, 10, Skew::Zipfian(1.0)).unwrap();
+        let counts = bucket_counts(&keys, 40000);
+        assert!(counts[9] < counts[0] / 5);
+        // bucket 0 over bucket 1 is 2^1 in expectation
+        let ratio = counts[0] as f64 / counts[1] as f64;
+        assert!((ratio - 2.0).abs() < 0.15);
+    }
+
+    #[test]
+    fn test_hotspot() {
+        let skew = Skew::Hotspot {
+            hot_buckets: 0.2,
+            hot_draws: 0.8,
+        };
+        let keys = Bucketed::new(0..1000, 10, skew).unwrap();
+        let counts = bucket_counts(&keys, 40000);
+        let hot: usize = counts[..2].iter().sum();
+        assert!((31000..33000).contains(&hot));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Bucketed::new(0..10, 0, Skew::Uniform).is_err());
+        assert!(Bucketed::new(0..3, 4, Skew::Uniform).is_err());
+        assert!(Bucketed::new(5..5, 1, Skew::Uniform).is_err());
+        assert!(Bucketed::new(0..10, 2, Skew::Zipfian(-1.0)).is_err());
+        let skew = Skew::Hotspot {
+            hot_buckets: 1.5,
+            hot_draws: 0.5,
+        };
+        assert!(Bucketed::new(0..10, 2, skew).is_err());
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert!(gen_bucketed(&mut pcg, 0..10, 2, Skew::Uniform).is_ok());
+    }
+}
@@ -0,0 +1,131 @@
+/*! Stream labels fixed at compile time.
+ *
+ *  Deriving a stream with `Pcg::stream_const` puts its label in the type
+ *  of the result, so a subsystem can demand its own stream in its
+ *  signatures, and `assert_distinct` rejects duplicate labels when the
+ *  crate is compiled rather than when two subsystems are found to be
+ *  drawing the same numbers.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::labels::{assert_distinct, label, ConstStream};
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  const PHYSICS: u64 = label("physics");
+ *  const LOOT: u64 = label("loot");
+ *  const _: () = assert_distinct(&[PHYSICS, LOOT]);
+ *
+ *  fn roll_loot(rng: &mut ConstStream<LOOT>) -> u32 {
+ *      rng.next_u32() % 100
+ *  }
+ *
+ *  let pcg = Pcg::seed_from_u64(12345);
+ *  let mut loot = pcg.stream_const::<LOOT>();
+ *  roll_loot(&mut loot);
+ *  // roll_loot(&mut pcg.stream_const::<PHYSICS>()) would not compile
+ *  ```
+ */
+use crate::{hash_label, Pcg};
+use rand_core::{Error, RngCore};
+
+/// Turns a name into a stream label, usable in constants.
+pub const fn label(name: &str) -> u64 {
+    hash_label(name)
+}
+
+/// Panics if any label appears twice. In a constant, such as
+/// `const _: () = assert_distinct(&[A, B]);`, the panic is a compile error.
+pub const fn assert_distinct(labels: &[u64]) {
+    let mut i = 0;
+    while i < labels.len() {
+        let mut j = i + 1;
+        while j < labels.len() {
+            if labels[i] == labels[j] {
+                panic!("duplicate stream label");
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+/// A generator derived for the stream label `LABEL`
+#[derive(Clone)]
+pub struct ConstStream<const LABEL: u64> {
+    pcg: Pcg,
+}
+
+impl<const LABEL: u64> ConstStream<LABEL> {
+    /// The label this stream was derived for.
+    pub const LABEL: u64 = LABEL;
+
+    /// Gives up the label's type, leaving a plain generator.
+    pub fn into_inner(self) -> Pcg {
+        self.pcg
+    }
+}
+
+impl<const LABEL: u64> RngCore for ConstStream<LABEL> {
+    fn next_u32(&mut self) -> u32 {
+        self.pcg.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.pcg.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.pcg.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.pcg.try_fill_bytes(dest)
+    }
+}
+
+impl Pcg {
+    /// Derives the substream for `LABEL`, typed by its label. This is the
+    /// same stream as `substream(LABEL)`.
+    pub fn stream_const<const LABEL: u64>(&self) -> ConstStream<LABEL> {
+        ConstStream {
+            pcg: self.substream(LABEL),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::SeedableRng;
+
+    const A: u64 = label("a");
+    const B: u64 = label("b");
+    const _: () = assert_distinct(&[A, B, 7]);
+
+    #[test]
+    fn test_stream_const() {
+        let pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut a = pcg.stream_const::<A>();
+        let mut expected = pcg.substream(A);
+        assert_eq!(a.next_u64(), expected.next_u64());
+        assert_eq!(ConstStream::<A>::LABEL, A);
+        assert_ne!(
+            pcg.stream_const::<B>().next_u64(),
+            pcg.substream(A).next_u64()
+        );
+        assert_eq!(a.into_inner().next_u64(), expected.next_u64());
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label("bloom-filter"), hash_label("bloom-filter"));
+        assert_eq!(label(""), 0xCBF29CE484222325);
+        assert_eq!(label("a"), 0xAF63DC4C8601EC8C);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate stream label")]
+    fn test_duplicate_label() {
+        assert_distinct(&[A, B, A]);
+    }
+}
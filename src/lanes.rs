@@ -0,0 +1,140 @@
+/*! Several `Pcg` lanes stepped together, for bulk u32 generation.
+ *
+ *  Each lane is an independent `Pcg` stream, and all lanes advance in one
+ *  loop over plain arrays. `std::simd` is not yet stable, so rather than
+ *  explicit vector instructions, the speedup over as many scalar `Pcg`s,
+ *  about 1.5x on x86-64 in `benches/lanes.rs`, comes from the lanes'
+ *  independent multiplies overlapping. Lane i produces exactly what the
+ *  `Pcg` returned by `lane(i)` would, so results can be checked against
+ *  the scalar generator.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::PcgX8;
+ *  # use rand_core::RngCore;
+ *  let mut lanes = PcgX8::new(12345);
+ *  let mut third = lanes.lane(2);
+ *
+ *  let block = lanes.next_u32x8();
+ *  assert_eq!(block[2], third.next_u32());
+ *
+ *  let mut samples = vec![0u32; 1 << 16];
+ *  lanes.fill_u32(&mut samples);
+ *  ```
+ */
+use crate::{Pcg, MULTIPLIER};
+use rand_core::SeedableRng;
+
+/// `N` independent `Pcg` streams stepped together
+#[derive(Clone, Debug)]
+pub struct PcgLanes<const N: usize> {
+    states: [u64; N],
+}
+
+/// Four lanes, one 256-bit vector of states
+pub type PcgX4 = PcgLanes<4>;
+/// Eight lanes, one 256-bit vector of outputs
+pub type PcgX8 = PcgLanes<8>;
+
+impl<const N: usize> PcgLanes<N> {
+    /// Seeds lane i as `Pcg::seed_from_u64(seed).substream(i)`.
+    pub fn new(seed: u64) -> PcgLanes<N> {
+        let root = Pcg::seed_from_u64(seed);
+        let mut states = [0; N];
+        for (i, state) in states.iter_mut().enumerate() {
+            *state = root.substream(i as u64).get_state();
+        }
+        PcgLanes { states }
+    }
+
+    /// A scalar `Pcg` at lane i's current position, with its draw count
+    /// starting from zero.
+    pub fn lane(&self, i: usize) -> Pcg {
+        let mut pcg = Pcg::seed_from_u64(0);
+        pcg.set_state(self.states[i]).unwrap();
+        pcg
+    }
+
+    /// Generate one u32 from every lane, advancing each one step.
+    #[inline]
+    pub fn next_lanes(&mut self) -> [u32; N] {
+        let mut out = [0; N];
+        for (state, out) in self.states.iter_mut().zip(out.iter_mut()) {
+            *out = step(state);
+        }
+        out
+    }
+
+    /// Fill dest with u32s, taking one from each lane in turn, so that
+    /// dest[k * N + i] is lane i's k-th output. A partial last block uses
+    /// the first lanes only.
+    pub fn fill_u32(&mut self, dest: &mut [u32]) {
+        let mut chunks = dest.chunks_exact_mut(N);
+        for chunk in &mut chunks {
+            chunk.copy_from_slice(&self.next_lanes());
+        }
+        let rest = chunks.into_remainder();
+        for (state, out) in self.states.iter_mut().zip(rest.iter_mut()) {
+            *out = step(state);
+        }
+    }
+}
+
+/// One `Pcg::next_u32` step on a bare state
+#[inline(always)]
+fn step(state: &mut u64) -> u32 {
+    *state = state.wrapping_mul(MULTIPLIER);
+    ((*state ^ (*state >> 22)) >> (22 + (*state >> 61))) as u32
+}
+
+impl PcgX4 {
+    /// Generate one u32 from each of the four lanes.
+    pub fn next_u32x4(&mut self) -> [u32; 4] {
+        self.next_lanes()
+    }
+}
+
+impl PcgX8 {
+    /// Generate one u32 from each of the eight lanes.
+    pub fn next_u32x8(&mut self) -> [u32; 8] {
+        self.next_lanes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::RngCore;
+
+    #[test]
+    fn test_lanes_match_pcg() {
+        let seed = rand::random::<u64>();
+        let mut lanes = PcgX8::new(seed);
+        let mut scalar: Vec<Pcg> = (0..8).map(|i| lanes.lane(i)).collect();
+        assert_eq!(
+            lanes.lane(5).get_state(),
+            Pcg::seed_from_u64(seed).substream(5).get_state()
+        );
+        for _ in 0..100 {
+            let block = lanes.next_u32x8();
+            for (i, pcg) in scalar.iter_mut().enumerate() {
+                assert_eq!(block[i], pcg.next_u32());
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_u32() {
+        let mut lanes = PcgX4::new(rand::random::<u64>());
+        let mut scalar: Vec<Pcg> = (0..4).map(|i| lanes.lane(i)).collect();
+        let mut dest = [0u32; 10];
+        lanes.fill_u32(&mut dest);
+        for (k, &x) in dest.iter().enumerate() {
+            assert_eq!(x, scalar[k % 4].next_u32());
+        }
+        // lanes 0 and 1 moved three steps, lanes 2 and 3 only two
+        assert_eq!(lanes.lane(1).get_state(), scalar[1].get_state());
+        assert_eq!(lanes.lane(3).get_state(), scalar[3].get_state());
+        assert_eq!(lanes.next_u32x4()[3], scalar[3].next_u32());
+    }
+}
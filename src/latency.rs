@@ -0,0 +1,198 @@
+/*! Simulated latency for reproducible performance tests.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::distributions::Empirical;
+ *  # use pcg::latency::LatencyModel;
+ *  # use rand_core::SeedableRng;
+ *  # use std::time::Duration;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let observed_ms = Empirical::from_samples(&[12.0, 15.0, 18.0, 250.0]).unwrap();
+ *  let model = LatencyModel::new(observed_ms, Duration::from_millis(1));
+ *
+ *  let delay = model.sample(&mut pcg);
+ *  assert!(delay >= Duration::from_millis(12));
+ *  ```
+ */
+use crate::distributions::Distribution;
+use rand_core::RngCore;
+use std::time::Duration;
+
+/// Draws latencies from a distribution, expressed in multiples of a unit
+/// duration. Negative draws are treated as zero.
+#[derive(Clone, Debug)]
+pub struct LatencyModel<D> {
+    dist: D,
+    unit: Duration,
+    max: Option<Duration>,
+}
+
+impl<D: Distribution<f64>> LatencyModel<D> {
+    /// Samples of `dist` are interpreted as a number of `unit`s, e.g. a
+    /// log-normal over milliseconds with a unit of one millisecond.
+    pub fn new(dist: D, unit: Duration) -> LatencyModel<D> {
+        LatencyModel {
+            dist,
+            unit,
+            max: None,
+        }
+    }
+
+    /// Caps every latency at `max`, taming heavy-tailed distributions.
+    pub fn with_max(mut self, max: Duration) -> LatencyModel<D> {
+        self.max = Some(max);
+        self
+    }
+
+    /// Draws a latency.
+    pub fn sample<R: RngCore + ?Sized>(&self, rng: &mut R) -> Duration {
+        let units = self.dist.sample(rng);
+        let secs = if units > 0.0 {
+            units * self.unit.as_secs_f64()
+        } else {
+            0.0
+        };
+        let max = self.max.unwrap_or(Duration::MAX);
+        if secs >= max.as_secs_f64() {
+            max
+        } else {
+            Duration::from_secs_f64(secs)
+        }
+    }
+
+    /// Draws a latency and returns a future that completes once it has
+    /// elapsed. The future works with any executor, timing itself on a
+    /// helper thread.
+    #[cfg(feature = "async")]
+    pub fn sleep_jittered<R: RngCore + ?Sized>(&self, rng: &mut R) -> Sleep {
+        Sleep::new(self.sample(rng))
+    }
+}
+
+#[cfg(feature = "async")]
+pub use self::sleep::Sleep;
+
+#[cfg(feature = "async")]
+mod sleep {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Poll, Waker};
+    use std::thread;
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Shared {
+        done: bool,
+        waker: Option<Waker>,
+    }
+
+    /// A future that completes after a duration has elapsed
+    pub struct Sleep {
+        duration: Duration,
+        shared: Option<Arc<Mutex<Shared>>>,
+    }
+
+    impl Sleep {
+        pub(super) fn new(duration: Duration) -> Sleep {
+            Sleep {
+                duration,
+                shared: None,
+            }
+        }
+
+        /// The duration this future waits for.
+        pub fn duration(&self) -> Duration {
+            self.duration
+        }
+    }
+
+    impl Future for Sleep {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.duration == Duration::ZERO {
+                return Poll::Ready(());
+            }
+            let duration = self.duration;
+            let shared = self.shared.get_or_insert_with(|| {
+                let shared = Arc::new(Mutex::new(Shared::default()));
+                let timer = Arc::clone(&shared);
+                thread::spawn(move || {
+                    thread::sleep(duration);
+                    let mut timer = timer.lock().unwrap();
+                    timer.done = true;
+                    if let Some(waker) = timer.waker.take() {
+                        waker.wake();
+                    }
+                });
+                shared
+            });
+            let mut shared = shared.lock().unwrap();
+            if shared.done {
+                Poll::Ready(())
+            } else {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::Empirical;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_sample() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let dist = Empirical::from_samples(&[-1.0, 2.0, 1000.0]).unwrap();
+        let model = LatencyModel::new(dist, Duration::from_millis(1));
+        let capped = model.clone().with_max(Duration::from_millis(100));
+        for _ in 0..100 {
+            let delay = model.sample(&mut pcg);
+            assert!([0, 2, 1000].contains(&delay.as_millis()));
+            assert!(capped.sample(&mut pcg) <= Duration::from_millis(100));
+        }
+
+        let seed = rand::random::<u64>();
+        let a = model.sample(&mut Pcg::seed_from_u64(seed));
+        assert_eq!(a, model.sample(&mut Pcg::seed_from_u64(seed)));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_sleep_jittered() {
+        use std::future::Future;
+        use std::sync::Arc;
+        use std::task::{Context, Poll, Wake};
+        use std::thread::{self, Thread};
+        use std::time::Instant;
+
+        struct Unpark(Thread);
+
+        impl Wake for Unpark {
+            fn wake(self: Arc<Self>) {
+                self.0.unpark();
+            }
+        }
+
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let dist = Empirical::from_samples(&[20.0]).unwrap();
+        let model = LatencyModel::new(dist, Duration::from_millis(1));
+        let mut sleep = Box::pin(model.sleep_jittered(&mut pcg));
+        assert_eq!(sleep.duration(), Duration::from_millis(20));
+
+        let waker = Arc::new(Unpark(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let start = Instant::now();
+        while sleep.as_mut().poll(&mut cx) == Poll::Pending {
+            thread::park();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
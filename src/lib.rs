@@ -11,6 +11,8 @@
  *
  *  # Example use
  *  ```
+ *  # use pcg::Pcg;
+ *  # use rand_core::{RngCore, SeedableRng};
  *  let seed: u64 = 12345; // or any u64 seed, to taste
  *  let mut pcg = Pcg::seed_from_u64(seed);
  *  
@@ -28,52 +30,231 @@ const MULTIPLIER: u64 = 0x1000000000000003;
 const INVERSE: u64 = 0x1AAAAAAAAAAAAAAB;
 const BYTE_LEN: usize = 8;
 
+pub mod analysis;
+pub mod annealing;
+pub mod backoff;
+pub mod bag;
+pub mod checkpoint;
+pub mod choice;
+pub mod color;
+pub mod crn;
+pub mod dag;
+pub mod distributions;
+pub mod dsp;
+pub mod events;
+pub mod fanout;
+pub mod faults;
+pub mod fixtures;
+pub mod frame;
+pub mod functions;
+pub mod genetic;
+pub mod geo;
+pub mod hashing;
+pub mod keys;
+pub mod labels;
+pub mod latency;
+pub mod markov;
+pub mod matrix;
+pub mod maze;
+pub mod migrate;
+pub mod namegen;
+pub mod permutation;
+pub mod polygon;
+pub mod priority;
+pub mod process;
+pub mod rounding;
+pub mod rows;
+pub mod sampling;
+pub mod schedule;
+pub mod search;
+pub mod shedding;
+pub mod shrink;
+pub mod snapshot;
+pub mod text;
+pub mod ticker;
+pub mod tiles;
+pub mod traffic;
+pub mod views;
+pub mod walk;
+pub mod workload;
+
+pub use block::PcgCore;
+pub use dxsm::{Pcg64Dxsm, Pcg64DxsmSeed};
+pub use functions::*;
+pub use lanes::{PcgLanes, PcgX4, PcgX8};
+pub use pcg32::{Pcg32, Pcg32Seed};
+pub use pcg64::{Pcg64, Pcg64Seed};
+pub use rxs_m_xs::PcgRxsMXs;
+pub use uniform::UniformInt;
+pub use xsh_rr::PcgXshRr;
+
+mod block;
+mod dxsm;
+mod lanes;
+mod pcg32;
+mod pcg64;
+mod rxs_m_xs;
+mod uniform;
+mod xsh_rr;
+
 #[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PcgSeed(pub [u8; BYTE_LEN]);
 
 use rand_core::*;
 use std::num::Wrapping;
+use std::ops::Range;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "PcgFields"))]
 pub struct Pcg {
     state: u64,
+    /// State advances since seeding, for draw_count()
+    draws: u64,
+}
+
+/// Pcg's fields as deserialized, before the state is checked
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct PcgFields {
+    state: u64,
+    draws: u64,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<PcgFields> for Pcg {
+    type Error = snapshot::SnapshotError;
+
+    /// Refuses a zero state, as set_state does.
+    fn try_from(fields: PcgFields) -> Result<Pcg, Self::Error> {
+        if fields.state == 0 {
+            return Err(snapshot::SnapshotError::InvalidState);
+        }
+        Ok(Pcg {
+            state: fields.state,
+            draws: fields.draws,
+        })
+    }
 }
 
 impl Pcg {
-    #[cfg(test)]
+    /// The current state. Passing it to set_state, on this or another
+    /// Pcg instance, resumes the sequence from this point.
     pub fn get_state(&self) -> u64 {
         self.state
     }
 
-    /// Advances the state by n steps, as if calling next_u32() n times
-    pub fn skip(&mut self, n: i32) {
-        if n == 0 {
-            return;
+    /// Replaces the state, refusing zero, which no Pcg instance can reach.
+    /// The draw count starts again from zero.
+    pub fn set_state(&mut self, state: u64) -> Result<(), snapshot::SnapshotError> {
+        if state == 0 {
+            return Err(snapshot::SnapshotError::InvalidState);
         }
-        let mut state = Wrapping(self.state);
-        if n > 0 {
-            for _ in 0..n {
-                state *= Wrapping(MULTIPLIER);
+        self.state = state;
+        self.draws = 0;
+        Ok(())
+    }
+
+    /// Advances the state by n steps, as if calling next_u32() n times,
+    /// or rewinds it for negative n. Takes O(log n) time, by raising the
+    /// multiplier (or its inverse) to the nth power by squaring.
+    pub fn skip(&mut self, n: i64) {
+        let base = if n >= 0 { MULTIPLIER } else { INVERSE };
+        let mut exponent = n.unsigned_abs();
+        let mut power = Wrapping(base);
+        let mut factor = Wrapping(1u64);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                factor *= power;
             }
-        } else {
-            for _ in n..0 {
-                state *= Wrapping(INVERSE);
+            power *= power;
+            exponent >>= 1;
+        }
+        self.state = (Wrapping(self.state) * factor).0;
+        self.draws = self.draws.wrapping_add(n as u64);
+    }
+
+    /// Advances the state until the next call to next_u32() will return an
+    /// output satisfying `predicate`, examining at most `max_steps` outputs.
+    /// Returns the number of steps skipped, or None if the cap was hit,
+    /// in which case the state has advanced by `max_steps`.
+    pub fn skip_until<F>(&mut self, mut predicate: F, max_steps: u64) -> Option<u64>
+    where
+        F: FnMut(u32) -> bool,
+    {
+        for steps in 0..max_steps {
+            let mut peek = self.clone();
+            if predicate(peek.next_u32()) {
+                return Some(steps);
             }
+            *self = peek;
         }
-        self.state = state.0;
+        None
+    }
+
+    /// The number of steps the state has advanced since seeding, counting
+    /// backwards for negative skips. Each output of next_u32() is one
+    /// step and each output of next_u64() two, so replays can check that
+    /// two runs consumed the same amount of randomness.
+    pub fn draw_count(&self) -> u64 {
+        self.draws
+    }
+
+    /// Generate a uniformly distributed f64 in [0, 1) from the top 53 bits
+    /// of next_u64(), so every value is a multiple of 2^-53.
+    pub fn next_f64(&mut self) -> f64 {
+        unit_f64(self)
+    }
+
+    /// Generate a uniformly distributed f32 in [0, 1) from the top 24 bits
+    /// of next_u32(), so every value is a multiple of 2^-24.
+    pub fn next_f32(&mut self) -> f32 {
+        unit_f32(self)
+    }
+
+    /// Generate a u32 uniformly distributed in `range`, without the
+    /// modulo bias of `next_u32() % n`. Panics if the range is empty.
+    pub fn gen_range_u32(&mut self, range: Range<u32>) -> u32 {
+        assert!(range.start < range.end, "empty range");
+        range.start + bounded_u32(self, range.end - range.start)
+    }
+
+    /// Generate a u64 uniformly distributed in `range`, without modulo
+    /// bias. Panics if the range is empty.
+    pub fn gen_range_u64(&mut self, range: Range<u64>) -> u64 {
+        assert!(range.start < range.end, "empty range");
+        range.start + bounded_u64(self, range.end - range.start)
+    }
+
+    /// Creates a Pcg instance seeded from the operating system's entropy
+    /// source, for when runs need not be reproducible. Panics if the
+    /// source fails, which is rare outside of early boot.
+    #[cfg(feature = "getrandom")]
+    pub fn from_entropy() -> Pcg {
+        <Pcg as SeedableRng>::from_entropy()
     }
 
     /// Creates a new Pcg instance with a unique state seeded from the
-    /// output of this Pcg instance.
+    /// output of this Pcg instance. The new sequence may overlap this
+    /// one; Pcg32 offers streams that are distinct by construction.
     pub fn new_stream(&mut self) -> Pcg {
         Self::seed_from_u64(self.next_u64())
     }
+
+    /// Derives a new Pcg instance from the current state and a label,
+    /// without advancing this Pcg instance. The same state and label
+    /// always produce the same substream.
+    pub fn substream(&self, label: u64) -> Pcg {
+        Self::seed_from_u64(mix64(self.state ^ mix64(label)))
+    }
 }
 
 impl RngCore for Pcg {
     /// Generate a random u32, advancing the state one step.
     fn next_u32(&mut self) -> u32 {
         self.state = (Wrapping(self.state) * Wrapping(MULTIPLIER)).0;
+        self.draws = self.draws.wrapping_add(1);
         ((self.state ^ (self.state >> 22)) >> (22 + (self.state >> 61))) as u32
     }
 
@@ -83,12 +264,29 @@ impl RngCore for Pcg {
         ((self.next_u32() as u64) << 32) ^ (self.next_u32() as u64)
     }
 
+    /// Fill dest with random bytes, writing each 32-bit output directly.
+    /// The bytes are the same as those of next_u64() in little-endian
+    /// order, which puts the second output of each pair first.
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        impls::fill_bytes_via_next(self, dest)
+        let mut chunks = dest.chunks_exact_mut(8);
+        for chunk in &mut chunks {
+            let high = self.next_u32();
+            chunk[4..].copy_from_slice(&high.to_le_bytes());
+            chunk[..4].copy_from_slice(&self.next_u32().to_le_bytes());
+        }
+        let rest = chunks.into_remainder();
+        if rest.len() > 4 {
+            let bytes = self.next_u64().to_le_bytes();
+            rest.copy_from_slice(&bytes[..rest.len()]);
+        } else if !rest.is_empty() {
+            let bytes = self.next_u32().to_le_bytes();
+            rest.copy_from_slice(&bytes[..rest.len()]);
+        }
     }
 
     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
-        Ok(self.fill_bytes(dest))
+        self.fill_bytes(dest);
+        Ok(())
     }
 }
 
@@ -102,6 +300,7 @@ impl SeedableRng for Pcg {
     fn seed_from_u64(seed: u64) -> Self {
         Self {
             state: if seed == 0 { 1 } else { seed }, // must not have zero as state
+            draws: 0,
         }
     }
 }
@@ -112,6 +311,70 @@ impl AsMut<[u8]> for PcgSeed {
     }
 }
 
+/// Generates a u32 in [0, n) without modulo bias, using Lemire's
+/// multiply-shift rejection method. n must not be zero.
+pub(crate) fn bounded_u32<R: RngCore + ?Sized>(rng: &mut R, n: u32) -> u32 {
+    let mut m = rng.next_u32() as u64 * n as u64;
+    if (m as u32) < n {
+        let threshold = n.wrapping_neg() % n;
+        while (m as u32) < threshold {
+            m = rng.next_u32() as u64 * n as u64;
+        }
+    }
+    (m >> 32) as u32
+}
+
+/// Generates a u64 in [0, n) without modulo bias, using Lemire's
+/// multiply-shift rejection method. n must not be zero.
+pub(crate) fn bounded_u64<R: RngCore + ?Sized>(rng: &mut R, n: u64) -> u64 {
+    let mut m = rng.next_u64() as u128 * n as u128;
+    if (m as u64) < n {
+        let threshold = n.wrapping_neg() % n;
+        while (m as u64) < threshold {
+            m = rng.next_u64() as u128 * n as u128;
+        }
+    }
+    (m >> 64) as u64
+}
+
+/// Generates an index in [0, n), drawing only 32 bits when n allows it.
+pub(crate) fn bounded_usize<R: RngCore + ?Sized>(rng: &mut R, n: usize) -> usize {
+    if n as u64 <= u32::MAX as u64 {
+        bounded_u32(rng, n as u32) as usize
+    } else {
+        bounded_u64(rng, n as u64) as usize
+    }
+}
+
+/// Generates a uniformly distributed f64 in [0, 1) from 53 random bits.
+pub(crate) fn unit_f64<R: RngCore + ?Sized>(rng: &mut R) -> f64 {
+    (rng.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+/// Generates a uniformly distributed f32 in [0, 1) from 24 random bits.
+pub(crate) fn unit_f32<R: RngCore + ?Sized>(rng: &mut R) -> f32 {
+    (rng.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32)
+}
+
+/// The splitmix64 finalizer, used to derive seeds from labels
+pub(crate) fn mix64(mut z: u64) -> u64 {
+    z = (Wrapping(z ^ (z >> 30)) * Wrapping(0xBF58476D1CE4E5B9)).0;
+    z = (Wrapping(z ^ (z >> 27)) * Wrapping(0x94D049BB133111EB)).0;
+    z ^ (z >> 31)
+}
+
+/// 64-bit FNV-1a, a stable hash for turning string labels into u64 labels
+pub(crate) const fn hash_label(label: &str) -> u64 {
+    let bytes = label.as_bytes();
+    let mut hash: u64 = 0xCBF29CE484222325;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash = (hash ^ bytes[i] as u64).wrapping_mul(0x100000001B3);
+        i += 1;
+    }
+    hash
+}
+
 fn arr_to_u64(mut arr: PcgSeed) -> u64 {
     let mut seed: u64 = 0;
     let mutarr = PcgSeed::as_mut(&mut arr);
@@ -139,11 +402,73 @@ mod tests {
         assert_eq!(pcg.get_state(), seed);
     }
 
+    #[test]
+    #[cfg(feature = "getrandom")]
+    fn test_from_entropy() {
+        let mut a = Pcg::from_entropy();
+        let mut b = Pcg::from_entropy();
+        assert_ne!(a.next_u64(), b.next_u64());
+        assert_eq!(a.draw_count(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        pcg.next_u64();
+        let json = serde_json::to_string(&pcg).unwrap();
+        let mut restored: Pcg = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.draw_count(), 2);
+        for _ in 0..100 {
+            assert_eq!(restored.next_u32(), pcg.next_u32());
+        }
+        assert!(serde_json::from_str::<Pcg>(r#"{"state":0,"draws":0}"#).is_err());
+
+        let seed = PcgSeed(rand::random::<u64>().to_le_bytes());
+        let json = serde_json::to_string(&seed).unwrap();
+        let restored: PcgSeed = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.0, seed.0);
+    }
+
+    #[test]
+    fn test_set_state() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let state = pcg.get_state();
+        let x = pcg.next_u64();
+
+        let mut other = Pcg::seed_from_u64(rand::random::<u64>());
+        other.next_u32();
+        other.set_state(state).unwrap();
+        assert_eq!(other.draw_count(), 0);
+        assert_eq!(other.next_u64(), x);
+        assert_eq!(
+            other.set_state(0),
+            Err(snapshot::SnapshotError::InvalidState)
+        );
+        assert_eq!(other.get_state(), pcg.get_state());
+    }
+
+    #[test]
+    fn test_fill_bytes_lengths() {
+        // the bytes must not change with the implementation
+        let seed = rand::random::<u64>();
+        for len in 0..40 {
+            let mut native = vec![0; len];
+            let mut pcg = Pcg::seed_from_u64(seed);
+            pcg.fill_bytes(&mut native);
+            let mut expected = vec![0; len];
+            let mut reference = Pcg::seed_from_u64(seed);
+            impls::fill_bytes_via_next(&mut reference, &mut expected);
+            assert_eq!(native, expected);
+            assert_eq!(pcg.draw_count(), reference.draw_count());
+        }
+    }
+
     #[test]
     fn test_next_u64() {
         let seed = rand::random::<u64>();
         let mut state = (Wrapping(seed) * Wrapping(MULTIPLIER)).0;
-        let mut next: u64 = (state ^ (state >> 22)) >> (22 + (state >> 61)) << 32;
+        let mut next: u64 = ((state ^ (state >> 22)) >> (22 + (state >> 61))) << 32;
         state = (Wrapping(state) * Wrapping(MULTIPLIER)).0;
         next ^= ((state ^ (state >> 22)) >> (22 + (state >> 61))) & 0xFFFFFFFF;
 
@@ -170,10 +495,8 @@ mod tests {
         let secondnext = (secondstate ^ (secondstate >> 22)) >> (22 + (secondstate >> 61));
         let mut next_eight_expected_bytes = [0; 8];
         for i in 0..4 {
-            next_eight_expected_bytes[i + 4] = ((next >> 8 * i) % 256) as u8;
-        }
-        for i in 0..4 {
-            next_eight_expected_bytes[i] = ((secondnext >> 8 * i) % 256) as u8;
+            next_eight_expected_bytes[i + 4] = ((next >> (8 * i)) % 256) as u8;
+            next_eight_expected_bytes[i] = ((secondnext >> (8 * i)) % 256) as u8;
         }
 
         let mut arr = [0; 8];
@@ -202,11 +525,42 @@ mod tests {
         let seed = rand::random::<u64>();
         let skips = rand::random::<i8>();
         let mut pcg = Pcg::seed_from_u64(seed);
-        pcg.skip(skips as i32);
-        pcg.skip(-skips as i32);
+        pcg.skip(skips as i64);
+        pcg.skip(-(skips as i64));
         assert_eq!(pcg.get_state(), seed);
     }
 
+    #[test]
+    fn test_skip_matches_stepping() {
+        let seed = rand::random::<u64>();
+        let steps = rand::random::<u16>();
+        let mut stepped = Pcg::seed_from_u64(seed);
+        for _ in 0..steps {
+            stepped.next_u32();
+        }
+        let mut pcg = Pcg::seed_from_u64(seed);
+        pcg.skip(steps as i64);
+        assert_eq!(pcg.get_state(), stepped.get_state());
+        assert_eq!(pcg.draw_count(), steps as u64);
+    }
+
+    #[test]
+    fn test_skip_far() {
+        let seed = rand::random::<u64>();
+        let mut pcg = Pcg::seed_from_u64(seed);
+        pcg.skip(i64::MAX);
+        pcg.skip(-i64::MAX);
+        assert_eq!(pcg.get_state(), seed);
+        pcg.skip(i64::MIN);
+        pcg.skip(1 << 62);
+        pcg.skip(1 << 62);
+        assert_eq!(pcg.get_state(), seed);
+        // the period is 2^62
+        let mut pcg = Pcg::seed_from_u64(seed | 1);
+        pcg.skip(1 << 62);
+        assert_eq!(pcg.get_state(), seed | 1);
+    }
+
     #[test]
     fn test_no_zeroes_in_state() {
         let mut pcg = Pcg::seed_from_u64(0);
@@ -238,4 +592,105 @@ mod tests {
         let next = ((state ^ (state >> 22)) >> (22 + (state >> 61))) as u32;
         assert_eq!(child.next_u32(), next);
     }
+
+    #[test]
+    fn test_substream() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let label = rand::random::<u64>();
+        let state = pcg.get_state();
+        let first = pcg.substream(label).next_u64();
+        assert_eq!(pcg.substream(label).next_u64(), first);
+        assert_eq!(pcg.get_state(), state);
+        assert_ne!(pcg.substream(!label).next_u64(), first);
+        pcg.skip(1);
+        assert_ne!(pcg.substream(label).next_u64(), first);
+    }
+
+    #[test]
+    fn test_skip_until() {
+        let seed = rand::random::<u64>();
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let steps = pcg.skip_until(|x| x % 16 == 0, 10000).unwrap();
+        let x = pcg.next_u32();
+        assert_eq!(x % 16, 0);
+
+        let mut expected = Pcg::seed_from_u64(seed);
+        expected.skip(steps as i64);
+        assert_eq!(expected.next_u32(), x);
+        let mut skipped = Pcg::seed_from_u64(seed);
+        for _ in 0..steps {
+            assert_ne!(skipped.next_u32() % 16, 0);
+        }
+
+        let mut capped = Pcg::seed_from_u64(seed);
+        assert_eq!(capped.skip_until(|_| false, 5), None);
+        expected = Pcg::seed_from_u64(seed);
+        expected.skip(5);
+        assert_eq!(capped.get_state(), expected.get_state());
+    }
+
+    #[test]
+    fn test_draw_count() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert_eq!(pcg.draw_count(), 0);
+        pcg.next_u32();
+        pcg.next_u64();
+        pcg.fill_bytes(&mut [0; 10]);
+        assert_eq!(pcg.draw_count(), 6);
+        pcg.skip(10);
+        pcg.skip(-4);
+        assert_eq!(pcg.draw_count(), 12);
+        let steps = pcg.skip_until(|x| x % 4 == 0, 1000).unwrap();
+        assert_eq!(pcg.draw_count(), 12 + steps);
+        assert_eq!(pcg.clone().draw_count(), pcg.draw_count());
+        assert_eq!(pcg.new_stream().draw_count(), 0);
+        assert_eq!(pcg.draw_count(), 14 + steps);
+    }
+
+    #[test]
+    fn test_next_float() {
+        let seed = rand::random::<u64>();
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut check = Pcg::seed_from_u64(seed);
+        let x = pcg.next_f64();
+        assert_eq!(x, (check.next_u64() >> 11) as f64 / (1u64 << 53) as f64);
+        let y = pcg.next_f32();
+        assert_eq!(y, (check.next_u32() >> 8) as f32 / (1u32 << 24) as f32);
+
+        let mut sum = 0.0;
+        for _ in 0..10000 {
+            let x = pcg.next_f64();
+            assert!((0.0..1.0).contains(&x));
+            assert!((0.0..1.0).contains(&pcg.next_f32()));
+            sum += x;
+        }
+        assert!((sum / 10000.0 - 0.5).abs() < 0.02);
+    }
+
+    #[test]
+    fn test_gen_range() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut counts = [0; 3];
+        for _ in 0..30000 {
+            let x = pcg.gen_range_u32(10..13);
+            counts[(x - 10) as usize] += 1;
+            let y = pcg.gen_range_u64(u64::MAX - 5..u64::MAX);
+            assert!((u64::MAX - 5..u64::MAX).contains(&y));
+        }
+        assert!(counts.iter().all(|&c| (9500..10500).contains(&c)));
+        assert_eq!(pcg.gen_range_u32(7..8), 7);
+        assert!(pcg.gen_range_u64(0..u64::MAX) < u64::MAX);
+    }
+
+    #[test]
+    fn test_bounded() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut seen = [false; 7];
+        for _ in 0..1000 {
+            seen[bounded_u32(&mut pcg, 7) as usize] = true;
+            assert!(bounded_u64(&mut pcg, 1 << 40) < 1 << 40);
+            assert_eq!(bounded_usize(&mut pcg, 1), 0);
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
 }
@@ -0,0 +1,186 @@
+/*! Order-k Markov models of text, for generating realistic-looking but
+ *  reproducible test text from a sample corpus.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::markov::MarkovText;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut model = MarkovText::words(1);
+ *  model.train("the cat sat on the mat");
+ *  model.train("the dog sat on the cat");
+ *
+ *  let text = model.generate(&mut pcg, 20);
+ *  assert!(text.starts_with("the "));
+ *  ```
+ */
+use crate::bounded_u64;
+use rand_core::RngCore;
+use std::collections::HashMap;
+
+/// Whether a model works on characters or on whitespace-separated words
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Unit {
+    Chars,
+    Words,
+}
+
+/// The preceding tokens, with None before the start of a text
+type Context = Vec<Option<String>>;
+
+/// A Markov model predicting each token from the `order` before it
+#[derive(Clone, Debug)]
+pub struct MarkovText {
+    order: usize,
+    unit: Unit,
+    /// Successors of each context with their counts, in order of first
+    /// appearance, so that sampling does not depend on hash order. None
+    /// marks the end of a text.
+    successors: HashMap<Context, Vec<(Option<String>, u64)>>,
+}
+
+impl MarkovText {
+    /// A model over characters, predicting each from the `order` before it.
+    pub fn chars(order: usize) -> MarkovText {
+        MarkovText::new(order, Unit::Chars)
+    }
+
+    /// A model over words, predicting each from the `order` before it.
+    /// Generated words are joined by single spaces.
+    pub fn words(order: usize) -> MarkovText {
+        MarkovText::new(order, Unit::Words)
+    }
+
+    fn new(order: usize, unit: Unit) -> MarkovText {
+        MarkovText {
+            order,
+            unit,
+            successors: HashMap::new(),
+        }
+    }
+
+    fn tokens(&self, text: &str) -> Vec<String> {
+        match self.unit {
+            Unit::Chars => text.chars().map(String::from).collect(),
+            Unit::Words => text.split_whitespace().map(String::from).collect(),
+        }
+    }
+
+    /// Adds a text to the model. Each text is treated as a whole, so that
+    /// generated text starts and ends the way training texts do.
+    pub fn train(&mut self, text: &str) {
+        let mut context: Context = vec![None; self.order];
+        let tokens = self.tokens(text);
+        for next in tokens.into_iter().map(Some).chain(std::iter::once(None)) {
+            let successors = self.successors.entry(context.clone()).or_default();
+            match successors.iter_mut().find(|(token, _)| *token == next) {
+                Some((_, count)) => *count += 1,
+                None => successors.push((next.clone(), 1)),
+            }
+            if self.order > 0 {
+                context.remove(0);
+                context.push(next);
+            }
+        }
+    }
+
+    /// Generates a text of at most `max_tokens` tokens, stopping early
+    /// where a training text ended. Returns an empty string if the model
+    /// has not been trained.
+    pub fn generate<R: RngCore + ?Sized>(&self, rng: &mut R, max_tokens: usize) -> String {
+        let mut context: Context = vec![None; self.order];
+        let mut tokens = Vec::new();
+        while tokens.len() < max_tokens {
+            let successors = match self.successors.get(&context) {
+                Some(successors) => successors,
+                None => break,
+            };
+            let total: u64 = successors.iter().map(|(_, count)| count).sum();
+            let mut target = bounded_u64(rng, total);
+            let mut next = &None;
+            for (token, count) in successors {
+                if target < *count {
+                    next = token;
+                    break;
+                }
+                target -= count;
+            }
+            let token = match next {
+                Some(token) => token.clone(),
+                None => break,
+            };
+            if self.order > 0 {
+                context.remove(0);
+                context.push(Some(token.clone()));
+            }
+            tokens.push(token);
+        }
+        match self.unit {
+            Unit::Chars => tokens.concat(),
+            Unit::Words => tokens.join(" "),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_chars() {
+        let mut model = MarkovText::chars(2);
+        model.train("abcabd");
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..100 {
+            let text = model.generate(&mut pcg, 100);
+            assert!(text.starts_with("ab"));
+            // every trigram of the output occurs in the training text
+            let chars: Vec<char> = text.chars().collect();
+            for w in chars.windows(3) {
+                let trigram: String = w.iter().collect();
+                assert!("abcabd".contains(&trigram));
+            }
+        }
+    }
+
+    #[test]
+    fn test_words() {
+        let mut model = MarkovText::words(1);
+        model.train("one two three");
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert_eq!(model.generate(&mut pcg, 10), "one two three");
+        assert_eq!(model.generate(&mut pcg, 2), "one two");
+        model.train("one four");
+        let mut seen = Vec::new();
+        for _ in 0..100 {
+            let text = model.generate(&mut pcg, 10);
+            if !seen.contains(&text) {
+                seen.push(text);
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, vec!["one four", "one two three"]);
+    }
+
+    #[test]
+    fn test_untrained_and_order_zero() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert_eq!(MarkovText::chars(3).generate(&mut pcg, 10), "");
+        let mut model = MarkovText::chars(0);
+        model.train("aab");
+        let text = model.generate(&mut pcg, 1000);
+        assert!(text.chars().all(|c| c == 'a' || c == 'b'));
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let mut model = MarkovText::words(2);
+        model.train("a b c a b d a b c d a c b a");
+        let seed = rand::random::<u64>();
+        let a = model.generate(&mut Pcg::seed_from_u64(seed), 50);
+        assert_eq!(a, model.generate(&mut Pcg::seed_from_u64(seed), 50));
+    }
+}
@@ -0,0 +1,224 @@
+/*! Random perfect mazes on a grid, for procedural generation.
+ *
+ *  A perfect maze has exactly one path between any two cells. Each cell
+ *  holds a bitmask of its walls, using `NORTH`, `EAST`, `SOUTH` and
+ *  `WEST`, with north towards row 0. The outer boundary is always walled.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::maze::{recursive_backtracker, NORTH, WEST};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let maze = recursive_backtracker(&mut pcg, 16, 8);
+ *
+ *  assert!(maze.has_wall(0, 0, NORTH | WEST));
+ *  ```
+ */
+use crate::bounded_usize;
+use crate::permutation::shuffle;
+use rand_core::RngCore;
+
+pub const NORTH: u8 = 1;
+pub const EAST: u8 = 2;
+pub const SOUTH: u8 = 4;
+pub const WEST: u8 = 8;
+
+/// A grid of cells, each with a bitmask of its walls
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Maze {
+    width: usize,
+    height: usize,
+    walls: Vec<u8>,
+}
+
+impl Maze {
+    /// A grid with every wall standing
+    fn closed(width: usize, height: usize) -> Maze {
+        assert!(width > 0 && height > 0, "maze must have cells");
+        Maze {
+            width,
+            height,
+            walls: vec![NORTH | EAST | SOUTH | WEST; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The walls of the cell in column x of row y.
+    pub fn walls(&self, x: usize, y: usize) -> u8 {
+        self.walls[y * self.width + x]
+    }
+
+    /// Whether the cell has all of the given walls.
+    pub fn has_wall(&self, x: usize, y: usize, walls: u8) -> bool {
+        self.walls(x, y) & walls == walls
+    }
+
+    /// The walls of every cell, row by row.
+    pub fn cells(&self) -> &[u8] {
+        &self.walls
+    }
+
+    /// The neighbour of cell i through the wall `dir`, and the wall
+    /// facing back, if there is one inside the grid
+    fn neighbour(&self, i: usize, dir: u8) -> Option<(usize, u8)> {
+        let (x, y) = (i % self.width, i / self.width);
+        match dir {
+            NORTH if y > 0 => Some((i - self.width, SOUTH)),
+            EAST if x + 1 < self.width => Some((i + 1, WEST)),
+            SOUTH if y + 1 < self.height => Some((i + self.width, NORTH)),
+            WEST if x > 0 => Some((i - 1, EAST)),
+            _ => None,
+        }
+    }
+
+    fn open(&mut self, i: usize, dir: u8, j: usize, back: u8) {
+        self.walls[i] &= !dir;
+        self.walls[j] &= !back;
+    }
+}
+
+/// Generates a maze by a randomized depth-first search, which gives long
+/// winding corridors with few branches.
+pub fn recursive_backtracker<R: RngCore + ?Sized>(
+    rng: &mut R,
+    width: usize,
+    height: usize,
+) -> Maze {
+    let mut maze = Maze::closed(width, height);
+    let mut visited = vec![false; width * height];
+    let start = bounded_usize(rng, width * height);
+    visited[start] = true;
+    // an explicit stack, since recursion would overflow on large grids
+    let mut stack = vec![start];
+    while let Some(&i) = stack.last() {
+        let mut options = [(0, 0, 0); 4];
+        let mut count = 0;
+        for &dir in &[NORTH, EAST, SOUTH, WEST] {
+            if let Some((j, back)) = maze.neighbour(i, dir) {
+                if !visited[j] {
+                    options[count] = (dir, j, back);
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            stack.pop();
+            continue;
+        }
+        let (dir, j, back) = options[bounded_usize(rng, count)];
+        maze.open(i, dir, j, back);
+        visited[j] = true;
+        stack.push(j);
+    }
+    maze
+}
+
+/// Generates a maze by randomized Kruskal's algorithm, knocking down
+/// walls in random order unless they separate already connected cells,
+/// which gives many short dead ends.
+pub fn kruskal<R: RngCore + ?Sized>(rng: &mut R, width: usize, height: usize) -> Maze {
+    let mut maze = Maze::closed(width, height);
+    let mut walls = Vec::with_capacity(2 * width * height);
+    for i in 0..width * height {
+        for &dir in &[EAST, SOUTH] {
+            if maze.neighbour(i, dir).is_some() {
+                walls.push((i, dir));
+            }
+        }
+    }
+    shuffle(rng, &mut walls);
+    let mut parent: Vec<usize> = (0..width * height).collect();
+    for (i, dir) in walls {
+        let (j, back) = maze.neighbour(i, dir).unwrap();
+        let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+        if a != b {
+            parent[a] = b;
+            maze.open(i, dir, j, back);
+        }
+    }
+    maze
+}
+
+/// Union-find root lookup, with path halving
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    /// Checks that walls agree between neighbours, the boundary is
+    /// closed, and the open passages form a spanning tree.
+    fn assert_perfect(maze: &Maze) {
+        let (w, h) = (maze.width(), maze.height());
+        let mut passages = 0;
+        for i in 0..w * h {
+            for &dir in &[NORTH, EAST, SOUTH, WEST] {
+                match maze.neighbour(i, dir) {
+                    Some((j, back)) => {
+                        assert_eq!(maze.walls[i] & dir == 0, maze.walls[j] & back == 0);
+                        if maze.walls[i] & dir == 0 {
+                            passages += 1;
+                        }
+                    }
+                    None => assert!(maze.walls[i] & dir != 0),
+                }
+            }
+        }
+        assert_eq!(passages, 2 * (w * h - 1));
+        let mut seen = vec![false; w * h];
+        let mut stack = vec![0];
+        seen[0] = true;
+        while let Some(i) = stack.pop() {
+            for &dir in &[NORTH, EAST, SOUTH, WEST] {
+                if maze.walls[i] & dir == 0 {
+                    let (j, _) = maze.neighbour(i, dir).unwrap();
+                    if !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        assert!(seen.iter().all(|&s| s));
+    }
+
+    #[test]
+    fn test_recursive_backtracker() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for &(w, h) in &[(1, 1), (1, 7), (5, 5), (30, 20)] {
+            assert_perfect(&recursive_backtracker(&mut pcg, w, h));
+        }
+        assert_perfect(&recursive_backtracker(&mut pcg, 500, 500));
+    }
+
+    #[test]
+    fn test_kruskal() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for &(w, h) in &[(1, 1), (7, 1), (5, 5), (30, 20)] {
+            assert_perfect(&kruskal(&mut pcg, w, h));
+        }
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seed = rand::random::<u64>();
+        let a = kruskal(&mut Pcg::seed_from_u64(seed), 10, 10);
+        assert_eq!(a, kruskal(&mut Pcg::seed_from_u64(seed), 10, 10));
+    }
+}
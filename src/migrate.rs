@@ -0,0 +1,208 @@
+/*! Moving saved generator positions from `Pcg` to another PCG variant.
+ *
+ *  Save files often record a generator as the seed it started from and
+ *  the number of draws made since. `legacy_at` recovers the `Pcg` at that
+ *  position, and `migrate` puts a newly chosen variant, seeded the same
+ *  way, at the corresponding position, so a game can switch algorithms
+ *  and keep counting draws as before. The values drawn from then on
+ *  differ, of course; where old saves must replay exactly, `CompatRng`
+ *  keeps them on `Pcg` while new ones use the new variant.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::migrate::{legacy_at, migrate, CompatRng};
+ *  # use pcg::PcgXshRr;
+ *  # use rand_core::RngCore;
+ *  // an old save: seed 12345, 1000 draws in
+ *  let mut old = legacy_at(12345, 1000);
+ *  let mut shim = CompatRng::<PcgXshRr>::legacy(12345, 1000);
+ *  assert_eq!(shim.next_u32(), old.next_u32());
+ *
+ *  // a new save carries on with the new variant
+ *  let mut new: PcgXshRr = migrate(12345, 1000).unwrap();
+ *  let mut shim = CompatRng::<PcgXshRr>::migrated(12345, 1000).unwrap();
+ *  assert_eq!(shim.next_u32(), new.next_u32());
+ *  ```
+ */
+use crate::{Pcg, Pcg32, Pcg64, Pcg64Dxsm, PcgRxsMXs, PcgXshRr};
+use rand_core::{Error, RngCore, SeedableRng};
+
+/// Variants a `Pcg` position can be carried over to
+pub trait MigrationTarget: RngCore + Sized {
+    /// Seeds the generator with `seed` and advances it past as many
+    /// values as `draws` u32 draws from `Pcg` would consume, or returns
+    /// None if no position corresponds, such as an odd number of draws
+    /// for a generator producing 64 bits per step.
+    fn at_position(seed: u64, draws: u64) -> Option<Self>;
+}
+
+impl MigrationTarget for PcgXshRr {
+    fn at_position(seed: u64, draws: u64) -> Option<PcgXshRr> {
+        // the period divides 2^64, so skipping by draws as an i64 lands
+        // in the same place even when the cast wraps
+        let mut rng = PcgXshRr::seed_from_u64(seed);
+        rng.skip(draws as i64);
+        Some(rng)
+    }
+}
+
+impl MigrationTarget for Pcg32 {
+    fn at_position(seed: u64, draws: u64) -> Option<Pcg32> {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        rng.skip(draws as i64);
+        Some(rng)
+    }
+}
+
+impl MigrationTarget for PcgRxsMXs {
+    fn at_position(seed: u64, draws: u64) -> Option<PcgRxsMXs> {
+        let mut rng = PcgRxsMXs::seed_from_u64(seed);
+        rng.skip(half(draws)?);
+        Some(rng)
+    }
+}
+
+impl MigrationTarget for Pcg64 {
+    fn at_position(seed: u64, draws: u64) -> Option<Pcg64> {
+        let mut rng = Pcg64::seed_from_u64(seed);
+        rng.skip(half(draws)?);
+        Some(rng)
+    }
+}
+
+impl MigrationTarget for Pcg64Dxsm {
+    fn at_position(seed: u64, draws: u64) -> Option<Pcg64Dxsm> {
+        let mut rng = Pcg64Dxsm::seed_from_u64(seed);
+        rng.skip(half(draws)?);
+        Some(rng)
+    }
+}
+
+/// The number of 64-bit steps covering `draws` 32-bit draws, if whole
+fn half(draws: u64) -> Option<i64> {
+    if draws & 1 == 0 {
+        Some((draws / 2) as i64)
+    } else {
+        None
+    }
+}
+
+/// The `Pcg` seeded with `seed` after `draws` u32 draws (each u64 draw
+/// counting as two), as recorded by `draw_count`.
+pub fn legacy_at(seed: u64, draws: u64) -> Pcg {
+    let mut pcg = Pcg::seed_from_u64(seed);
+    pcg.skip(draws as i64);
+    pcg
+}
+
+/// The variant `T` seeded with `seed` at the position corresponding to
+/// `draws` u32 draws from `Pcg`, where one exists.
+pub fn migrate<T: MigrationTarget>(seed: u64, draws: u64) -> Option<T> {
+    T::at_position(seed, draws)
+}
+
+/// A generator that is either still the legacy `Pcg`, for saves that
+/// must replay exactly, or already migrated to `T`
+#[derive(Clone)]
+pub enum CompatRng<T> {
+    Legacy(Pcg),
+    Migrated(T),
+}
+
+impl<T: MigrationTarget> CompatRng<T> {
+    /// Resumes an old save on `Pcg`.
+    pub fn legacy(seed: u64, draws: u64) -> CompatRng<T> {
+        CompatRng::Legacy(legacy_at(seed, draws))
+    }
+
+    /// Resumes a save on `T`, at the position `migrate` gives.
+    pub fn migrated(seed: u64, draws: u64) -> Option<CompatRng<T>> {
+        migrate(seed, draws).map(CompatRng::Migrated)
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, CompatRng::Legacy(_))
+    }
+}
+
+impl<T: RngCore> RngCore for CompatRng<T> {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            CompatRng::Legacy(pcg) => pcg.next_u32(),
+            CompatRng::Migrated(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            CompatRng::Legacy(pcg) => pcg.next_u64(),
+            CompatRng::Migrated(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            CompatRng::Legacy(pcg) => pcg.fill_bytes(dest),
+            CompatRng::Migrated(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        match self {
+            CompatRng::Legacy(pcg) => pcg.try_fill_bytes(dest),
+            CompatRng::Migrated(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_at() {
+        let seed = rand::random::<u64>();
+        let mut pcg = Pcg::seed_from_u64(seed);
+        for _ in 0..100 {
+            pcg.next_u64();
+        }
+        let mut legacy = legacy_at(seed, 200);
+        assert_eq!(legacy.draw_count(), 200);
+        assert_eq!(legacy.next_u32(), pcg.next_u32());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let seed = rand::random::<u64>();
+        let mut rr = PcgXshRr::seed_from_u64(seed);
+        let mut wide = Pcg64::seed_from_u64(seed);
+        for _ in 0..10 {
+            rr.next_u64();
+            wide.next_u64();
+        }
+        let mut migrated: PcgXshRr = migrate(seed, 20).unwrap();
+        assert_eq!(migrated.next_u32(), rr.next_u32());
+        let mut migrated: Pcg64 = migrate(seed, 20).unwrap();
+        assert_eq!(migrated.next_u64(), wide.next_u64());
+
+        assert!(migrate::<Pcg64>(seed, 21).is_none());
+        assert!(migrate::<PcgRxsMXs>(seed, 21).is_none());
+        assert!(migrate::<Pcg64Dxsm>(seed, 21).is_none());
+        assert!(migrate::<Pcg32>(seed, 21).is_some());
+        assert!(migrate::<Pcg32>(seed, u64::MAX).is_some());
+    }
+
+    #[test]
+    fn test_compat_rng() {
+        let seed = rand::random::<u64>();
+        let mut shim = CompatRng::<Pcg32>::legacy(seed, 7);
+        assert!(shim.is_legacy());
+        assert_eq!(shim.next_u64(), legacy_at(seed, 7).next_u64());
+
+        let mut shim = CompatRng::<Pcg32>::migrated(seed, 7).unwrap();
+        assert!(!shim.is_legacy());
+        let mut expected: Pcg32 = migrate(seed, 7).unwrap();
+        assert_eq!(shim.next_u64(), expected.next_u64());
+        assert!(CompatRng::<Pcg64>::migrated(seed, 7).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+/*! Pronounceable names built from syllable templates, for naming NPCs,
+ *  planets and the like.
+ *
+ *  A template is a string of class letters and literals: each class
+ *  letter is replaced by a random syllable from that class, and any
+ *  other character is copied as it is.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::namegen::NameGen;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let names = NameGen::new()
+ *      .with_class('C', &["k", "t", "r", "th", "s"])
+ *      .with_class('V', &["a", "o", "ei", "u"])
+ *      .with_pattern("CVC-CV")
+ *      .with_pattern("VCV")
+ *      .capitalized();
+ *
+ *  let name = names.generate(&mut pcg);
+ *  assert!(name.chars().next().unwrap().is_uppercase());
+ *  ```
+ */
+use crate::bounded_usize;
+use rand_core::RngCore;
+
+/// Generates names from syllable classes and templates
+#[derive(Clone, Debug, Default)]
+pub struct NameGen {
+    classes: Vec<(char, Vec<String>)>,
+    patterns: Vec<Vec<char>>,
+    capitalized: bool,
+}
+
+impl NameGen {
+    pub fn new() -> NameGen {
+        NameGen::default()
+    }
+
+    /// Defines the syllables that `letter` stands for in templates,
+    /// replacing any earlier definition.
+    pub fn with_class(mut self, letter: char, syllables: &[&str]) -> NameGen {
+        assert!(!syllables.is_empty(), "a class needs syllables");
+        let syllables = syllables.iter().map(|s| s.to_string()).collect();
+        self.classes.retain(|(l, _)| *l != letter);
+        self.classes.push((letter, syllables));
+        self
+    }
+
+    /// Adds a template; each name uses one, chosen uniformly.
+    pub fn with_pattern(mut self, pattern: &str) -> NameGen {
+        self.patterns.push(pattern.chars().collect());
+        self
+    }
+
+    /// Capitalizes the first letter of each word of generated names,
+    /// where words are separated by anything other than letters.
+    pub fn capitalized(mut self) -> NameGen {
+        self.capitalized = true;
+        self
+    }
+
+    /// Generates a name. At least one template must have been added.
+    pub fn generate<R: RngCore + ?Sized>(&self, rng: &mut R) -> String {
+        assert!(!self.patterns.is_empty(), "no templates to generate from");
+        let pattern = &self.patterns[bounded_usize(rng, self.patterns.len())];
+        let mut name = String::new();
+        for &c in pattern {
+            match self.classes.iter().find(|(letter, _)| *letter == c) {
+                Some((_, syllables)) => {
+                    name.push_str(&syllables[bounded_usize(rng, syllables.len())])
+                }
+                None => name.push(c),
+            }
+        }
+        if self.capitalized {
+            name = capitalize_words(&name);
+        }
+        name
+    }
+}
+
+fn capitalize_words(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut word_start = true;
+    for c in s.chars() {
+        if word_start && c.is_alphabetic() {
+            out.extend(c.to_uppercase());
+        } else {
+            out.push(c);
+        }
+        word_start = !c.is_alphabetic();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_namegen() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let names = NameGen::new()
+            .with_class('C', &["k", "t"])
+            .with_class('V', &["a", "o"])
+            .with_pattern("CV-CVC");
+        for _ in 0..100 {
+            let name: Vec<char> = names.generate(&mut pcg).chars().collect();
+            assert_eq!(name.len(), 6);
+            assert_eq!(name[2], '-');
+            for &i in &[0, 3, 5] {
+                assert!(name[i] == 'k' || name[i] == 't');
+            }
+            assert!(name[1] == 'a' || name[1] == 'o');
+        }
+    }
+
+    #[test]
+    fn test_patterns_and_capitals() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let names = NameGen::new()
+            .with_class('S', &["zor"])
+            .with_class('S', &["el"])
+            .with_pattern("S S")
+            .with_pattern("S'S")
+            .capitalized();
+        let mut seen = Vec::new();
+        for _ in 0..100 {
+            let name = names.generate(&mut pcg);
+            if !seen.contains(&name) {
+                seen.push(name);
+            }
+        }
+        seen.sort();
+        assert_eq!(seen, vec!["El El", "El'El"]);
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seed = rand::random::<u64>();
+        let names = NameGen::new()
+            .with_class('C', &["b", "d", "g", "l", "m", "n"])
+            .with_class('V', &["a", "e", "i"])
+            .with_pattern("CVCVC");
+        let a: Vec<_> = {
+            let mut pcg = Pcg::seed_from_u64(seed);
+            (0..10).map(|_| names.generate(&mut pcg)).collect()
+        };
+        let mut pcg = Pcg::seed_from_u64(seed);
+        assert!(a.iter().all(|name| *name == names.generate(&mut pcg)));
+    }
+}
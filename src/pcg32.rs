@@ -0,0 +1,190 @@
+/*! PCG-XSH-RR 64/32 (LCG), the reference library's `pcg32`.
+ *
+ *  Unlike the MCG behind `Pcg`, an LCG adds an odd increment at each
+ *  step, and every increment gives a distinct sequence, or stream. Two
+ *  generators on different streams never share their sequences, however
+ *  they are seeded, which reseeding from another generator's output
+ *  cannot promise. With the same seed and stream, outputs match the
+ *  reference implementation.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg32;
+ *  # use rand_core::RngCore;
+ *  let mut a = Pcg32::with_stream(42, 1);
+ *  let mut b = Pcg32::with_stream(42, 2);
+ *
+ *  assert_eq!(a.stream_id(), 1);
+ *  assert_ne!(a.next_u32(), b.next_u32());
+ *  ```
+ */
+use rand_core::{impls, Error, RngCore, SeedableRng};
+use std::num::Wrapping;
+
+const MULTIPLIER: u64 = 6364136223846793005;
+/// The stream of the reference library's default increment,
+/// 1442695040888963407
+const DEFAULT_STREAM: u64 = 721347520444481703;
+
+/// A seed: the initial state then the stream id, both little-endian
+#[derive(Default)]
+pub struct Pcg32Seed(pub [u8; 16]);
+
+impl AsMut<[u8]> for Pcg32Seed {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    /// Creates a generator on the given stream, seeded as the reference
+    /// library's `pcg32_srandom_r(seed, stream_id)`. Stream ids are 63
+    /// bits, so ids differing only in the top bit name the same stream.
+    pub fn with_stream(seed: u64, stream_id: u64) -> Pcg32 {
+        let mut pcg = Pcg32 {
+            state: 0,
+            increment: (stream_id << 1) | 1,
+        };
+        pcg.step();
+        pcg.state = pcg.state.wrapping_add(seed);
+        pcg.step();
+        pcg
+    }
+
+    /// The stream this generator is on.
+    pub fn stream_id(&self) -> u64 {
+        self.increment >> 1
+    }
+
+    /// Advances the state by n steps, as if calling next_u32() n times,
+    /// or rewinds it for negative n, in O(log n) time.
+    pub fn skip(&mut self, n: i64) {
+        // Brown's algorithm: square the step's affine map
+        // x -> mult * x + plus, applying it for each set bit of n.
+        // The period is 2^64, so a negative n is a long way forward.
+        let mut delta = n as u64;
+        let (mut mult, mut plus) = (Wrapping(MULTIPLIER), Wrapping(self.increment));
+        let (mut acc_mult, mut acc_plus) = (Wrapping(1u64), Wrapping(0u64));
+        while delta > 0 {
+            if delta & 1 == 1 {
+                acc_mult *= mult;
+                acc_plus = acc_plus * mult + plus;
+            }
+            plus = (mult + Wrapping(1)) * plus;
+            mult *= mult;
+            delta >>= 1;
+        }
+        self.state = (acc_mult * Wrapping(self.state) + acc_plus).0;
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+}
+
+impl RngCore for Pcg32 {
+    /// Generate a random u32, advancing the state one step.
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Generate a random u64, advancing the state two steps.
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) ^ (self.next_u32() as u64)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Pcg32 {
+    type Seed = Pcg32Seed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 8];
+        let mut stream = [0; 8];
+        state.copy_from_slice(&seed.0[..8]);
+        stream.copy_from_slice(&seed.0[8..]);
+        Pcg32::with_stream(u64::from_le_bytes(state), u64::from_le_bytes(stream))
+    }
+
+    /// Seeds a generator on the reference library's default stream.
+    fn seed_from_u64(seed: u64) -> Self {
+        Pcg32::with_stream(seed, DEFAULT_STREAM)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_outputs() {
+        // from the reference library's pcg32-demo, seeded with (42, 54)
+        let mut pcg = Pcg32::with_stream(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+        for &x in &expected {
+            assert_eq!(pcg.next_u32(), x);
+        }
+    }
+
+    #[test]
+    fn test_streams() {
+        let seed = rand::random::<u64>();
+        let mut a = Pcg32::with_stream(seed, 7);
+        let mut b = Pcg32::with_stream(seed, 8);
+        assert_eq!(a.stream_id(), 7);
+        assert_eq!(b.stream_id(), 8);
+        let same = (0..100).filter(|_| a.next_u32() == b.next_u32()).count();
+        assert!(same < 5);
+        assert_eq!(Pcg32::seed_from_u64(seed).increment, 1442695040888963407);
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut pcg = Pcg32::with_stream(rand::random::<u64>(), rand::random::<u64>());
+        let start = pcg.clone();
+        let steps = rand::random::<u16>();
+        let mut stepped = pcg.clone();
+        for _ in 0..steps {
+            stepped.next_u32();
+        }
+        pcg.skip(steps as i64);
+        assert_eq!(pcg.state, stepped.state);
+        pcg.skip(-(steps as i64));
+        assert_eq!(pcg.state, start.state);
+        pcg.skip(i64::MIN);
+        pcg.skip(i64::MIN);
+        assert_eq!(pcg.state, start.state);
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&42u64.to_le_bytes());
+        bytes[8..].copy_from_slice(&54u64.to_le_bytes());
+        let mut pcg = Pcg32::from_seed(Pcg32Seed(bytes));
+        assert_eq!(pcg.stream_id(), 54);
+        assert_eq!(pcg.next_u32(), 0xa15c02b7);
+    }
+}
@@ -0,0 +1,164 @@
+/*! PCG-XSL-RR 128/64 (MCG), the reference library's `pcg64_fast`.
+ *
+ *  A 128-bit multiplicative state gives a full 64-bit output at every
+ *  step, where `Pcg` stitches two 32-bit steps together, and a period of
+ *  2^126. With the same seed, outputs match the reference implementation.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg64;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = Pcg64::seed_from_u64(12345);
+ *  let x = pcg.next_u64();
+ *
+ *  pcg.skip(-1);
+ *  assert_eq!(pcg.next_u64(), x);
+ *  ```
+ */
+use rand_core::{impls, Error, RngCore, SeedableRng};
+use std::num::Wrapping;
+
+/// The reference library's 128-bit multiplier
+const MULTIPLIER: u128 = 0x2360ED051FC65DA44385DF649FCCF645;
+/// the inverse of MULTIPLIER; (MULTIPLIER*INVERSE)%(2^128) = 1
+const INVERSE: u128 = 0x07DDA22B9397986098ABC8B0716EAC8D;
+
+/// A seed: the initial state, little-endian
+#[derive(Default)]
+pub struct Pcg64Seed(pub [u8; 16]);
+
+impl AsMut<[u8]> for Pcg64Seed {
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pcg64 {
+    state: u128,
+}
+
+impl Pcg64 {
+    /// Creates a generator with the given 128-bit state, as the reference
+    /// library's `pcg64_fast_srandom_r`. The low bit is set, since an even
+    /// state would shorten the period.
+    pub fn new(state: u128) -> Pcg64 {
+        Pcg64 { state: state | 1 }
+    }
+
+    /// Advances the state by n steps, as if calling next_u64() n times,
+    /// or rewinds it for negative n, in O(log n) time.
+    pub fn skip(&mut self, n: i64) {
+        let base = if n >= 0 { MULTIPLIER } else { INVERSE };
+        let mut exponent = n.unsigned_abs();
+        let mut power = Wrapping(base);
+        let mut factor = Wrapping(1u128);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                factor *= power;
+            }
+            power *= power;
+            exponent >>= 1;
+        }
+        self.state = (Wrapping(self.state) * factor).0;
+    }
+}
+
+impl RngCore for Pcg64 {
+    /// Generate a random u32 from the high half of a u64, advancing the
+    /// state one step.
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Generate a random u64, advancing the state one step.
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_mul(MULTIPLIER);
+        let folded = (self.state >> 64) as u64 ^ self.state as u64;
+        folded.rotate_right((self.state >> 122) as u32)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for Pcg64 {
+    type Seed = Pcg64Seed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Pcg64::new(u128::from_le_bytes(seed.0))
+    }
+
+    fn seed_from_u64(seed: u64) -> Self {
+        Pcg64::new(seed as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_outputs() {
+        // from the reference library's test suite, for pcg64_fast seeded with 42
+        let mut pcg = Pcg64::new(42);
+        let expected = [
+            0x63b4a3a813ce700a,
+            0x382954200617ab24,
+            0xa7fd85ae3fe950ce,
+            0xd715286aa2887737,
+            0x60c92fee2e59f32c,
+            0x84c4e96beff30017,
+        ];
+        for &x in &expected {
+            assert_eq!(pcg.next_u64(), x);
+        }
+    }
+
+    #[test]
+    fn test_next_u64() {
+        let seed = rand::random::<u64>();
+        let state = (seed as u128 | 1).wrapping_mul(MULTIPLIER);
+        let expected = ((state >> 64) as u64 ^ state as u64).rotate_right((state >> 122) as u32);
+
+        let mut pcg = Pcg64::seed_from_u64(seed);
+        assert_eq!(pcg.next_u64(), expected);
+        assert_eq!(pcg.state, state);
+        let high = (pcg.clone().next_u64() >> 32) as u32;
+        assert_eq!(pcg.next_u32(), high);
+    }
+
+    #[test]
+    fn test_inverse() {
+        assert_eq!(MULTIPLIER.wrapping_mul(INVERSE), 1);
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut pcg = Pcg64::new(rand::random::<u128>());
+        let start = pcg.clone();
+        let steps = rand::random::<u16>();
+        let mut stepped = pcg.clone();
+        for _ in 0..steps {
+            stepped.next_u64();
+        }
+        pcg.skip(steps as i64);
+        assert_eq!(pcg.state, stepped.state);
+        pcg.skip(-(steps as i64));
+        assert_eq!(pcg.state, start.state);
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let state = rand::random::<u128>() | 1;
+        let pcg = Pcg64::from_seed(Pcg64Seed(state.to_le_bytes()));
+        assert_eq!(pcg.state, state);
+        assert_eq!(Pcg64::seed_from_u64(0).state, 1);
+    }
+}
@@ -0,0 +1,485 @@
+/*! Random permutations, permutation group utilities, shuffled IDs, and
+ *  random subsets as bitsets.
+ *
+ *  A permutation of length n is represented as a slice `p` holding each
+ *  of 0..n exactly once, mapping `i` to `p[i]`.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::permutation::*;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let p = random_permutation(&mut pcg, 10);
+ *  let identity = compose(&p, &invert(&p));
+ *
+ *  assert_eq!(identity, (0..10).collect::<Vec<_>>());
+ *  ```
+ */
+use crate::{bounded_usize, mix64, Pcg};
+use rand_core::{RngCore, SeedableRng};
+use std::collections::HashMap;
+
+/// Generates a bitset of `n_bits` bits with exactly `k` of them set, every
+/// such set equally likely. Bit i is bit i % 64 of word i / 64, and bits
+/// past n_bits in the last word are clear. Takes min(k, n_bits - k) draws,
+/// with Floyd's algorithm (a partial Fisher-Yates shuffle that keeps its
+/// state in the bitset itself), choosing the clear bits when most are set.
+/// Panics if k > n_bits.
+pub fn gen_bitset<R: RngCore + ?Sized>(rng: &mut R, n_bits: usize, k: usize) -> Vec<u64> {
+    assert!(k <= n_bits, "cannot set more bits than there are");
+    let words = n_bits.div_ceil(64);
+    let invert = k > n_bits / 2;
+    let chosen = if invert { n_bits - k } else { k };
+    let mut bits = vec![0u64; words];
+    let is_set = |bits: &[u64], i: usize| bits[i / 64] >> (i % 64) & 1 == 1;
+    for j in n_bits - chosen..n_bits {
+        let t = bounded_usize(rng, j + 1);
+        let i = if is_set(&bits, t) { j } else { t };
+        bits[i / 64] |= 1 << (i % 64);
+    }
+    if invert {
+        for word in bits.iter_mut() {
+            *word = !*word;
+        }
+        if !n_bits.is_multiple_of(64) {
+            bits[words - 1] &= (1 << (n_bits % 64)) - 1;
+        }
+    }
+    bits
+}
+
+/// Feistel rounds in an IdPermutation
+const ROUNDS: usize = 6;
+
+/// Shuffles a slice in place with the Fisher-Yates algorithm, drawing
+/// from any generator.
+pub fn shuffle<T, R: RngCore + ?Sized>(rng: &mut R, slice: &mut [T]) {
+    for i in (1..slice.len()).rev() {
+        slice.swap(i, bounded_usize(rng, i + 1));
+    }
+}
+
+impl Pcg {
+    /// Shuffles a slice in place with the Fisher-Yates algorithm: one
+    /// bounded draw per element but the first. Pcg's consecutive draws are
+    /// correlated (see the analysis module), enough to make some orders of
+    /// a short slice come up several percent more often than others, well
+    /// beyond sampling noise. Where that matters, use `permutation::shuffle`
+    /// with `Pcg32` or `Pcg64`, whose orders are uniform.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        shuffle(self, slice)
+    }
+
+    /// Picks an element of a slice uniformly, or None if it is empty.
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        if slice.is_empty() {
+            None
+        } else {
+            Some(&slice[bounded_usize(self, slice.len())])
+        }
+    }
+
+    /// Picks k distinct elements of a slice, in random order, every
+    /// selection equally likely; all of them, shuffled, if k exceeds its
+    /// length. Takes k draws and O(k) memory however long the slice is.
+    pub fn choose_multiple<'a, T>(&mut self, slice: &'a [T], k: usize) -> Vec<&'a T> {
+        sample_indices(self, slice.len(), k)
+            .into_iter()
+            .map(|i| &slice[i])
+            .collect()
+    }
+}
+
+/// Picks min(k, n) distinct indices below n in random order, with a
+/// Fisher-Yates shuffle stopped after k steps. Only the displaced entries
+/// of the notional array 0..n are stored.
+pub(crate) fn sample_indices<R: RngCore + ?Sized>(rng: &mut R, n: usize, k: usize) -> Vec<usize> {
+    let mut displaced = HashMap::new();
+    (0..k.min(n))
+        .map(|i| {
+            let j = i + bounded_usize(rng, n - i);
+            let at_j = *displaced.get(&j).unwrap_or(&j);
+            let at_i = *displaced.get(&i).unwrap_or(&i);
+            displaced.insert(j, at_i);
+            at_j
+        })
+        .collect()
+}
+
+/// Generates a uniformly random permutation of 0..n.
+pub fn random_permutation<R: RngCore + ?Sized>(rng: &mut R, n: usize) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..n).collect();
+    shuffle(rng, &mut p);
+    p
+}
+
+/// Generates a uniformly random cyclic permutation of 0..n, i.e. one
+/// consisting of a single cycle through every element (Sattolo's algorithm).
+pub fn random_cycle<R: RngCore + ?Sized>(rng: &mut R, n: usize) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..n).collect();
+    for i in (1..n).rev() {
+        p.swap(i, bounded_usize(rng, i));
+    }
+    p
+}
+
+/// Composes two permutations of equal length, applying `second` first:
+/// the result maps `i` to `first[second[i]]`.
+pub fn compose(first: &[usize], second: &[usize]) -> Vec<usize> {
+    assert_eq!(first.len(), second.len(), "permutations differ in length");
+    second.iter().map(|&i| first[i]).collect()
+}
+
+/// Inverts a permutation, so that `compose(p, invert(p))` is the identity.
+pub fn invert(p: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; p.len()];
+    for (i, &j) in p.iter().enumerate() {
+        inverse[j] = i;
+    }
+    inverse
+}
+
+/// Checks that `p` holds each of 0..p.len() exactly once.
+pub fn is_permutation(p: &[usize]) -> bool {
+    let mut seen = vec![false; p.len()];
+    for &i in p {
+        if i >= p.len() || seen[i] {
+            return false;
+        }
+        seen[i] = true;
+    }
+    true
+}
+
+/// A pseudorandom bijection of 0..n computed on demand, in constant
+/// memory, for handing out non-sequential public IDs without storing a
+/// mapping table. A Feistel network permutes the smallest power-of-four
+/// domain covering n, and values falling outside 0..n are permuted again
+/// until they land inside ("cycle walking"), which takes fewer than four
+/// rounds of the network on average.
+#[derive(Clone, Debug)]
+pub struct IdPermutation {
+    n: u64,
+    half_bits: u32,
+    keys: [u64; ROUNDS],
+}
+
+impl IdPermutation {
+    /// The permutation of 0..n for `seed`.
+    pub fn new(n: u64, seed: u64) -> IdPermutation {
+        let bits = 64 - n.saturating_sub(1).leading_zeros();
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut keys = [0; ROUNDS];
+        for key in keys.iter_mut() {
+            *key = pcg.next_u64();
+        }
+        IdPermutation {
+            n,
+            half_bits: bits.div_ceil(2).max(1),
+            keys,
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.n
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.n == 0
+    }
+
+    /// The ID for index i, which must be less than n.
+    pub fn permute(&self, i: u64) -> u64 {
+        assert!(i < self.n, "index out of range");
+        let mut x = self.feistel(i, false);
+        while x >= self.n {
+            x = self.feistel(x, false);
+        }
+        x
+    }
+
+    /// The index whose ID is `id`, which must be less than n.
+    pub fn unpermute(&self, id: u64) -> u64 {
+        assert!(id < self.n, "ID out of range");
+        let mut x = self.feistel(id, true);
+        while x >= self.n {
+            x = self.feistel(x, true);
+        }
+        x
+    }
+
+    /// One pass of the network over the power-of-four domain, or its
+    /// inverse
+    fn feistel(&self, x: u64, inverse: bool) -> u64 {
+        let mask = u64::MAX >> (64 - self.half_bits);
+        let round = |half: u64, key: u64| mix64(half ^ key) & mask;
+        let (mut left, mut right) = (x >> self.half_bits, x & mask);
+        if inverse {
+            for &key in self.keys.iter().rev() {
+                let previous = right ^ round(left, key);
+                right = left;
+                left = previous;
+            }
+        } else {
+            for &key in self.keys.iter() {
+                let next = left ^ round(right, key);
+                left = right;
+                right = next;
+            }
+        }
+        left << self.half_bits | right
+    }
+}
+
+/// The IDs of indices 0, 1, 2 and so on, under an IdPermutation
+#[derive(Clone, Debug)]
+pub struct ShuffledIds {
+    permutation: IdPermutation,
+    next: u64,
+}
+
+impl Iterator for ShuffledIds {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.next == self.permutation.len() {
+            return None;
+        }
+        self.next += 1;
+        Some(self.permutation.permute(self.next - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.permutation.len() - self.next) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+/// Lazily yields every ID in 0..n exactly once, in a pseudorandom order
+/// fixed by the seed. Use `IdPermutation` directly to map single indices,
+/// or to recover the index behind an ID.
+pub fn shuffled_ids(n: u64, seed: u64) -> ShuffledIds {
+    ShuffledIds {
+        permutation: IdPermutation::new(n, seed),
+        next: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pcg, Pcg32};
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_random_permutation() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for n in 0..50 {
+            assert!(is_permutation(&random_permutation(&mut pcg, n)));
+        }
+    }
+
+    /// How often each order of three elements comes up in 60,000 shuffles
+    fn order_counts<F: FnMut(&mut [char; 3])>(mut shuffle: F) -> Vec<u32> {
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..60000 {
+            let mut deck = ['a', 'b', 'c'];
+            shuffle(&mut deck);
+            *counts.entry(deck).or_insert(0) += 1;
+        }
+        counts.into_values().collect()
+    }
+
+    /// Pearson's chi-square statistic of the counts against 10,000 each
+    fn chi_square(counts: &[u32]) -> f64 {
+        counts
+            .iter()
+            .map(|&c| (c as f64 - 10000.0).powi(2) / 10000.0)
+            .sum()
+    }
+
+    #[test]
+    fn test_pcg_shuffle_skew() {
+        // the skew the Pcg::shuffle documentation warns of: with 5 degrees
+        // of freedom, uniform orders exceed 20.5 one time in a thousand
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let counts = order_counts(|deck| pcg.shuffle(deck));
+        assert_eq!(counts.len(), 6);
+        assert!(chi_square(&counts) > 30.0);
+        let mut rng = Pcg32::seed_from_u64(0x2545F4914F6CDD1D);
+        let counts = order_counts(|deck| shuffle(&mut rng, deck));
+        assert!(chi_square(&counts) < 20.5);
+    }
+
+    #[test]
+    fn test_shuffle() {
+        // each of the 6 orders of three elements about equally often, with
+        // Pcg32 rather than Pcg, whose correlated draws skew them
+        let mut rng = Pcg32::seed_from_u64(0x2545F4914F6CDD1D);
+        let counts = order_counts(|deck| shuffle(&mut rng, deck));
+        assert_eq!(counts.len(), 6);
+        assert!(counts.iter().all(|&c| (9600..10400).contains(&c)));
+
+        let seed = rand::random::<u64>();
+        let mut deck: Vec<u32> = (0..52).collect();
+        Pcg::seed_from_u64(seed).shuffle(&mut deck);
+        let mut again: Vec<u32> = (0..52).collect();
+        Pcg::seed_from_u64(seed).shuffle(&mut again);
+        assert_eq!(deck, again);
+        assert_ne!(deck, (0..52).collect::<Vec<u32>>());
+        deck.sort_unstable();
+        assert_eq!(deck, (0..52).collect::<Vec<u32>>());
+
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut empty: [u8; 0] = [];
+        pcg.shuffle(&mut empty);
+        let mut single = [7];
+        pcg.shuffle(&mut single);
+        assert_eq!(single, [7]);
+    }
+
+    #[test]
+    fn test_choose() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let empty: [u8; 0] = [];
+        assert_eq!(pcg.choose(&empty), None);
+        assert_eq!(pcg.choose(&[9]), Some(&9));
+        let mut counts = [0; 4];
+        for _ in 0..40000 {
+            counts[*pcg.choose(&[0, 1, 2, 3]).unwrap()] += 1;
+        }
+        assert!(counts.iter().all(|&c| (9700..10300).contains(&c)));
+    }
+
+    #[test]
+    fn test_choose_multiple() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let items: Vec<u32> = (0..100).collect();
+        let mut chosen = pcg.choose_multiple(&items, 10);
+        assert_eq!(chosen.len(), 10);
+        chosen.sort_unstable();
+        chosen.dedup();
+        assert_eq!(chosen.len(), 10);
+        let mut all = pcg.choose_multiple(&items, 1000);
+        all.sort_unstable();
+        assert_eq!(all, items.iter().collect::<Vec<_>>());
+        assert!(pcg.choose_multiple(&items, 0).is_empty());
+
+        // each of the 6 ordered pairs from three elements equally often
+        let mut rng = Pcg32::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..60000 {
+            *counts.entry(sample_indices(&mut rng, 3, 2)).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(counts.values().all(|&c| (9600..10400).contains(&c)));
+        assert_eq!(sample_indices(&mut rng, usize::MAX, 3).len(), 3);
+    }
+
+    #[test]
+    fn test_gen_bitset() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for &(n_bits, k) in &[
+            (0, 0),
+            (1, 1),
+            (64, 0),
+            (64, 64),
+            (100, 3),
+            (100, 97),
+            (1000, 500),
+        ] {
+            let bits = gen_bitset(&mut pcg, n_bits, k);
+            assert_eq!(bits.len(), n_bits.div_ceil(64));
+            assert_eq!(bits.iter().map(|w| w.count_ones()).sum::<u32>() as usize, k);
+            if !n_bits.is_multiple_of(64) {
+                assert_eq!(bits[bits.len() - 1] >> (n_bits % 64), 0);
+            }
+        }
+
+        // every one of the 10 subsets of 2 out of 5 equally often, whether
+        // by choosing set bits or, for 3 of 5, clear ones
+        let mut rng = Pcg32::seed_from_u64(0x2545F4914F6CDD1D);
+        for &k in &[2, 3] {
+            let mut counts = [0; 32];
+            for _ in 0..50000 {
+                counts[gen_bitset(&mut rng, 5, k)[0] as usize] += 1;
+            }
+            let subsets: Vec<&i32> = counts.iter().filter(|&&c| c > 0).collect();
+            assert_eq!(subsets.len(), 10);
+            assert!(subsets.iter().all(|&&c| (4700..5300).contains(&c)));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot set more bits")]
+    fn test_gen_bitset_too_many() {
+        gen_bitset(&mut Pcg::seed_from_u64(1), 10, 11);
+    }
+
+    #[test]
+    fn test_random_cycle() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for n in 1..50 {
+            let p = random_cycle(&mut pcg, n);
+            assert!(is_permutation(&p));
+            let mut i = p[0];
+            let mut length = 1;
+            while i != 0 {
+                i = p[i];
+                length += 1;
+            }
+            assert_eq!(length, n);
+        }
+    }
+
+    #[test]
+    fn test_compose_invert() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let a = random_permutation(&mut pcg, 20);
+        let b = random_permutation(&mut pcg, 20);
+        let ab = compose(&a, &b);
+        assert!(is_permutation(&ab));
+        for i in 0..20 {
+            assert_eq!(ab[i], a[b[i]]);
+        }
+        let identity: Vec<usize> = (0..20).collect();
+        assert_eq!(compose(&invert(&a), &a), identity);
+        assert_eq!(compose(&ab, &invert(&ab)), identity);
+    }
+
+    #[test]
+    fn test_shuffled_ids() {
+        let seed = rand::random::<u64>();
+        for &n in &[0, 1, 2, 3, 10, 1000, 4096, 4097] {
+            let ids: Vec<usize> = shuffled_ids(n, seed).map(|id| id as usize).collect();
+            assert_eq!(ids.len(), n as usize);
+            assert!(is_permutation(&ids));
+        }
+        let ids: Vec<u64> = shuffled_ids(1000, seed).collect();
+        assert_eq!(ids, shuffled_ids(1000, seed).collect::<Vec<_>>());
+        assert_ne!(ids, shuffled_ids(1000, seed ^ 1).collect::<Vec<_>>());
+        assert_ne!(ids, (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_id_permutation() {
+        let seed = rand::random::<u64>();
+        for &n in &[5, 1 << 20, u64::MAX, (1 << 63) + 1] {
+            let permutation = IdPermutation::new(n, seed);
+            for _ in 0..100 {
+                let i = rand::random::<u64>() % n;
+                let id = permutation.permute(i);
+                assert!(id < n);
+                assert_eq!(permutation.unpermute(id), i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_permutation() {
+        assert!(is_permutation(&[2, 0, 1]));
+        assert!(!is_permutation(&[2, 0, 0]));
+        assert!(!is_permutation(&[3, 0, 1]));
+    }
+}
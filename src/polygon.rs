@@ -0,0 +1,215 @@
+/*! Random polygons, for property-testing computational geometry.
+ *
+ *  Polygons are vectors of (x, y) vertices, with an edge from each
+ *  vertex to the next and from the last back to the first.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::polygon::{random_convex_polygon, random_simple_polygon, Bounds};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let bounds = Bounds::new(0.0, 0.0, 100.0, 50.0);
+ *
+ *  let convex = random_convex_polygon(&mut pcg, 8, bounds);
+ *  let simple = random_simple_polygon(&mut pcg, 20, bounds);
+ *
+ *  assert_eq!(convex.len(), 8);
+ *  assert!(simple.iter().all(|&(x, y)| bounds.contains(x, y)));
+ *  ```
+ */
+use crate::permutation::shuffle;
+use crate::unit_f64;
+use rand_core::RngCore;
+
+/// An axis-aligned rectangle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub x_min: f64,
+    pub y_min: f64,
+    pub x_max: f64,
+    pub y_max: f64,
+}
+
+impl Bounds {
+    pub fn new(x_min: f64, y_min: f64, x_max: f64, y_max: f64) -> Bounds {
+        assert!(
+            x_min < x_max && y_min < y_max,
+            "bounds must have positive area"
+        );
+        Bounds {
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+        }
+    }
+
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        (self.x_min..=self.x_max).contains(&x) && (self.y_min..=self.y_max).contains(&y)
+    }
+
+    fn point<R: RngCore + ?Sized>(&self, rng: &mut R) -> (f64, f64) {
+        (
+            self.x_min + unit_f64(rng) * (self.x_max - self.x_min),
+            self.y_min + unit_f64(rng) * (self.y_max - self.y_min),
+        )
+    }
+}
+
+/// Generates a convex polygon with n vertices inside the bounds, in
+/// counterclockwise order, by Valtr's algorithm.
+pub fn random_convex_polygon<R: RngCore + ?Sized>(
+    rng: &mut R,
+    n: usize,
+    bounds: Bounds,
+) -> Vec<(f64, f64)> {
+    assert!(n >= 3, "a polygon needs at least three vertices");
+    let (x_start, mut dx) = edge_components(rng, n, bounds.x_min, bounds.x_max);
+    let (y_start, mut dy) = edge_components(rng, n, bounds.y_min, bounds.y_max);
+    shuffle(rng, &mut dy);
+    // joining the edges in order of angle makes a convex polygon
+    let mut edges: Vec<(f64, f64)> = dx.drain(..).zip(dy).collect();
+    edges.sort_by(|a, b| a.1.atan2(a.0).partial_cmp(&b.1.atan2(b.0)).unwrap());
+
+    let mut vertices = Vec::with_capacity(n);
+    let (mut x, mut y) = (0.0, 0.0);
+    for (ex, ey) in edges {
+        vertices.push((x, y));
+        x += ex;
+        y += ey;
+    }
+    // move the polygon onto the span of the points it was drawn from
+    let min_x = vertices.iter().map(|v| v.0).fold(f64::INFINITY, f64::min);
+    let min_y = vertices.iter().map(|v| v.1).fold(f64::INFINITY, f64::min);
+    vertices
+        .into_iter()
+        .map(|(x, y)| {
+            (
+                (x - min_x + x_start).clamp(bounds.x_min, bounds.x_max),
+                (y - min_y + y_start).clamp(bounds.y_min, bounds.y_max),
+            )
+        })
+        .collect()
+}
+
+/// Draws n sorted coordinates in [lo, hi] and splits the gaps between
+/// them into two chains from the smallest to the largest, returning the
+/// smallest and the signed steps of both chains, which sum to zero.
+fn edge_components<R: RngCore + ?Sized>(
+    rng: &mut R,
+    n: usize,
+    lo: f64,
+    hi: f64,
+) -> (f64, Vec<f64>) {
+    let mut coords: Vec<f64> = (0..n).map(|_| lo + unit_f64(rng) * (hi - lo)).collect();
+    coords.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let (min, max) = (coords[0], coords[n - 1]);
+    let mut steps = Vec::with_capacity(n);
+    let (mut upper, mut lower) = (min, min);
+    for &c in &coords[1..n - 1] {
+        if rng.next_u32() & 1 == 0 {
+            steps.push(c - upper);
+            upper = c;
+        } else {
+            steps.push(lower - c);
+            lower = c;
+        }
+    }
+    steps.push(max - upper);
+    steps.push(lower - max);
+    (min, steps)
+}
+
+/// Generates a simple (non-self-intersecting) polygon with n vertices
+/// drawn uniformly from the bounds. The vertices are joined in random
+/// order, and crossing edges are then uncrossed by 2-opt moves until none
+/// remain, which takes O(n^3) time or more, so keep n modest.
+pub fn random_simple_polygon<R: RngCore + ?Sized>(
+    rng: &mut R,
+    n: usize,
+    bounds: Bounds,
+) -> Vec<(f64, f64)> {
+    assert!(n >= 3, "a polygon needs at least three vertices");
+    let mut vertices: Vec<(f64, f64)> = (0..n).map(|_| bounds.point(rng)).collect();
+    // each move shortens the perimeter, so this terminates
+    while let Some((i, j)) = find_crossing(&vertices) {
+        vertices[i + 1..=j].reverse();
+    }
+    vertices
+}
+
+/// Finds edges (i, i + 1) and (j, j + 1), i < j, that properly cross.
+fn find_crossing(vertices: &[(f64, f64)]) -> Option<(usize, usize)> {
+    let n = vertices.len();
+    for i in 0..n {
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue; // adjacent through the closing edge
+            }
+            let (a, b) = (vertices[i], vertices[i + 1]);
+            let (c, d) = (vertices[j], vertices[(j + 1) % n]);
+            if crosses(a, b, c, d) {
+                return Some((i, j));
+            }
+        }
+    }
+    None
+}
+
+fn orientation(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Whether segments ab and cd cross at a point interior to both
+fn crosses(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let (o1, o2) = (orientation(a, b, c), orientation(a, b, d));
+    let (o3, o4) = (orientation(c, d, a), orientation(c, d, b));
+    o1 * o2 < 0.0 && o3 * o4 < 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_convex() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let bounds = Bounds::new(-5.0, 10.0, 5.0, 12.0);
+        for &n in &[3, 4, 10, 50] {
+            for _ in 0..20 {
+                let polygon = random_convex_polygon(&mut pcg, n, bounds);
+                assert_eq!(polygon.len(), n);
+                for i in 0..n {
+                    let (a, b, c) = (polygon[i], polygon[(i + 1) % n], polygon[(i + 2) % n]);
+                    assert!(bounds.contains(a.0, a.1));
+                    // counterclockwise turns throughout, allowing for rounding
+                    assert!(orientation(a, b, c) > -1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_simple() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let bounds = Bounds::new(0.0, 0.0, 1.0, 1.0);
+        for &n in &[3, 5, 30] {
+            for _ in 0..10 {
+                let polygon = random_simple_polygon(&mut pcg, n, bounds);
+                assert_eq!(polygon.len(), n);
+                assert!(polygon.iter().all(|&(x, y)| bounds.contains(x, y)));
+                assert_eq!(find_crossing(&polygon), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_crosses() {
+        assert!(crosses((0.0, 0.0), (1.0, 1.0), (0.0, 1.0), (1.0, 0.0)));
+        assert!(!crosses((0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)));
+        assert!(!crosses((0.0, 0.0), (1.0, 1.0), (1.0, 1.0), (2.0, 0.0)));
+    }
+}
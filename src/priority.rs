@@ -0,0 +1,133 @@
+/*! Compact random tie-breakers for deterministic schedulers.
+ *
+ *  A priority source passes a counter through a keyed bijection on u32,
+ *  so its values look random but never repeat: any 2^32 consecutive
+ *  values from one source are all distinct. Sources with different
+ *  labels use different bijections.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::priority::priority_source;
+ *  let mut priorities = priority_source("scheduler");
+ *  let a = priorities.next_priority();
+ *  let b = priorities.next_priority();
+ *
+ *  assert_ne!(a, b);
+ *  assert_eq!(priority_source("scheduler").next_priority(), a);
+ *  ```
+ */
+use crate::{hash_label, mix64};
+
+/// Yields distinct pseudorandom u32 priorities
+#[derive(Clone, Debug)]
+pub struct PrioritySource {
+    key: u64,
+    counter: u32,
+}
+
+/// Creates the priority source for a stream label.
+pub fn priority_source(stream_label: &str) -> PrioritySource {
+    PrioritySource::with_seed(0, stream_label)
+}
+
+/// Mixes x under a key; every step is invertible, so for a fixed key
+/// this is a bijection on u32.
+fn permute(mut x: u32, key: u64) -> u32 {
+    x ^= key as u32;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB352D);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846CA68B);
+    x = x.wrapping_add((key >> 32) as u32);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7FEB352D);
+    x ^= x >> 15;
+    x
+}
+
+impl PrioritySource {
+    /// Creates the priority source for a stream label under a seed, so
+    /// that separate runs can use separate orders.
+    pub fn with_seed(seed: u64, stream_label: &str) -> PrioritySource {
+        PrioritySource {
+            key: mix64(seed ^ mix64(hash_label(stream_label))),
+            counter: 0,
+        }
+    }
+
+    /// Returns the next priority. Values repeat only after 2^32 calls.
+    pub fn next_priority(&mut self) -> u32 {
+        let priority = permute(self.counter, self.key);
+        self.counter = self.counter.wrapping_add(1);
+        priority
+    }
+
+    /// The number of priorities handed out, modulo 2^32.
+    pub fn position(&self) -> u32 {
+        self.counter
+    }
+}
+
+impl Iterator for PrioritySource {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        Some(self.next_priority())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_no_duplicates() {
+        let mut source = PrioritySource::with_seed(rand::random::<u64>(), "tasks");
+        let mut seen = HashSet::new();
+        for _ in 0..1 << 20 {
+            assert!(seen.insert(source.next_priority()));
+        }
+        assert_eq!(source.position(), 1 << 20);
+    }
+
+    /// The multiplicative inverse of an odd number modulo 2^32
+    fn inverse(a: u32) -> u32 {
+        let mut x = a;
+        for _ in 0..5 {
+            x = x.wrapping_mul(2u32.wrapping_sub(a.wrapping_mul(x)));
+        }
+        x
+    }
+
+    fn unpermute(mut x: u32, key: u64) -> u32 {
+        x ^= (x >> 15) ^ (x >> 30);
+        x = x.wrapping_mul(inverse(0x7FEB352D));
+        x ^= x >> 16;
+        x = x.wrapping_sub((key >> 32) as u32);
+        x = x.wrapping_mul(inverse(0x846CA68B));
+        x ^= (x >> 15) ^ (x >> 30);
+        x = x.wrapping_mul(inverse(0x7FEB352D));
+        x ^= x >> 16;
+        x ^ key as u32
+    }
+
+    #[test]
+    fn test_bijection() {
+        let key = rand::random::<u64>();
+        for _ in 0..10000 {
+            let x = rand::random::<u32>();
+            assert_eq!(unpermute(permute(x, key), key), x);
+        }
+    }
+
+    #[test]
+    fn test_labels() {
+        let seed = rand::random::<u64>();
+        let first: Vec<u32> = PrioritySource::with_seed(seed, "tasks").take(3).collect();
+        let again: Vec<u32> = PrioritySource::with_seed(seed, "tasks").take(3).collect();
+        let other: Vec<u32> = PrioritySource::with_seed(seed, "other").take(3).collect();
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+    }
+}
@@ -0,0 +1,182 @@
+/*! Simple autocorrelated random processes, as building blocks for
+ *  synthetic time series. Each process is driven by a step distribution
+ *  supplying its innovations, typically StandardNormal.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::distributions::StandardNormal;
+ *  # use pcg::process::{Ar1, Process};
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut ar1 = Ar1::new(0.0, 0.9, 0.0, StandardNormal);
+ *  let series: Vec<f64> = ar1.path(&mut pcg).take(100).collect();
+ *
+ *  assert_eq!(series.len(), 100);
+ *  ```
+ */
+use crate::distributions::Distribution;
+use rand_core::RngCore;
+
+/// A discrete-time process producing one value per step
+pub trait Process {
+    /// Advances the process one step and returns its new value.
+    fn step<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> f64;
+
+    /// The current value of the process.
+    fn value(&self) -> f64;
+
+    /// Returns an iterator over successive values of the process.
+    fn path<'a, R: RngCore + ?Sized>(&'a mut self, rng: &'a mut R) -> Path<'a, Self, R>
+    where
+        Self: Sized,
+    {
+        Path { process: self, rng }
+    }
+}
+
+/// An endless iterator over the values of a process
+pub struct Path<'a, P, R: ?Sized> {
+    process: &'a mut P,
+    rng: &'a mut R,
+}
+
+impl<'a, P: Process, R: RngCore + ?Sized> Iterator for Path<'a, P, R> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<f64> {
+        Some(self.process.step(self.rng))
+    }
+}
+
+/// The autoregressive process x' = c + phi * x + e, where e is drawn from
+/// the step distribution. It is stationary when |phi| < 1.
+#[derive(Clone, Debug)]
+pub struct Ar1<D> {
+    c: f64,
+    phi: f64,
+    x: f64,
+    noise: D,
+}
+
+impl<D: Distribution<f64>> Ar1<D> {
+    pub fn new(c: f64, phi: f64, x0: f64, noise: D) -> Ar1<D> {
+        Ar1 {
+            c,
+            phi,
+            x: x0,
+            noise,
+        }
+    }
+}
+
+impl<D: Distribution<f64>> Process for Ar1<D> {
+    fn step<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> f64 {
+        self.x = self.c + self.phi * self.x + self.noise.sample(rng);
+        self.x
+    }
+
+    fn value(&self) -> f64 {
+        self.x
+    }
+}
+
+/// The Ornstein-Uhlenbeck process dx = theta * (mu - x) dt + sigma dW,
+/// discretized exactly over steps of length dt. The step distribution
+/// stands in for the Brownian increments and should have zero mean and
+/// unit variance.
+#[derive(Clone, Debug)]
+pub struct OrnsteinUhlenbeck<D> {
+    mu: f64,
+    decay: f64,
+    spread: f64,
+    x: f64,
+    noise: D,
+}
+
+impl<D: Distribution<f64>> OrnsteinUhlenbeck<D> {
+    /// theta is the rate of mean reversion, and must be positive.
+    pub fn new(
+        theta: f64,
+        mu: f64,
+        sigma: f64,
+        dt: f64,
+        x0: f64,
+        noise: D,
+    ) -> OrnsteinUhlenbeck<D> {
+        assert!(theta > 0.0, "theta must be positive");
+        let decay = (-theta * dt).exp();
+        OrnsteinUhlenbeck {
+            mu,
+            decay,
+            spread: sigma * ((1.0 - decay * decay) / (2.0 * theta)).sqrt(),
+            x: x0,
+            noise,
+        }
+    }
+}
+
+impl<D: Distribution<f64>> Process for OrnsteinUhlenbeck<D> {
+    fn step<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> f64 {
+        self.x = self.mu + (self.x - self.mu) * self.decay + self.spread * self.noise.sample(rng);
+        self.x
+    }
+
+    fn value(&self) -> f64 {
+        self.x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distributions::StandardNormal;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    struct Zero;
+
+    impl Distribution<f64> for Zero {
+        fn sample<R: RngCore + ?Sized>(&self, _rng: &mut R) -> f64 {
+            0.0
+        }
+    }
+
+    #[test]
+    fn test_ar1() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut ar1 = Ar1::new(1.0, 0.8, 5.0, StandardNormal);
+        let series: Vec<f64> = ar1.path(&mut pcg).skip(100).take(50000).collect();
+        let n = series.len() as f64;
+        let mean = series.iter().sum::<f64>() / n;
+        assert!((mean - 5.0).abs() < 0.1);
+
+        let variance = series.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+        let lagged = series
+            .windows(2)
+            .map(|w| (w[0] - mean) * (w[1] - mean))
+            .sum::<f64>()
+            / (n - 1.0);
+        assert!((lagged / variance - 0.8).abs() < 0.02);
+        assert_eq!(ar1.value(), series[series.len() - 1]);
+    }
+
+    #[test]
+    fn test_ornstein_uhlenbeck() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut ou = OrnsteinUhlenbeck::new(2.0, 3.0, 1.0, 0.5, 10.0, Zero);
+        let expected = 3.0 + 7.0 * (-1.0f64).exp();
+        assert!((ou.step(&mut pcg) - expected).abs() < 1e-12);
+        for _ in 0..100 {
+            ou.step(&mut pcg);
+        }
+        assert!((ou.value() - 3.0).abs() < 1e-12);
+
+        // the stationary variance is sigma^2 / (2 theta)
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut ou = OrnsteinUhlenbeck::new(2.0, 0.0, 1.0, 0.1, 0.0, StandardNormal);
+        let series: Vec<f64> = ou.path(&mut pcg).take(50000).collect();
+        let variance = series.iter().map(|x| x * x).sum::<f64>() / series.len() as f64;
+        assert!((variance - 0.25).abs() < 0.02);
+    }
+}
@@ -0,0 +1,108 @@
+/*! Stochastic rounding, which rounds up or down at random with
+ *  probabilities that make the expected result equal the input. Sums of
+ *  stochastically rounded values stay unbiased, unlike round-to-nearest,
+ *  which is why quantized training and numerics tests use it.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::rounding::stochastic_round;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let total: i64 = (0..1000).map(|_| stochastic_round(&mut pcg, 0.25)).sum();
+ *
+ *  // about 250, where rounding to nearest would give 0
+ *  assert!(total > 150 && total < 350);
+ *  ```
+ */
+use rand_core::RngCore;
+
+/// Scales a fraction in [0, 1) to the u64 range; exact, since it only
+/// changes the exponent
+const TWO_POW_64: f64 = 18446744073709551616.0;
+
+/// Decides whether to round up, with probability `fraction`, which must
+/// be in [0, 1). The comparison uses 64 random bits, so the probability is
+/// exact to within 2^-64 even for fractions too small for a random float.
+fn round_up<R: RngCore + ?Sized>(rng: &mut R, fraction: f64) -> bool {
+    rng.next_u64() < (fraction * TWO_POW_64) as u64
+}
+
+/// Rounds x down or up to a neighbouring integer, up with probability
+/// equal to its fractional part. Integers come back unchanged, values
+/// beyond the range of i64 saturate, and NaN panics.
+pub fn stochastic_round<R: RngCore + ?Sized>(rng: &mut R, x: f64) -> i64 {
+    assert!(!x.is_nan(), "cannot round NaN");
+    let floor = x.floor();
+    let fraction = x - floor;
+    if fraction > 0.0 && round_up(rng, fraction) {
+        floor as i64 + 1
+    } else {
+        floor as i64
+    }
+}
+
+/// Stochastically rounds each value in place to an integer-valued f32.
+/// Values that are already integers, infinite or NaN are left as they
+/// are and draw nothing.
+pub fn stochastic_round_slice<R: RngCore + ?Sized>(rng: &mut R, values: &mut [f32]) {
+    for x in values {
+        let floor = x.floor();
+        // exact, since floor and x are within one of each other
+        let fraction = *x - floor;
+        if fraction > 0.0 {
+            *x = if round_up(rng, fraction as f64) {
+                floor + 1.0
+            } else {
+                floor
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_stochastic_round() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let n = 100000;
+        let total: i64 = (0..n).map(|_| stochastic_round(&mut pcg, -2.3)).sum();
+        assert!((total as f64 / n as f64 + 2.3).abs() < 0.01);
+        for _ in 0..1000 {
+            let x = stochastic_round(&mut pcg, 7.9);
+            assert!(x == 7 || x == 8);
+        }
+        assert_eq!(stochastic_round(&mut pcg, 5.0), 5);
+        assert_eq!(stochastic_round(&mut pcg, 1e300), i64::MAX);
+        assert_eq!(stochastic_round(&mut pcg, f64::NEG_INFINITY), i64::MIN);
+    }
+
+    #[test]
+    fn test_tiny_fractions() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert!((0..1000).all(|_| stochastic_round(&mut pcg, 1e-30) == 0));
+        assert!((0..1000).all(|_| stochastic_round(&mut pcg, 3.0 - 1e-15) == 3));
+        assert!(round_up(&mut pcg, 0.999_999_999_999));
+    }
+
+    #[test]
+    fn test_slice() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut values = vec![0.75f32; 100000];
+        values.push(f32::NAN);
+        values.push(-4.0);
+        stochastic_round_slice(&mut pcg, &mut values);
+        assert!(values[100000].is_nan());
+        assert_eq!(values[100001], -4.0);
+        let ones = values[..100000].iter().filter(|&&x| x == 1.0).count();
+        assert_eq!(
+            values[..100000].iter().filter(|&&x| x == 0.0).count(),
+            100000 - ones
+        );
+        assert!((ones as f64 / 100000.0 - 0.75).abs() < 0.01);
+    }
+}
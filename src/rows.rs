@@ -0,0 +1,218 @@
+/*! Random table rows for seeding test databases.
+ *
+ *  Columns are described by specs: integer ranges, strings over a charset,
+ *  and foreign keys drawn from another table's keys, any of them
+ *  nullable. Each row is generated from the seed and its index alone, so
+ *  a large table can be generated in shards, in any order, and still come
+ *  out the same.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::rows::{Column, RowGen, Value};
+ *  let columns = vec![
+ *      Column::int(18..=99),
+ *      Column::text("abcdefghijklmnopqrstuvwxyz", 3..=12),
+ *      Column::foreign_key(vec![10, 20, 30]).nullable(0.2),
+ *  ];
+ *  let users = RowGen::new(columns, 12345);
+ *
+ *  for row in users.rows(0..100) {
+ *      assert!(matches!(row[0], Value::Int(18..=99)));
+ *  }
+ *  assert_eq!(users.row(42), users.rows(40..50).nth(2).unwrap());
+ *  ```
+ */
+use crate::{bounded_u64, bounded_usize, mix64, unit_f64, Pcg};
+use rand_core::{RngCore, SeedableRng};
+use std::ops::{Range, RangeInclusive};
+
+/// A value in a generated row
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Text(String),
+}
+
+#[derive(Clone, Debug)]
+enum Kind {
+    Int(RangeInclusive<i64>),
+    Text {
+        charset: Vec<char>,
+        lengths: RangeInclusive<usize>,
+    },
+    ForeignKey(Vec<i64>),
+}
+
+/// The spec of one column
+#[derive(Clone, Debug)]
+pub struct Column {
+    kind: Kind,
+    null_probability: f64,
+}
+
+impl Column {
+    /// Integers uniform over `range`, which must not be empty.
+    pub fn int(range: RangeInclusive<i64>) -> Column {
+        assert!(!range.is_empty(), "integer range is empty");
+        Column::new(Kind::Int(range))
+    }
+
+    /// Strings of characters from `charset`, with lengths uniform over
+    /// `lengths`. The charset must not be empty.
+    pub fn text(charset: &str, lengths: RangeInclusive<usize>) -> Column {
+        assert!(!charset.is_empty(), "charset is empty");
+        assert!(!lengths.is_empty(), "length range is empty");
+        Column::new(Kind::Text {
+            charset: charset.chars().collect(),
+            lengths,
+        })
+    }
+
+    /// Keys picked uniformly from `pool`, such as the primary keys of a
+    /// table generated earlier. The pool must not be empty.
+    pub fn foreign_key(pool: Vec<i64>) -> Column {
+        assert!(!pool.is_empty(), "foreign key pool is empty");
+        Column::new(Kind::ForeignKey(pool))
+    }
+
+    /// Makes the column null with the given probability.
+    pub fn nullable(mut self, null_probability: f64) -> Column {
+        assert!(
+            (0.0..=1.0).contains(&null_probability),
+            "null probability must lie in [0, 1]"
+        );
+        self.null_probability = null_probability;
+        self
+    }
+
+    fn new(kind: Kind) -> Column {
+        Column {
+            kind,
+            null_probability: 0.0,
+        }
+    }
+
+    fn generate<R: RngCore + ?Sized>(&self, rng: &mut R) -> Value {
+        if unit_f64(rng) < self.null_probability {
+            return Value::Null;
+        }
+        match &self.kind {
+            Kind::Int(range) => {
+                let span = range.end().wrapping_sub(*range.start()) as u64;
+                let offset = if span == u64::MAX {
+                    rng.next_u64()
+                } else {
+                    bounded_u64(rng, span + 1)
+                };
+                Value::Int(range.start().wrapping_add(offset as i64))
+            }
+            Kind::Text { charset, lengths } => {
+                let len = lengths.start() + bounded_usize(rng, lengths.end() - lengths.start() + 1);
+                let text = (0..len)
+                    .map(|_| charset[bounded_usize(rng, charset.len())])
+                    .collect();
+                Value::Text(text)
+            }
+            Kind::ForeignKey(pool) => Value::Int(pool[bounded_usize(rng, pool.len())]),
+        }
+    }
+}
+
+/// Generates rows of values for a list of columns
+#[derive(Clone, Debug)]
+pub struct RowGen {
+    columns: Vec<Column>,
+    seed: u64,
+}
+
+impl RowGen {
+    pub fn new(columns: Vec<Column>, seed: u64) -> RowGen {
+        RowGen { columns, seed }
+    }
+
+    /// The row at `index`, which depends only on the seed, the columns
+    /// and the index.
+    pub fn row(&self, index: u64) -> Vec<Value> {
+        // each cell gets its own generator, so that a null or a longer
+        // string in one column cannot shift the values of the next
+        let row_seed = mix64(self.seed ^ mix64(index));
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let mut pcg = Pcg::seed_from_u64(mix64(row_seed.wrapping_add(i as u64)));
+                column.generate(&mut pcg)
+            })
+            .collect()
+    }
+
+    /// The rows with indices in `range`, in order.
+    pub fn rows(&self, range: Range<u64>) -> impl Iterator<Item = Vec<Value>> + '_ {
+        range.map(move |index| self.row(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns() {
+        let columns = vec![
+            Column::int(-5..=5),
+            Column::text("xyz", 2..=4),
+            Column::foreign_key(vec![7, 8]),
+            Column::int(i64::MIN..=i64::MAX).nullable(1.0),
+        ];
+        let gen = RowGen::new(columns, rand::random::<u64>());
+        for row in gen.rows(0..1000) {
+            assert_eq!(row.len(), 4);
+            assert!(matches!(row[0], Value::Int(-5..=5)));
+            match &row[1] {
+                Value::Text(s) => {
+                    assert!((2..=4).contains(&s.len()));
+                    assert!(s.chars().all(|c| "xyz".contains(c)));
+                }
+                other => panic!("expected text, got {:?}", other),
+            }
+            assert!(row[2] == Value::Int(7) || row[2] == Value::Int(8));
+            assert_eq!(row[3], Value::Null);
+        }
+    }
+
+    #[test]
+    fn test_nulls() {
+        let gen = RowGen::new(
+            vec![Column::int(0..=9).nullable(0.25)],
+            rand::random::<u64>(),
+        );
+        let nulls = gen
+            .rows(0..10000)
+            .filter(|row| row[0] == Value::Null)
+            .count();
+        assert!((2300..2700).contains(&nulls));
+    }
+
+    #[test]
+    fn test_rows_are_independent() {
+        let seed = rand::random::<u64>();
+        let columns = vec![Column::int(0..=1_000_000), Column::text("ab", 0..=8)];
+        let gen = RowGen::new(columns.clone(), seed);
+        let all: Vec<Vec<Value>> = gen.rows(0..100).collect();
+        let shard: Vec<Vec<Value>> = RowGen::new(columns, seed).rows(50..100).collect();
+        assert_eq!(&all[50..], &shard[..]);
+
+        // a column's nullability does not disturb the others
+        let nullable = RowGen::new(
+            vec![
+                Column::int(0..=1_000_000).nullable(0.5),
+                Column::text("ab", 0..=8),
+            ],
+            seed,
+        );
+        for (index, row) in all.iter().enumerate() {
+            assert_eq!(nullable.row(index as u64)[1], row[1]);
+        }
+    }
+}
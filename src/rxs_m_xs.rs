@@ -0,0 +1,203 @@
+/*! PCG-RXS-M-XS 64/64 (LCG), whose output is the whole state, permuted.
+ *
+ *  Every step of the permutation is invertible, so each 64-bit output
+ *  identifies the state that produced it. `unoutput` recovers that state,
+ *  which lets a bug report quoting one suspicious value be replayed from
+ *  exactly that point. The flip side is that every output reveals the
+ *  state, so this variant suits debugging and hashing better than
+ *  anything an adversary can observe. With the same seed, outputs match
+ *  the reference library's `pcg_oneseq_64_rxs_m_xs_64`.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::PcgRxsMXs;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = PcgRxsMXs::seed_from_u64(12345);
+ *  let suspicious = pcg.next_u64();
+ *  let following = pcg.next_u64();
+ *
+ *  let mut replay = PcgRxsMXs::from_state(PcgRxsMXs::unoutput(suspicious));
+ *  assert_eq!(replay.next_u64(), suspicious);
+ *  assert_eq!(replay.next_u64(), following);
+ *  ```
+ */
+use crate::{arr_to_u64, PcgSeed};
+use rand_core::{impls, Error, RngCore, SeedableRng};
+use std::num::Wrapping;
+
+const MULTIPLIER: u64 = 6364136223846793005;
+/// The reference library's default increment
+const INCREMENT: u64 = 1442695040888963407;
+/// The output's multiplier, and its inverse
+const OUTPUT_MULTIPLIER: u64 = 12605985483714917081;
+const OUTPUT_INVERSE: u64 = 0xD04CA582ACB86D69;
+
+#[derive(Clone, Debug)]
+pub struct PcgRxsMXs {
+    state: u64,
+}
+
+impl PcgRxsMXs {
+    /// Creates a generator whose next output is the permutation of
+    /// `state`, such as one recovered by `unoutput`.
+    pub fn from_state(state: u64) -> PcgRxsMXs {
+        PcgRxsMXs { state }
+    }
+
+    /// The state that produces `output`. Since the output permutation is
+    /// a bijection, exactly one state does.
+    pub fn unoutput(output: u64) -> u64 {
+        // undo the final xorshift; 43 bits is over half the word, so one
+        // xor restores it
+        let word = output ^ (output >> 43);
+        let word = word.wrapping_mul(OUTPUT_INVERSE);
+        // the random xorshift leaves the top five bits, which chose its
+        // shift, untouched; each pass then restores `shift` more bits
+        let shift = (word >> 59) + 5;
+        let mut state = word;
+        for _ in 0..64 / shift {
+            state = word ^ (state >> shift);
+        }
+        state
+    }
+
+    /// Advances the state by n steps, as if calling next_u64() n times,
+    /// or rewinds it for negative n, in O(log n) time.
+    pub fn skip(&mut self, n: i64) {
+        // Brown's algorithm, as for Pcg32
+        let mut delta = n as u64;
+        let (mut mult, mut plus) = (Wrapping(MULTIPLIER), Wrapping(INCREMENT));
+        let (mut acc_mult, mut acc_plus) = (Wrapping(1u64), Wrapping(0u64));
+        while delta > 0 {
+            if delta & 1 == 1 {
+                acc_mult *= mult;
+                acc_plus = acc_plus * mult + plus;
+            }
+            plus = (mult + Wrapping(1)) * plus;
+            mult *= mult;
+            delta >>= 1;
+        }
+        self.state = (acc_mult * Wrapping(self.state) + acc_plus).0;
+    }
+
+    fn step(&mut self) {
+        self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+    }
+}
+
+fn output(state: u64) -> u64 {
+    let word = ((state >> ((state >> 59) + 5)) ^ state).wrapping_mul(OUTPUT_MULTIPLIER);
+    (word >> 43) ^ word
+}
+
+impl RngCore for PcgRxsMXs {
+    /// Generate a random u32 from the high half of a u64, advancing the
+    /// state one step.
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Generate a random u64, advancing the state one step.
+    fn next_u64(&mut self) -> u64 {
+        let old = self.state;
+        self.step();
+        output(old)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for PcgRxsMXs {
+    type Seed = PcgSeed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::seed_from_u64(arr_to_u64(seed))
+    }
+
+    /// Seeds the generator as the reference library's `srandom` does.
+    fn seed_from_u64(seed: u64) -> Self {
+        let mut pcg = PcgRxsMXs { state: 0 };
+        pcg.step();
+        pcg.state = pcg.state.wrapping_add(seed);
+        pcg.step();
+        pcg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_outputs() {
+        // from the reference library's pcg_oneseq_64_rxs_m_xs_64, seeded
+        // with 42
+        let mut pcg = PcgRxsMXs::seed_from_u64(42);
+        let expected = [
+            0x27a53829edf003a9,
+            0xdf28458e5c04c31c,
+            0x2756dc550bc36037,
+            0xa10325553eb09ee9,
+            0x40a0fccb8d9df09f,
+            0x5c2047cfefb5e9ca,
+        ];
+        for &x in &expected {
+            assert_eq!(pcg.next_u64(), x);
+        }
+    }
+
+    #[test]
+    fn test_unoutput() {
+        assert_eq!(OUTPUT_MULTIPLIER.wrapping_mul(OUTPUT_INVERSE), 1);
+        for _ in 0..1000 {
+            let state = rand::random::<u64>();
+            assert_eq!(PcgRxsMXs::unoutput(output(state)), state);
+        }
+        for &state in &[0, 1, u64::MAX, 1 << 63, 0x07FF_FFFF_FFFF_FFFF] {
+            assert_eq!(PcgRxsMXs::unoutput(output(state)), state);
+        }
+    }
+
+    #[test]
+    fn test_replay_from_output() {
+        let mut pcg = PcgRxsMXs::seed_from_u64(rand::random::<u64>());
+        pcg.skip(rand::random::<u16>() as i64);
+        let outputs: Vec<u64> = (0..10).map(|_| pcg.next_u64()).collect();
+        let mut replay = PcgRxsMXs::from_state(PcgRxsMXs::unoutput(outputs[3]));
+        for &x in &outputs[3..] {
+            assert_eq!(replay.next_u64(), x);
+        }
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut pcg = PcgRxsMXs::seed_from_u64(rand::random::<u64>());
+        let start = pcg.clone();
+        let steps = rand::random::<u16>();
+        let mut stepped = pcg.clone();
+        for _ in 0..steps {
+            stepped.next_u64();
+        }
+        pcg.skip(steps as i64);
+        assert_eq!(pcg.state, stepped.state);
+        pcg.skip(-(steps as i64));
+        assert_eq!(pcg.state, start.state);
+    }
+
+    #[test]
+    fn test_seed_from_u64() {
+        let seed = rand::random::<u64>();
+        let expected = INCREMENT
+            .wrapping_add(seed)
+            .wrapping_mul(MULTIPLIER)
+            .wrapping_add(INCREMENT);
+        assert_eq!(PcgRxsMXs::seed_from_u64(seed).state, expected);
+    }
+}
@@ -0,0 +1,415 @@
+/*! Sampling from sequences too large to hold in memory, and resampling
+ *  without drawing item by item.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::sampling::reservoir_sample;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let sample = reservoir_sample(&mut pcg, 0..1_000_000, 10);
+ *
+ *  assert_eq!(sample.len(), 10);
+ *  ```
+ */
+use crate::distributions::{open_unit_f64, Beta, Distribution};
+use crate::{bounded_u64, bounded_usize, unit_f64};
+use rand_core::RngCore;
+use std::convert::TryFrom;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{self, BufRead, BufReader};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+/// Picks k items uniformly at random from an iterator in a single pass,
+/// holding only k items at a time. If the iterator yields fewer than k
+/// items, all of them are returned.
+pub fn reservoir_sample<T, I, R>(rng: &mut R, iter: I, k: usize) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+    R: RngCore + ?Sized,
+{
+    let mut reservoir = Vec::with_capacity(k);
+    for (i, item) in iter.into_iter().enumerate() {
+        if i < k {
+            reservoir.push(item);
+        } else {
+            let j = bounded_u64(rng, i as u64 + 1) as usize;
+            if j < k {
+                reservoir[j] = item;
+            }
+        }
+    }
+    reservoir
+}
+
+/// Picks k lines uniformly at random from a text file, reading it once
+/// through a buffer. The same seed and file give the same lines.
+#[cfg(feature = "fs")]
+pub fn sample_lines<P, R>(rng: &mut R, path: P, k: usize) -> io::Result<Vec<String>>
+where
+    P: AsRef<Path>,
+    R: RngCore + ?Sized,
+{
+    let reader = BufReader::new(File::open(path)?);
+    let mut error = None;
+    let lines = reader.lines().map_while(|line| match line {
+        Ok(line) => Some(line),
+        Err(e) => {
+            error = Some(e);
+            None
+        }
+    });
+    let sample = reservoir_sample(rng, lines, k);
+    match error {
+        Some(e) => Err(e),
+        None => Ok(sample),
+    }
+}
+
+/// Counts how many times each of `n_items` items is picked in `n_draws`
+/// uniform draws with replacement, as for a bootstrap resample, without
+/// making the draws one by one. Takes O(n_items log n_draws) time, by
+/// drawing each item's count from a binomial distribution conditioned on
+/// the counts before it.
+pub fn sample_with_replacement_counts<R: RngCore + ?Sized>(
+    rng: &mut R,
+    n_items: usize,
+    n_draws: u64,
+) -> Vec<u64> {
+    assert!(n_items > 0 || n_draws == 0, "no items to draw from");
+    let mut remaining = n_draws;
+    let mut counts = Vec::with_capacity(n_items);
+    for i in 0..n_items {
+        let left = (n_items - i) as f64;
+        let count = if i + 1 == n_items {
+            remaining
+        } else {
+            binomial(rng, remaining, 1.0 / left)
+        };
+        counts.push(count);
+        remaining -= count;
+    }
+    counts
+}
+
+/// Picks k of the n points 0..n as initial centers for k-means, by the
+/// k-means++ method: the first uniformly, and each after it with
+/// probability proportional to its squared distance from the nearest
+/// center so far, which spreads the centers out. `distance(i, j)` is the
+/// distance between points i and j. Returns distinct indices in the order
+/// chosen, all n of them if k > n. Calls `distance` at most n k times.
+pub fn kmeanspp_seeds<R, F>(rng: &mut R, n: usize, mut distance: F, k: usize) -> Vec<usize>
+where
+    R: RngCore + ?Sized,
+    F: FnMut(usize, usize) -> f64,
+{
+    let k = k.min(n);
+    let mut centers = Vec::with_capacity(k);
+    if k == 0 {
+        return centers;
+    }
+    centers.push(bounded_usize(rng, n));
+    // squared distance from each point to its nearest center
+    let mut nearest = vec![f64::INFINITY; n];
+    while centers.len() < k {
+        let newest = *centers.last().unwrap();
+        for (i, d) in nearest.iter_mut().enumerate() {
+            let to_newest = if i == newest {
+                0.0
+            } else {
+                distance(i, newest)
+            };
+            *d = d.min(to_newest * to_newest);
+        }
+        let total: f64 = nearest.iter().sum();
+        let next = if total > 0.0 {
+            let mut target = unit_f64(rng) * total;
+            let mut chosen = None;
+            for (i, &d) in nearest.iter().enumerate() {
+                if d > 0.0 {
+                    chosen = Some(i);
+                    if target < d {
+                        break;
+                    }
+                    target -= d;
+                }
+            }
+            // rounding can leave the target just past the last weight,
+            // so the last point with any weight stands in
+            chosen.unwrap()
+        } else {
+            // every remaining point coincides with a center
+            let open: Vec<usize> = (0..n).filter(|i| !centers.contains(i)).collect();
+            open[bounded_usize(rng, open.len())]
+        };
+        centers.push(next);
+    }
+    centers
+}
+
+/// Gap lengths between successes in independent trials, as returned by
+/// `bernoulli_skips`
+#[derive(Clone, Debug)]
+pub struct BernoulliSkips<R> {
+    rng: R,
+    p: f64,
+    /// ln(1 - p), the log of the chance of a trial failing
+    ln_fail: f64,
+}
+
+/// Generates, for a run of independent trials that each succeed with
+/// probability `p`, the number of failures before each success: the
+/// geometric distribution, one draw per gap. Subsampling a sequence by
+/// stepping over these gaps touches only the selected elements, where a
+/// coin flip per element costs a draw each, which for small p is most of
+/// the work. Ends if no further success could be reached within a u64,
+/// so at once for p = 0; for p = 1 every gap is zero and nothing is drawn.
+pub fn bernoulli_skips<R: RngCore>(rng: R, p: f64) -> BernoulliSkips<R> {
+    assert!((0.0..=1.0).contains(&p), "probability must lie in [0, 1]");
+    BernoulliSkips {
+        rng,
+        p,
+        ln_fail: (-p).ln_1p(),
+    }
+}
+
+impl<R: RngCore> Iterator for BernoulliSkips<R> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.p == 0.0 {
+            return None;
+        } else if self.p == 1.0 {
+            return Some(0);
+        }
+        let gap = (open_unit_f64(&mut self.rng).ln() / self.ln_fail).floor();
+        if gap < u64::MAX as f64 {
+            Some(gap as u64)
+        } else {
+            None
+        }
+    }
+}
+
+/// Keeps each item of an iterator independently with a fixed probability,
+/// as returned by `sample_stream`
+#[derive(Clone, Debug)]
+pub struct SampleStream<I, R> {
+    iter: I,
+    skips: BernoulliSkips<R>,
+}
+
+/// Keeps each item of `iter` with probability `fraction`, independently,
+/// for downsampling streams too large to hold. Rather than drawing once
+/// per item, steps over the gaps from `bernoulli_skips`, so sparse samples
+/// cost little more than the iteration. The same generator state and
+/// items give the same sample. Pass `&mut rng` to keep using the
+/// generator afterwards.
+pub fn sample_stream<T, I, R>(rng: R, iter: I, fraction: f64) -> SampleStream<I::IntoIter, R>
+where
+    I: IntoIterator<Item = T>,
+    R: RngCore,
+{
+    assert!(
+        (0.0..=1.0).contains(&fraction),
+        "fraction must lie in [0, 1]"
+    );
+    SampleStream {
+        iter: iter.into_iter(),
+        skips: bernoulli_skips(rng, fraction),
+    }
+}
+
+impl<I: Iterator, R: RngCore> Iterator for SampleStream<I, R> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let gap = self.skips.next()?;
+        self.iter.nth(usize::try_from(gap).ok()?)
+    }
+}
+
+/// Generates the number of successes in n independent trials that each
+/// succeed with probability p.
+fn binomial<R: RngCore + ?Sized>(rng: &mut R, mut n: u64, mut p: f64) -> u64 {
+    let mut successes = 0;
+    // Devroye's order statistic method: the ith smallest of n uniforms
+    // is Beta(i, n + 1 - i) distributed, and splits the trials into those
+    // below it, which succeed with probability p / y, and those above
+    while n > 64 {
+        let i = n.div_ceil(2);
+        let y = Beta::new(i as f64, (n + 1 - i) as f64).unwrap().sample(rng);
+        if y <= p {
+            successes += i;
+            n -= i;
+            p = (p - y) / (1.0 - y);
+        } else {
+            n = i - 1;
+            p /= y;
+        }
+    }
+    successes + (0..n).filter(|_| unit_f64(rng) < p).count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_reservoir_sample() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert_eq!(reservoir_sample(&mut pcg, 0..3, 5), vec![0, 1, 2]);
+        let mut sample = reservoir_sample(&mut pcg, 0..1000, 10);
+        sample.sort_unstable();
+        sample.dedup();
+        assert_eq!(sample.len(), 10);
+
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut counts = [0; 10];
+        for _ in 0..10000 {
+            for i in reservoir_sample(&mut pcg, 0..10, 3) {
+                counts[i] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&c| (2700..3300).contains(&c)));
+    }
+
+    #[test]
+    fn test_sample_with_replacement_counts() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let counts = sample_with_replacement_counts(&mut pcg, 10, 1_000_000);
+        assert_eq!(counts.iter().sum::<u64>(), 1_000_000);
+        // each count is Binomial(10^6, 0.1), with standard deviation 300
+        assert!(counts.iter().all(|&c| (98500..101500).contains(&c)));
+
+        let counts = sample_with_replacement_counts(&mut pcg, 5, 3);
+        assert_eq!(counts.len(), 5);
+        assert_eq!(counts.iter().sum::<u64>(), 3);
+        assert_eq!(
+            sample_with_replacement_counts(&mut pcg, 3, 0),
+            vec![0, 0, 0]
+        );
+        assert!(sample_with_replacement_counts(&mut pcg, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_kmeanspp_seeds() {
+        // three tight, well separated clusters of ten points on a line
+        let points: Vec<f64> = (0..30)
+            .map(|i| (i / 10) as f64 * 100.0 + (i % 10) as f64 * 0.1)
+            .collect();
+        let distance = |i: usize, j: usize| (points[i] - points[j]).abs();
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut spread = 0;
+        for _ in 0..100 {
+            let mut clusters: Vec<usize> = kmeanspp_seeds(&mut pcg, 30, distance, 3)
+                .iter()
+                .map(|&c| c / 10)
+                .collect();
+            clusters.sort_unstable();
+            spread += (clusters == vec![0, 1, 2]) as usize;
+        }
+        // each later center lands in a new cluster with chance over 0.999
+        assert!(spread >= 98);
+
+        let seed = rand::random::<u64>();
+        let first = kmeanspp_seeds(&mut Pcg::seed_from_u64(seed), 30, distance, 5);
+        let again = kmeanspp_seeds(&mut Pcg::seed_from_u64(seed), 30, distance, 5);
+        assert_eq!(first, again);
+
+        // duplicate points still give distinct centers
+        let mut all = kmeanspp_seeds(&mut pcg, 4, |_, _| 0.0, 10);
+        all.sort_unstable();
+        assert_eq!(all, vec![0, 1, 2, 3]);
+        assert!(kmeanspp_seeds(&mut pcg, 0, distance, 3).is_empty());
+        assert!(kmeanspp_seeds(&mut pcg, 30, distance, 0).is_empty());
+    }
+
+    #[test]
+    fn test_bernoulli_skips() {
+        // Geometric(0.01) gaps have mean 99 and variance 9900
+        let pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let gaps: Vec<f64> = bernoulli_skips(pcg, 0.01)
+            .take(20000)
+            .map(|gap| gap as f64)
+            .collect();
+        let mean = gaps.iter().sum::<f64>() / 20000.0;
+        let variance = gaps.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 20000.0;
+        assert!((mean - 99.0).abs() < 2.0);
+        assert!((variance / 9900.0 - 1.0).abs() < 0.06);
+
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let zeros = bernoulli_skips(&mut pcg, 0.5)
+            .take(10000)
+            .filter(|&gap| gap == 0);
+        assert!((4800..5200).contains(&zeros.count()));
+        assert_eq!(bernoulli_skips(&mut pcg, 0.0).next(), None);
+        assert!(bernoulli_skips(&mut pcg, 1e-300).take(100).count() < 100);
+        let draws = pcg.draw_count();
+        assert!(bernoulli_skips(&mut pcg, 1.0).take(10).all(|gap| gap == 0));
+        assert_eq!(pcg.draw_count(), draws);
+    }
+
+    #[test]
+    fn test_sample_stream() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut counts = [0; 10];
+        for _ in 0..10000 {
+            for i in sample_stream(&mut pcg, 0..10, 0.3) {
+                counts[i] += 1;
+            }
+        }
+        assert!(counts.iter().all(|&c| (2850..3150).contains(&c)));
+
+        let sample: Vec<u64> = sample_stream(&mut pcg, 0..1_000_000, 0.001).collect();
+        assert!((900..1100).contains(&sample.len()));
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+
+        let seed = rand::random::<u64>();
+        let first: Vec<u32> = sample_stream(Pcg::seed_from_u64(seed), 0..1000, 0.1).collect();
+        let again: Vec<u32> = sample_stream(Pcg::seed_from_u64(seed), 0..1000, 0.1).collect();
+        assert_eq!(first, again);
+
+        assert_eq!(sample_stream(&mut pcg, 0..5, 0.0).count(), 0);
+        let all: Vec<i32> = sample_stream(&mut pcg, 0..5, 1.0).collect();
+        assert_eq!(all, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_binomial() {
+        // Binomial(1000, 0.3) has mean 300 and variance 210
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let draws: Vec<f64> = (0..10000)
+            .map(|_| binomial(&mut pcg, 1000, 0.3) as f64)
+            .collect();
+        let mean = draws.iter().sum::<f64>() / 10000.0;
+        let variance = draws.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / 10000.0;
+        assert!((mean - 300.0).abs() < 1.0);
+        assert!((variance - 210.0).abs() < 15.0);
+        assert_eq!(binomial(&mut pcg, 1000, 0.0), 0);
+        assert_eq!(binomial(&mut pcg, 1000, 1.0), 1000);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_sample_lines() {
+        let path = std::env::temp_dir().join(format!("pcg-lines-{}", rand::random::<u64>()));
+        let text: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, text).unwrap();
+
+        let seed = rand::random::<u64>();
+        let lines = sample_lines(&mut Pcg::seed_from_u64(seed), &path, 5).unwrap();
+        assert_eq!(lines.len(), 5);
+        assert!(lines.iter().all(|line| line.starts_with("line ")));
+        let again = sample_lines(&mut Pcg::seed_from_u64(seed), &path, 5).unwrap();
+        assert_eq!(lines, again);
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(sample_lines(&mut Pcg::seed_from_u64(seed), &path, 5).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+/*! Reproducible randomized test ordering, for custom test harnesses.
+ *
+ *  Running tests in a random order flushes out hidden dependencies
+ *  between them; running them in the same random order again, from the
+ *  seed a failing run printed, reproduces the failure. The order depends
+ *  only on the seed and the set of names, not on the order the harness
+ *  discovered them in, and each test's seed depends only on the run's
+ *  seed and its own name, so a single test can be rerun alone with the
+ *  seed it saw in the full run.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::schedule::{schedule, test_seed};
+ *  let names = ["parses_empty", "parses_nested", "rejects_garbage"];
+ *  let run_seed = 12345; // e.g. from an environment variable
+ *
+ *  for test in schedule(run_seed, &names) {
+ *      println!("running {} with seed {:#x}", test.name, test.seed);
+ *  }
+ *
+ *  let rerun = schedule(run_seed, &["rejects_garbage", "parses_empty", "parses_nested"]);
+ *  assert_eq!(rerun, schedule(run_seed, &names));
+ *  assert_eq!(rerun[0].seed, test_seed(run_seed, rerun[0].name));
+ *  ```
+ */
+use crate::{hash_label, mix64};
+
+/// A test's place in a randomized run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScheduledTest<'a> {
+    pub name: &'a str,
+    /// A seed for the test's own randomness, which it may ignore
+    pub seed: u64,
+}
+
+/// Orders the named tests by a permutation derived from `run_seed`,
+/// pairing each with its seed. Adding or removing a test leaves the
+/// relative order of the others unchanged.
+pub fn schedule<'a>(run_seed: u64, names: &[&'a str]) -> Vec<ScheduledTest<'a>> {
+    let mut tests: Vec<ScheduledTest<'a>> = names
+        .iter()
+        .map(|&name| ScheduledTest {
+            name,
+            seed: test_seed(run_seed, name),
+        })
+        .collect();
+    // sorting by a hash of each seed is a uniform shuffle, barring
+    // collisions, which fall back to the names
+    tests.sort_by_key(|test| (mix64(test.seed), test.name));
+    tests
+}
+
+/// The seed `schedule` gives the test named `name` in the run seeded
+/// with `run_seed`.
+pub fn test_seed(run_seed: u64, name: &str) -> u64 {
+    mix64(run_seed ^ mix64(hash_label(name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAMES: [&str; 6] = ["a", "b", "c", "d", "e", "f"];
+
+    #[test]
+    fn test_schedule() {
+        let seed = rand::random::<u64>();
+        let tests = schedule(seed, &NAMES);
+        let mut names: Vec<&str> = tests.iter().map(|t| t.name).collect();
+        names.sort_unstable();
+        assert_eq!(names, NAMES);
+        for test in &tests {
+            assert_eq!(test.seed, test_seed(seed, test.name));
+        }
+
+        let mut reversed = NAMES;
+        reversed.reverse();
+        assert_eq!(schedule(seed, &reversed), tests);
+        assert!((1..4).any(|i| schedule(seed ^ i, &NAMES) != tests));
+    }
+
+    #[test]
+    fn test_schedule_subset() {
+        let seed = rand::random::<u64>();
+        let full: Vec<&str> = schedule(seed, &NAMES).iter().map(|t| t.name).collect();
+        let partial: Vec<&str> = schedule(seed, &NAMES[1..]).iter().map(|t| t.name).collect();
+        let expected: Vec<&str> = full.into_iter().filter(|&n| n != "a").collect();
+        assert_eq!(partial, expected);
+    }
+
+    #[test]
+    fn test_schedule_uniform() {
+        // each of the 6 orders of three tests should be equally likely
+        let mut counts = std::collections::HashMap::new();
+        let base = rand::random::<u64>();
+        for seed in base..base + 6000 {
+            let order: Vec<&str> = schedule(seed, &NAMES[..3]).iter().map(|t| t.name).collect();
+            *counts.entry(order).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 6);
+        assert!(counts.values().all(|&n| (850..1150).contains(&n)));
+    }
+
+    #[test]
+    fn test_schedule_is_stable() {
+        let order: Vec<&str> = schedule(12345, &NAMES).iter().map(|t| t.name).collect();
+        assert_eq!(order, STABLE_ORDER);
+    }
+
+    /// Changing this order changes the reruns of every seed users have
+    /// recorded; it may only change together with the stream format version.
+    const STABLE_ORDER: [&str; 6] = ["c", "e", "f", "a", "d", "b"];
+}
@@ -0,0 +1,157 @@
+/*! Searching many seeds for interesting ones, such as procedural worlds
+ *  worth a closer look or inputs that make a test work hardest. Scoring
+ *  is often the slow part, so `seed_search_parallel` spreads it across
+ *  threads, ranking the seeds exactly as `seed_search` does. Like
+ *  `analysis::find_bad_seeds_parallel`, it uses the standard library's
+ *  scoped threads rather than a thread pool crate, so parallel search
+ *  needs no extra dependency or feature.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::search::seed_search;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  # use pcg::Pcg;
+ *  // find the seeds whose first draw is largest
+ *  let ranked = seed_search(12345, 100, |pcg| pcg.next_u32());
+ *
+ *  let (best_seed, best_score) = ranked[0];
+ *  assert_eq!(Pcg::seed_from_u64(best_seed).next_u32(), best_score);
+ *  ```
+ */
+use crate::{mix64, Pcg};
+use rand_core::SeedableRng;
+use std::cmp::Ordering;
+use std::ops::Range;
+
+/// Scores `n_seeds` seeds derived from `master_seed`, passing the
+/// generator for each to `score_fn`, and returns the seeds with their
+/// scores from highest to lowest. Seeding a `Pcg` with a returned seed
+/// reproduces the stream it was scored on.
+///
+/// Scores that cannot be compared, such as NaN, rank last.
+pub fn seed_search<S, F>(master_seed: u64, n_seeds: usize, score_fn: F) -> Vec<(u64, S)>
+where
+    S: PartialOrd,
+    F: FnMut(&mut Pcg) -> S,
+{
+    rank(score_range(master_seed, 0..n_seeds as u64, score_fn))
+}
+
+/// As `seed_search`, splitting the seeds between `threads` threads, so
+/// `score_fn` must be shareable between them. The ranking is the same as
+/// `seed_search` gives, ties included.
+pub fn seed_search_parallel<S, F>(
+    master_seed: u64,
+    n_seeds: usize,
+    score_fn: F,
+    threads: usize,
+) -> Vec<(u64, S)>
+where
+    S: PartialOrd + Send,
+    F: Fn(&mut Pcg) -> S + Sync,
+{
+    assert!(threads > 0, "at least one thread is needed");
+    let n = n_seeds as u64;
+    let chunk = n.div_ceil(threads as u64).max(1);
+    let score_fn = &score_fn;
+    let scored = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..n)
+            .step_by(chunk as usize)
+            .map(|start| {
+                scope.spawn(move || score_range(master_seed, start..n.min(start + chunk), score_fn))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    rank(scored)
+}
+
+/// Scores the seeds with the given indices, in order
+fn score_range<S, F>(master_seed: u64, indices: Range<u64>, mut score_fn: F) -> Vec<(u64, S)>
+where
+    F: FnMut(&mut Pcg) -> S,
+{
+    indices
+        .map(|i| {
+            let seed = mix64(master_seed ^ mix64(i));
+            (seed, score_fn(&mut Pcg::seed_from_u64(seed)))
+        })
+        .collect()
+}
+
+/// Sorts scored seeds from highest to lowest, incomparable scores last
+fn rank<S: PartialOrd>(mut ranked: Vec<(u64, S)>) -> Vec<(u64, S)> {
+    // the sort is stable, so ties keep the order they were derived in
+    ranked.sort_by(
+        |(_, a), (_, b)| match (a.partial_cmp(a), b.partial_cmp(b)) {
+            (Some(_), Some(_)) => b.partial_cmp(a).unwrap_or(Ordering::Equal),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    );
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::RngCore;
+
+    #[test]
+    fn test_ranking() {
+        let master = rand::random::<u64>();
+        let ranked = seed_search(master, 50, |pcg| pcg.next_u64());
+        assert_eq!(ranked.len(), 50);
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+        for &(seed, score) in &ranked {
+            assert_eq!(Pcg::seed_from_u64(seed).next_u64(), score);
+        }
+        assert_eq!(seed_search(master, 50, |pcg| pcg.next_u64()), ranked);
+    }
+
+    #[test]
+    fn test_stateful_score() {
+        // the serial search takes scorers that are neither Fn nor Send
+        let scored = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut calls = 0;
+        let ranked = seed_search(rand::random::<u64>(), 10, |pcg| {
+            calls += 1;
+            let score = pcg.next_u32();
+            scored.borrow_mut().push(score);
+            score
+        });
+        assert_eq!(calls, 10);
+        assert_eq!(scored.borrow().iter().max(), Some(&ranked[0].1));
+    }
+
+    #[test]
+    fn test_parallel() {
+        let master = rand::random::<u64>();
+        // few distinct scores, so ties must keep their order too
+        let score = |pcg: &mut Pcg| pcg.next_u32() % 4;
+        let serial = seed_search(master, 101, score);
+        for &threads in &[1, 3, 8, 200] {
+            assert_eq!(seed_search_parallel(master, 101, score, threads), serial);
+        }
+        assert!(seed_search_parallel(master, 0, score, 4).is_empty());
+    }
+
+    #[test]
+    fn test_nan_last() {
+        let ranked = seed_search(rand::random::<u64>(), 20, |pcg| match pcg.next_u32() % 3 {
+            0 => f64::NAN,
+            x => x as f64,
+        });
+        let first_nan = ranked.iter().position(|(_, s)| s.is_nan());
+        if let Some(i) = first_nan {
+            assert!(ranked[i..].iter().all(|(_, s)| s.is_nan()));
+        }
+        assert!(ranked
+            .iter()
+            .all(|(_, s)| s.is_nan() || *s == 1.0 || *s == 2.0));
+    }
+}
@@ -0,0 +1,177 @@
+/*! Probabilistic load shedding driven by queueing delay.
+ *
+ *  A `LoadShedder` turns a controller input, the delay requests currently
+ *  see, into a probability of dropping each new request: none at or below
+ *  a target delay, rising linearly to a maximum at an upper bound. The
+ *  decisions come from the shedder's own stream, one draw per request
+ *  whatever the load, so a simulated overload replays exactly and the
+ *  same code can shed real traffic.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::shedding::LoadShedder;
+ *  # use std::time::Duration;
+ *  let mut shedder = LoadShedder::new(Duration::from_millis(50), Duration::from_millis(250), 12345);
+ *
+ *  shedder.observe(Duration::from_millis(150));
+ *  for _ in 0..1000 {
+ *      if shedder.should_shed() {
+ *          continue; // reject the request
+ *      }
+ *      // serve the request
+ *  }
+ *
+ *  assert_eq!(shedder.drop_probability(), 0.5);
+ *  assert_eq!(shedder.admitted() + shedder.shed(), 1000);
+ *  ```
+ */
+use crate::distributions::{Bernoulli, Distribution};
+use crate::Pcg;
+use rand_core::SeedableRng;
+use std::time::Duration;
+
+/// Decides which requests to drop, and counts its decisions
+#[derive(Clone)]
+pub struct LoadShedder {
+    pcg: Pcg,
+    target: Duration,
+    max: Duration,
+    max_drop: f64,
+    coin: Bernoulli,
+    admitted: u64,
+    shed: u64,
+}
+
+impl LoadShedder {
+    /// Creates a shedder that starts dropping once the observed delay
+    /// passes `target` and drops every request from `max` on. Decisions
+    /// are drawn from a stream seeded with `seed`.
+    pub fn new(target: Duration, max: Duration, seed: u64) -> LoadShedder {
+        assert!(target < max, "target delay must be below the maximum");
+        LoadShedder {
+            pcg: Pcg::seed_from_u64(seed),
+            target,
+            max,
+            max_drop: 1.0,
+            coin: Bernoulli::new(0.0).unwrap(),
+            admitted: 0,
+            shed: 0,
+        }
+    }
+
+    /// Caps the drop probability, so that some requests always get
+    /// through to measure whether the overload has passed.
+    pub fn with_max_drop_probability(mut self, p: f64) -> LoadShedder {
+        assert!((0.0..=1.0).contains(&p), "probability must lie in [0, 1]");
+        self.max_drop = p;
+        self
+    }
+
+    /// Sets the controller input: the delay requests are currently seeing.
+    pub fn observe(&mut self, delay: Duration) {
+        let excess = delay.saturating_sub(self.target).as_secs_f64();
+        let span = (self.max - self.target).as_secs_f64();
+        let p = (excess / span).min(1.0) * self.max_drop;
+        self.coin = Bernoulli::new(p).unwrap();
+    }
+
+    /// The probability with which requests are currently dropped.
+    pub fn drop_probability(&self) -> f64 {
+        self.coin.p()
+    }
+
+    /// Decides whether to drop the next request, counting the decision.
+    pub fn should_shed(&mut self) -> bool {
+        let shed = self.coin.sample(&mut self.pcg);
+        if shed {
+            self.shed += 1;
+        } else {
+            self.admitted += 1;
+        }
+        shed
+    }
+
+    /// The number of requests let through since creation or the last reset.
+    pub fn admitted(&self) -> u64 {
+        self.admitted
+    }
+
+    /// The number of requests dropped since creation or the last reset.
+    pub fn shed(&self) -> u64 {
+        self.shed
+    }
+
+    /// Zeroes the counters, leaving the stream and input as they are.
+    pub fn reset_counters(&mut self) {
+        self.admitted = 0;
+        self.shed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shedder() -> LoadShedder {
+        LoadShedder::new(Duration::from_millis(100), Duration::from_millis(300), 42)
+    }
+
+    #[test]
+    fn test_drop_probability() {
+        let mut shedder = shedder();
+        assert_eq!(shedder.drop_probability(), 0.0);
+        shedder.observe(Duration::from_millis(100));
+        assert_eq!(shedder.drop_probability(), 0.0);
+        shedder.observe(Duration::from_millis(150));
+        assert_eq!(shedder.drop_probability(), 0.25);
+        shedder.observe(Duration::from_secs(10));
+        assert_eq!(shedder.drop_probability(), 1.0);
+
+        let mut capped = shedder.with_max_drop_probability(0.9);
+        capped.observe(Duration::from_secs(10));
+        assert!((capped.drop_probability() - 0.9).abs() < 1e-9);
+        capped.observe(Duration::from_millis(200));
+        assert!((capped.drop_probability() - 0.45).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_counters() {
+        let mut shedder = shedder();
+        for _ in 0..100 {
+            assert!(!shedder.should_shed());
+        }
+        assert_eq!((shedder.admitted(), shedder.shed()), (100, 0));
+        shedder.observe(Duration::from_millis(250));
+        let shed = (0..10000).filter(|_| shedder.should_shed()).count() as u64;
+        assert!((7300..7700).contains(&shed));
+        assert_eq!(shedder.shed(), shed);
+        assert_eq!(shedder.admitted(), 10100 - shed);
+        shedder.reset_counters();
+        assert_eq!((shedder.admitted(), shedder.shed()), (0, 0));
+    }
+
+    #[test]
+    fn test_replay() {
+        // one draw per decision, so the stream stays aligned under any load
+        let decisions = |delays: &[u64]| {
+            let mut shedder = shedder();
+            delays
+                .iter()
+                .map(|&ms| {
+                    shedder.observe(Duration::from_millis(ms));
+                    shedder.should_shed()
+                })
+                .collect::<Vec<bool>>()
+        };
+        let delays: Vec<u64> = (0..500).map(|i| i % 400).collect();
+        assert_eq!(decisions(&delays), decisions(&delays));
+        let mut quiet = shedder();
+        let mut busy = shedder();
+        busy.observe(Duration::from_millis(200));
+        for _ in 0..50 {
+            quiet.should_shed();
+            busy.should_shed();
+        }
+        assert_eq!(quiet.pcg.draw_count(), busy.pcg.draw_count());
+    }
+}
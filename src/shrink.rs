@@ -0,0 +1,261 @@
+/*! A recordable, replayable generator for shrinking failing randomized
+ *  tests, in the style of Hypothesis's choice sequences.
+ *
+ *  A `ShrinkablePcg` records every u32 it hands out. Replaying an edited
+ *  copy of that record, with draws deleted or made smaller, drives the
+ *  test towards simpler inputs, since smaller draws produce smaller
+ *  values from the bounded and float helpers built on them. `shrink`
+ *  performs that search greedily, keeping any edit under which the test
+ *  still fails.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::shrink::{shrink, ShrinkablePcg};
+ *  # use rand_core::RngCore;
+ *  // a "test" that fails when a list of random numbers has a large sum
+ *  fn fails(rng: &mut ShrinkablePcg) -> bool {
+ *      let len = rng.next_u32() % 10;
+ *      let list: Vec<u64> = (0..len).map(|_| (rng.next_u32() % 1000) as u64).collect();
+ *      list.iter().sum::<u64>() > 1500
+ *  }
+ *
+ *  let failing = (0..)
+ *      .map(ShrinkablePcg::new)
+ *      .find_map(|mut rng| if fails(&mut rng) { Some(rng) } else { None })
+ *      .unwrap();
+ *  let minimal = shrink(failing.choices(), fails);
+ *
+ *  // replaying the result still fails, with a short list of draws
+ *  assert!(fails(&mut ShrinkablePcg::replay(&minimal)));
+ *  assert!(minimal.len() <= 4);
+ *  ```
+ */
+use crate::Pcg;
+use rand_core::{impls, Error, RngCore, SeedableRng};
+
+/// Seeds the draws that follow a replayed sequence
+const OVERRUN_SEED: u64 = 0x9E3779B97F4A7C15;
+
+/// A generator that records its draws, or replays a recorded sequence
+#[derive(Clone)]
+pub struct ShrinkablePcg {
+    /// Draws beyond the replayed sequence, if any, come from here
+    pcg: Pcg,
+    replay: Vec<u32>,
+    choices: Vec<u32>,
+    overran: bool,
+}
+
+impl ShrinkablePcg {
+    /// A fresh generator, recording its draws.
+    pub fn new(seed: u64) -> ShrinkablePcg {
+        ShrinkablePcg {
+            pcg: Pcg::seed_from_u64(seed),
+            replay: Vec::new(),
+            choices: Vec::new(),
+            overran: false,
+        }
+    }
+
+    /// A generator that hands out `choices` in order. Draws past the end
+    /// come from a fixed fallback stream and mark the run as overrun.
+    pub fn replay(choices: &[u32]) -> ShrinkablePcg {
+        ShrinkablePcg {
+            pcg: Pcg::seed_from_u64(OVERRUN_SEED),
+            replay: choices.to_vec(),
+            choices: Vec::with_capacity(choices.len()),
+            overran: false,
+        }
+    }
+
+    /// The draws made so far.
+    pub fn choices(&self) -> &[u32] {
+        &self.choices
+    }
+
+    /// Whether a replay ran past the end of its sequence.
+    pub fn overran(&self) -> bool {
+        self.overran
+    }
+}
+
+impl RngCore for ShrinkablePcg {
+    fn next_u32(&mut self) -> u32 {
+        let i = self.choices.len();
+        let x = if self.replay.is_empty() {
+            self.pcg.next_u32()
+        } else if i < self.replay.len() {
+            self.replay[i]
+        } else {
+            self.overran = true;
+            self.pcg.next_u32()
+        };
+        self.choices.push(x);
+        x
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Whether a is shorter than b, or as long and lexicographically smaller
+fn simpler(a: &[u32], b: &[u32]) -> bool {
+    (a.len(), a) < (b.len(), b)
+}
+
+/// Greedily simplifies a failing choice sequence: deletes blocks of
+/// draws, zeroes them, and shrinks single draws, keeping each change
+/// under which `fails` still returns true without overrunning. Returns
+/// the simplest failing sequence found, which is never more complex than
+/// the input. `fails` must be deterministic given the draws.
+pub fn shrink<F>(choices: &[u32], mut fails: F) -> Vec<u32>
+where
+    F: FnMut(&mut ShrinkablePcg) -> bool,
+{
+    let mut best = choices.to_vec();
+    // the draws a successful run consumed, if simpler than the best so far
+    let mut attempt = |candidate: &[u32], best: &[u32]| {
+        let mut rng = ShrinkablePcg::replay(candidate);
+        if fails(&mut rng) && !rng.overran() && simpler(rng.choices(), best) {
+            Some(rng.choices)
+        } else {
+            None
+        }
+    };
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for &size in &[8, 4, 2, 1] {
+            let mut i = 0;
+            while i + size <= best.len() {
+                let mut candidate = best.clone();
+                candidate.drain(i..i + size);
+                if let Some(shorter) = attempt(&candidate, &best) {
+                    best = shorter;
+                    improved = true;
+                    continue;
+                }
+                // the draw before the block may have been a length or count
+                if i > 0 && candidate[i - 1] > 0 {
+                    candidate[i - 1] -= 1;
+                    if let Some(shorter) = attempt(&candidate, &best) {
+                        best = shorter;
+                        improved = true;
+                        continue;
+                    }
+                }
+                let mut candidate = best.clone();
+                candidate[i..i + size].iter_mut().for_each(|x| *x = 0);
+                if let Some(zeroed) = attempt(&candidate, &best) {
+                    best = zeroed;
+                    improved = true;
+                }
+                i += 1;
+            }
+        }
+        // a smaller draw can shorten the sequence, so recheck the bound
+        let mut i = 0;
+        while i < best.len() {
+            // binary search for the smallest value that still fails
+            let (mut lo, mut hi) = (0, best[i]);
+            while lo < hi && i < best.len() {
+                let mid = lo + (hi - lo) / 2;
+                let mut candidate = best.clone();
+                candidate[i] = mid;
+                match attempt(&candidate, &best) {
+                    Some(smaller) => {
+                        best = smaller;
+                        improved = true;
+                        hi = mid;
+                    }
+                    None => lo = mid + 1,
+                }
+            }
+            i += 1;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounded_u32;
+
+    #[test]
+    fn test_record_and_replay() {
+        let mut rng = ShrinkablePcg::new(rand::random::<u64>());
+        let drawn: Vec<u64> = (0..10).map(|_| rng.next_u64()).collect();
+        assert_eq!(rng.choices().len(), 20);
+
+        let mut replayed = ShrinkablePcg::replay(rng.choices());
+        assert!(drawn.iter().all(|&x| replayed.next_u64() == x));
+        assert!(!replayed.overran());
+        replayed.next_u32();
+        assert!(replayed.overran());
+    }
+
+    #[test]
+    fn test_zeroes_do_not_hang() {
+        // zero is always rejected by the bounded helpers, so a replay of
+        // zeroes must run out into the fallback stream
+        let mut rng = ShrinkablePcg::replay(&[0; 4]);
+        assert!(bounded_u32(&mut rng, 3) < 3);
+        assert!(rng.overran());
+    }
+
+    #[test]
+    fn test_shrink() {
+        // fails when some drawn value exceeds 1000
+        let fails = |rng: &mut ShrinkablePcg| {
+            let len = bounded_u32(rng, 20);
+            (0..len).any(|_| bounded_u32(rng, 10000) > 1000)
+        };
+        let mut seed = rand::random::<u64>();
+        let mut rng = ShrinkablePcg::new(seed);
+        while !fails(&mut rng) {
+            seed += 1;
+            rng = ShrinkablePcg::new(seed);
+        }
+        let minimal = shrink(rng.choices(), fails);
+        let mut replay = ShrinkablePcg::replay(&minimal);
+        assert!(fails(&mut replay));
+        // one element, drawn as the smallest failing value
+        assert_eq!(minimal.len(), 2);
+        let mut replay = ShrinkablePcg::replay(&minimal);
+        assert_eq!(bounded_u32(&mut replay, 20), 1);
+        assert_eq!(bounded_u32(&mut replay, 10000), 1001);
+    }
+
+    #[test]
+    fn test_shrink_length_draw() {
+        // lowering the length draws fewer values, shortening the sequence
+        // while single draws are being minimized
+        let fails = |rng: &mut ShrinkablePcg| {
+            let len = bounded_u32(rng, 40);
+            (0..len).for_each(|_| {
+                rng.next_u32();
+            });
+            len >= 10
+        };
+        for seed in 0..200 {
+            let mut rng = ShrinkablePcg::new(seed);
+            if fails(&mut rng) {
+                let minimal = shrink(rng.choices(), fails);
+                let mut replay = ShrinkablePcg::replay(&minimal);
+                assert_eq!(bounded_u32(&mut replay, 40), 10);
+                assert_eq!(minimal.len(), 11);
+            }
+        }
+    }
+}
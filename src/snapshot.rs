@@ -0,0 +1,259 @@
+/*! Versioned snapshots of generator state.
+ *
+ *  A snapshot records which algorithm produced it and the stream format
+ *  version of the crate that wrote it, alongside the state itself.
+ *  Restoring refuses snapshots from another algorithm or a newer format,
+ *  rather than silently replaying a different sequence.
+ *
+ *  The byte layout is 12 bytes, all little-endian:
+ *  algorithm id (u16), format version (u16), state (u64).
+ *
+ *  Where the algorithm and version are known from context, `Pcg::to_bytes`
+ *  writes the bare state instead: 8 bytes, the u64 state little-endian.
+ *  Neither layout will change in future versions of this crate.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::snapshot::Snapshot;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let bytes = pcg.snapshot().to_bytes();
+ *  let x = pcg.next_u32();
+ *
+ *  let mut restored = Pcg::restore(&Snapshot::from_bytes(bytes)).unwrap();
+ *  assert_eq!(restored.next_u32(), x);
+ *  ```
+ */
+use crate::Pcg;
+use std::error::Error;
+use std::fmt;
+
+/// The version of the stream format written by this crate. It changes
+/// whenever a given seed would produce a different sequence.
+pub const STREAM_FORMAT_VERSION: u16 = 1;
+
+const SNAPSHOT_LEN: usize = 12;
+
+/// The generator algorithms a snapshot may record
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// PCG-XSH-RS-64/32 (MCG), as implemented by Pcg
+    XshRs6432Mcg,
+}
+
+impl Algorithm {
+    pub fn id(self) -> u16 {
+        match self {
+            Algorithm::XshRs6432Mcg => 1,
+        }
+    }
+
+    pub fn from_id(id: u16) -> Option<Algorithm> {
+        match id {
+            1 => Some(Algorithm::XshRs6432Mcg),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::XshRs6432Mcg => write!(f, "PCG-XSH-RS-64/32 (MCG)"),
+        }
+    }
+}
+
+/// A point-in-time copy of a generator's state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    algorithm: u16,
+    version: u16,
+    state: u64,
+}
+
+/// The reasons a snapshot may be refused
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot was taken from a different algorithm
+    IncompatibleAlgorithm { expected: Algorithm, found: u16 },
+    /// The snapshot was written by a newer stream format
+    IncompatibleVersion { supported: u16, found: u16 },
+    /// The recorded state can never be reached by the algorithm
+    InvalidState,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::IncompatibleAlgorithm { expected, found } => {
+                match Algorithm::from_id(*found) {
+                    Some(algorithm) => {
+                        write!(f, "snapshot is from {}, expected {}", algorithm, expected)
+                    }
+                    None => write!(
+                        f,
+                        "snapshot is from unknown algorithm {}, expected {}",
+                        found, expected
+                    ),
+                }
+            }
+            SnapshotError::IncompatibleVersion { supported, found } => write!(
+                f,
+                "snapshot has stream format version {}, newest supported is {}",
+                found, supported
+            ),
+            SnapshotError::InvalidState => write!(f, "snapshot state is invalid"),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl Snapshot {
+    /// The id of the algorithm that produced this snapshot.
+    pub fn algorithm(&self) -> u16 {
+        self.algorithm
+    }
+
+    /// The stream format version this snapshot was written with.
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    pub fn to_bytes(&self) -> [u8; SNAPSHOT_LEN] {
+        let mut bytes = [0; SNAPSHOT_LEN];
+        bytes[0..2].copy_from_slice(&self.algorithm.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..12].copy_from_slice(&self.state.to_le_bytes());
+        bytes
+    }
+
+    /// Parses a snapshot; compatibility is checked when it is restored.
+    pub fn from_bytes(bytes: [u8; SNAPSHOT_LEN]) -> Snapshot {
+        let mut state = [0; 8];
+        state.copy_from_slice(&bytes[4..12]);
+        Snapshot {
+            algorithm: u16::from_le_bytes([bytes[0], bytes[1]]),
+            version: u16::from_le_bytes([bytes[2], bytes[3]]),
+            state: u64::from_le_bytes(state),
+        }
+    }
+}
+
+impl Pcg {
+    /// Captures the current state, tagged with the algorithm and
+    /// stream format version.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            algorithm: Algorithm::XshRs6432Mcg.id(),
+            version: STREAM_FORMAT_VERSION,
+            state: self.state,
+        }
+    }
+
+    /// The state alone, as 8 little-endian bytes. Unlike a snapshot, the
+    /// bytes do not record the stream format version, so they replay the
+    /// same sequence only under the same version.
+    pub fn to_bytes(&self) -> [u8; 8] {
+        self.state.to_le_bytes()
+    }
+
+    /// Recreates a Pcg instance from the bytes of `to_bytes`, refusing the
+    /// all-zero state, which no Pcg instance can reach. The restored draw
+    /// count starts from zero.
+    pub fn from_bytes(bytes: [u8; 8]) -> Result<Pcg, SnapshotError> {
+        match u64::from_le_bytes(bytes) {
+            0 => Err(SnapshotError::InvalidState),
+            state => Ok(Pcg { state, draws: 0 }),
+        }
+    }
+
+    /// Recreates a Pcg instance from a snapshot, refusing snapshots from
+    /// other algorithms or newer stream formats. Snapshots hold only the
+    /// state, so the restored draw count starts from zero.
+    pub fn restore(snapshot: &Snapshot) -> Result<Pcg, SnapshotError> {
+        if snapshot.algorithm != Algorithm::XshRs6432Mcg.id() {
+            return Err(SnapshotError::IncompatibleAlgorithm {
+                expected: Algorithm::XshRs6432Mcg,
+                found: snapshot.algorithm,
+            });
+        }
+        if snapshot.version > STREAM_FORMAT_VERSION {
+            return Err(SnapshotError::IncompatibleVersion {
+                supported: STREAM_FORMAT_VERSION,
+                found: snapshot.version,
+            });
+        }
+        if snapshot.state == 0 {
+            return Err(SnapshotError::InvalidState);
+        }
+        Ok(Pcg {
+            state: snapshot.state,
+            draws: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::{RngCore, SeedableRng};
+
+    #[test]
+    fn test_round_trip() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let snapshot = Snapshot::from_bytes(pcg.snapshot().to_bytes());
+        assert_eq!(snapshot, pcg.snapshot());
+        assert_eq!(snapshot.algorithm(), Algorithm::XshRs6432Mcg.id());
+        assert_eq!(snapshot.version(), STREAM_FORMAT_VERSION);
+
+        let mut restored = Pcg::restore(&snapshot).unwrap();
+        assert_eq!(restored.next_u64(), pcg.next_u64());
+    }
+
+    #[test]
+    fn test_state_bytes() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut restored = Pcg::from_bytes(pcg.to_bytes()).unwrap();
+        assert_eq!(restored.next_u64(), pcg.next_u64());
+        assert_eq!(restored.draw_count(), 2);
+
+        let pcg = Pcg::seed_from_u64(0x0123456789ABCDEF);
+        assert_eq!(
+            pcg.to_bytes(),
+            [0xEF, 0xCD, 0xAB, 0x89, 0x67, 0x45, 0x23, 0x01]
+        );
+        assert_eq!(
+            Pcg::from_bytes([0; 8]).err(),
+            Some(SnapshotError::InvalidState)
+        );
+    }
+
+    #[test]
+    fn test_refuses_incompatible() {
+        let pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut bytes = pcg.snapshot().to_bytes();
+        bytes[0] = 0xFF;
+        let err = Pcg::restore(&Snapshot::from_bytes(bytes)).err().unwrap();
+        assert_eq!(
+            err,
+            SnapshotError::IncompatibleAlgorithm {
+                expected: Algorithm::XshRs6432Mcg,
+                found: 0xFF,
+            }
+        );
+        assert!(err.to_string().contains("unknown algorithm 255"));
+
+        let mut bytes = pcg.snapshot().to_bytes();
+        bytes[2..4].copy_from_slice(&(STREAM_FORMAT_VERSION + 1).to_le_bytes());
+        let err = Pcg::restore(&Snapshot::from_bytes(bytes)).err().unwrap();
+        assert!(matches!(err, SnapshotError::IncompatibleVersion { .. }));
+
+        let mut bytes = pcg.snapshot().to_bytes();
+        bytes[4..12].copy_from_slice(&[0; 8]);
+        let err = Pcg::restore(&Snapshot::from_bytes(bytes)).err().unwrap();
+        assert_eq!(err, SnapshotError::InvalidState);
+    }
+}
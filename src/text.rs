@@ -0,0 +1,130 @@
+/*! Generators for text that tends to break text handling.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::text::gen_nasty_string;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let s = gen_nasty_string(&mut pcg, 20);
+ *
+ *  // still valid UTF-8, but good luck displaying it
+ *  assert!(std::str::from_utf8(s.as_bytes()).is_ok());
+ *  ```
+ */
+use crate::{bounded_u32, bounded_usize};
+use rand_core::RngCore;
+use std::char;
+
+/// Code points on either side of an encoding boundary: UTF-8 length
+/// changes, the surrogate gap, noncharacters and the last code point
+const BOUNDARIES: &[char] = &[
+    '\u{7F}',
+    '\u{80}',
+    '\u{7FF}',
+    '\u{800}',
+    '\u{D7FF}',
+    '\u{E000}',
+    '\u{FFFD}',
+    '\u{FFFE}',
+    '\u{FFFF}',
+    '\u{10000}',
+    '\u{10FFFF}',
+];
+
+/// Bidirectional overrides, embeddings, isolates and marks
+const BIDI_CONTROLS: &[char] = &[
+    '\u{200E}', '\u{200F}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}', '\u{2066}',
+    '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// Zero-width characters and unusual whitespace and line breaks
+const INVISIBLES: &[char] = &[
+    '\u{0}', '\t', '\r', '\n', '\u{A0}', '\u{AD}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{2028}',
+    '\u{2029}', '\u{3000}', '\u{FEFF}',
+];
+
+/// Multi-code-point graphemes: ZWJ emoji sequences, flags, skin tones,
+/// and Hangul jamo
+const LONG_GRAPHEMES: &[&str] = &[
+    "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}",
+    "\u{1F3F3}\u{FE0F}\u{200D}\u{1F308}",
+    "\u{1F1FA}\u{1F1F8}",
+    "\u{1F44D}\u{1F3FD}",
+    "\u{1100}\u{1161}\u{11A8}",
+    "e\u{301}",
+    "\r\n",
+];
+
+/// Generates a string of up to `max_pieces` fragments, weighted toward
+/// content that breaks naive text handling: combining mark pile-ups,
+/// bidi controls, multi-code-point graphemes, nulls and other invisibles,
+/// and code points at encoding boundaries. The rest is ASCII or any
+/// random code point.
+pub fn gen_nasty_string<R: RngCore + ?Sized>(rng: &mut R, max_pieces: usize) -> String {
+    let mut s = String::new();
+    for _ in 0..bounded_usize(rng, max_pieces + 1) {
+        match bounded_u32(rng, 8) {
+            0 => s.push((b' ' + bounded_u32(rng, 95) as u8) as char),
+            1 => s.push(random_char(rng)),
+            2 => {
+                // a base letter buried under combining marks
+                s.push((b'a' + bounded_u32(rng, 26) as u8) as char);
+                for _ in 0..1 + bounded_u32(rng, 16) {
+                    s.push(char::from_u32(0x300 + bounded_u32(rng, 0x70)).unwrap());
+                }
+            }
+            3 => s.push(choose(rng, BIDI_CONTROLS)),
+            4 => s.push(choose(rng, INVISIBLES)),
+            5 => s.push_str(choose(rng, LONG_GRAPHEMES)),
+            6 => s.push(choose(rng, BOUNDARIES)),
+            _ => s.push_str(&"A".repeat(1 + bounded_usize(rng, 256))),
+        }
+    }
+    s
+}
+
+/// A uniformly random Unicode scalar value
+fn random_char<R: RngCore + ?Sized>(rng: &mut R) -> char {
+    // 0x110000 code points less the 0x800 surrogates
+    let n = bounded_u32(rng, 0x110000 - 0x800);
+    let n = if n >= 0xD800 { n + 0x800 } else { n };
+    char::from_u32(n).unwrap()
+}
+
+fn choose<T: Copy, R: RngCore + ?Sized>(rng: &mut R, items: &[T]) -> T {
+    items[bounded_usize(rng, items.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_nasty_coverage() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let all: String = (0..200).map(|_| gen_nasty_string(&mut pcg, 20)).collect();
+        assert!(all.contains('\u{0}'));
+        assert!(all.chars().any(|c| ('\u{300}'..='\u{36F}').contains(&c)));
+        assert!(all.chars().any(|c| BIDI_CONTROLS.contains(&c)));
+        assert!(all.chars().any(|c| c > '\u{FFFF}'));
+        assert!(all.contains('\u{200D}'));
+    }
+
+    #[test]
+    fn test_nasty_empty() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert!(gen_nasty_string(&mut pcg, 0).is_empty());
+        assert!((0..100).any(|_| gen_nasty_string(&mut pcg, 3).is_empty()));
+    }
+
+    #[test]
+    fn test_nasty_reproducible() {
+        let seed = rand::random::<u64>();
+        let a = gen_nasty_string(&mut Pcg::seed_from_u64(seed), 50);
+        let b = gen_nasty_string(&mut Pcg::seed_from_u64(seed), 50);
+        assert_eq!(a, b);
+    }
+}
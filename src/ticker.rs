@@ -0,0 +1,136 @@
+/*! Deterministic sampling decisions for profilers and loggers.
+ *
+ *  A ticker splits events into periods and samples exactly the configured
+ *  number of events in each period, at random positions within it. The
+ *  long-run frequency is therefore exact, and no period is ever starved
+ *  or flooded, while the positions still vary.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::ticker::SampleTicker;
+ *  let mut ticker = SampleTicker::new(3, 100, 12345);
+ *  let sampled = (0..1000).filter(|_| ticker.should_sample()).count();
+ *
+ *  assert_eq!(sampled, 30);
+ *  ```
+ */
+use crate::{bounded_u32, Pcg};
+use rand_core::SeedableRng;
+
+/// Samples `samples` out of every `period` events
+#[derive(Clone)]
+pub struct SampleTicker {
+    pcg: Pcg,
+    samples: u32,
+    period: u32,
+    /// Events and samples left in the current period
+    ticks_left: u32,
+    samples_left: u32,
+    ticks: u64,
+    sampled: u64,
+}
+
+impl SampleTicker {
+    /// Creates a ticker that samples exactly `samples` of every `period`
+    /// events, where `samples` is at most `period`.
+    pub fn new(samples: u32, period: u32, seed: u64) -> SampleTicker {
+        assert!(period > 0, "period must be positive");
+        assert!(samples <= period, "cannot sample more events than occur");
+        SampleTicker {
+            pcg: Pcg::seed_from_u64(seed),
+            samples,
+            period,
+            ticks_left: period,
+            samples_left: samples,
+            ticks: 0,
+            sampled: 0,
+        }
+    }
+
+    /// Creates a ticker that samples one of every `period` events.
+    pub fn one_in(period: u32, seed: u64) -> SampleTicker {
+        SampleTicker::new(1, period, seed)
+    }
+
+    /// Records an event and says whether to sample it.
+    pub fn should_sample(&mut self) -> bool {
+        // selection sampling: take this event with probability
+        // samples_left / ticks_left, which places exactly `samples`
+        // uniformly among the period's events
+        let sample = bounded_u32(&mut self.pcg, self.ticks_left) < self.samples_left;
+        self.ticks_left -= 1;
+        if sample {
+            self.samples_left -= 1;
+            self.sampled += 1;
+        }
+        if self.ticks_left == 0 {
+            self.ticks_left = self.period;
+            self.samples_left = self.samples;
+        }
+        self.ticks += 1;
+        sample
+    }
+
+    /// The configured frequency, samples / period.
+    pub fn rate(&self) -> f64 {
+        self.samples as f64 / self.period as f64
+    }
+
+    /// The number of events recorded so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The number of events sampled so far.
+    pub fn sampled(&self) -> u64 {
+        self.sampled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_rate() {
+        let mut ticker = SampleTicker::new(7, 64, rand::random::<u64>());
+        for period in 1..100 {
+            let sampled = (0..64).filter(|_| ticker.should_sample()).count();
+            assert_eq!(sampled, 7);
+            assert_eq!(ticker.sampled(), period * 7);
+        }
+        assert_eq!(ticker.ticks(), 99 * 64);
+        assert_eq!(ticker.rate(), 7.0 / 64.0);
+    }
+
+    #[test]
+    fn test_extremes() {
+        let seed = rand::random::<u64>();
+        let mut never = SampleTicker::new(0, 10, seed);
+        assert!((0..100).all(|_| !never.should_sample()));
+        let mut always = SampleTicker::new(10, 10, seed);
+        assert!((0..100).all(|_| always.should_sample()));
+    }
+
+    #[test]
+    fn test_jitter() {
+        let mut ticker = SampleTicker::one_in(10, 0x2545F4914F6CDD1D);
+        let mut positions = [0; 10];
+        for _ in 0..10000 {
+            let position = (0..10).position(|_| ticker.should_sample()).unwrap();
+            positions[position] += 1;
+            for _ in position + 1..10 {
+                assert!(!ticker.should_sample());
+            }
+        }
+        assert!(positions.iter().all(|&c| (800..1200).contains(&c)));
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seed = rand::random::<u64>();
+        let mut a = SampleTicker::new(3, 17, seed);
+        let mut b = SampleTicker::new(3, 17, seed);
+        assert!((0..1000).all(|_| a.should_sample() == b.should_sample()));
+    }
+}
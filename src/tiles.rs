@@ -0,0 +1,376 @@
+/*! Weighted tile choice under neighbour constraints, and a small
+ *  wave-function-collapse solver built on it, for terrain and level
+ *  generation.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::tiles::{Direction, TileSet, Wave};
+ *  # use rand_core::SeedableRng;
+ *  // water, sand, grass: sand may touch anything, water and grass only
+ *  // themselves and sand
+ *  let (water, sand, grass) = (0, 1, 2);
+ *  let mut tiles = TileSet::new(&[3.0, 1.0, 3.0]);
+ *  for &(a, b) in &[(water, water), (water, sand), (sand, sand), (sand, grass), (grass, grass)] {
+ *      tiles.allow_everywhere(a, b);
+ *  }
+ *
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let next_to_water = tiles.pick(&mut pcg, [Some(water), None, None, None]);
+ *  assert_ne!(next_to_water, Some(grass));
+ *
+ *  let map = Wave::new(&tiles, 16, 16).collapse(&mut pcg).unwrap();
+ *  assert_eq!(map.len(), 256);
+ *  ```
+ */
+use crate::{bounded_u32, unit_f64};
+use rand_core::RngCore;
+
+/// A side of a grid cell, with north towards row 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::East,
+    Direction::South,
+    Direction::West,
+];
+
+impl Direction {
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Tiles with weights and rules for which may sit next to which
+#[derive(Clone, Debug)]
+pub struct TileSet {
+    weights: Vec<f64>,
+    /// allowed[d][a * n + b]: whether b may lie in direction d of a
+    allowed: [Vec<bool>; 4],
+}
+
+impl TileSet {
+    /// Creates a set of tiles 0..n with the given positive weights, and
+    /// no adjacencies allowed yet.
+    pub fn new(weights: &[f64]) -> TileSet {
+        assert!(!weights.is_empty(), "a tile set needs tiles");
+        assert!(
+            weights.iter().all(|&w| w > 0.0 && w.is_finite()),
+            "tile weights must be positive"
+        );
+        let n = weights.len();
+        TileSet {
+            weights: weights.to_vec(),
+            allowed: [
+                vec![false; n * n],
+                vec![false; n * n],
+                vec![false; n * n],
+                vec![false; n * n],
+            ],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    /// Allows tile b to lie in direction `dir` of tile a, and so a in the
+    /// opposite direction of b.
+    pub fn allow(&mut self, a: usize, dir: Direction, b: usize) {
+        let n = self.len();
+        self.allowed[dir.index()][a * n + b] = true;
+        self.allowed[dir.opposite().index()][b * n + a] = true;
+    }
+
+    /// Allows tiles a and b to lie next to each other in any direction.
+    pub fn allow_everywhere(&mut self, a: usize, b: usize) {
+        for &dir in &DIRECTIONS {
+            self.allow(a, dir, b);
+        }
+    }
+
+    /// Whether tile b may lie in direction `dir` of tile a.
+    pub fn allows(&self, a: usize, dir: Direction, b: usize) -> bool {
+        self.allowed[dir.index()][a * self.len() + b]
+    }
+
+    /// Picks a tile, with probability proportional to its weight, among
+    /// those compatible with the known neighbours, given as north, east,
+    /// south and west. Returns None if no tile fits.
+    pub fn pick<R: RngCore + ?Sized>(
+        &self,
+        rng: &mut R,
+        neighbours: [Option<usize>; 4],
+    ) -> Option<usize> {
+        let fits = |tile: usize| {
+            DIRECTIONS
+                .iter()
+                .zip(&neighbours)
+                .all(|(&dir, neighbour)| neighbour.is_none_or(|n| self.allows(tile, dir, n)))
+        };
+        self.choose_weighted(rng, (0..self.len()).filter(|&t| fits(t)))
+    }
+
+    fn choose_weighted<R, I>(&self, rng: &mut R, candidates: I) -> Option<usize>
+    where
+        R: RngCore + ?Sized,
+        I: Iterator<Item = usize> + Clone,
+    {
+        let total: f64 = candidates.clone().map(|t| self.weights[t]).sum();
+        let mut target = unit_f64(rng) * total;
+        let mut last = None;
+        for tile in candidates {
+            if target < self.weights[tile] {
+                return Some(tile);
+            }
+            target -= self.weights[tile];
+            last = Some(tile);
+        }
+        // rounding can leave the target just past the last weight
+        last
+    }
+}
+
+/// A grid in which each cell holds the tiles it may still become.
+/// Collapsing repeatedly fixes the most constrained cell to a weighted
+/// choice and propagates the consequences to its neighbours.
+#[derive(Clone, Debug)]
+pub struct Wave<'a> {
+    tiles: &'a TileSet,
+    width: usize,
+    height: usize,
+    /// possible[cell * n + tile]
+    possible: Vec<bool>,
+    /// Set when the rules leave some cell without options
+    contradiction: bool,
+}
+
+impl<'a> Wave<'a> {
+    /// A grid where every cell may be any tile.
+    pub fn new(tiles: &'a TileSet, width: usize, height: usize) -> Wave<'a> {
+        let mut wave = Wave {
+            tiles,
+            width,
+            height,
+            possible: vec![true; width * height * tiles.len()],
+            contradiction: false,
+        };
+        // rule out tiles that can have no neighbour where one is needed
+        wave.contradiction = (0..width * height).any(|cell| wave.propagate(cell).is_err());
+        wave
+    }
+
+    /// The tiles cell (x, y) may still become.
+    pub fn options(&self, x: usize, y: usize) -> impl Iterator<Item = usize> + '_ {
+        self.options_at(y * self.width + x)
+    }
+
+    fn options_at(&self, cell: usize) -> impl Iterator<Item = usize> + Clone + '_ {
+        let n = self.tiles.len();
+        (0..n).filter(move |&t| self.possible[cell * n + t])
+    }
+
+    fn neighbour(&self, cell: usize, dir: Direction) -> Option<usize> {
+        let (x, y) = (cell % self.width, cell / self.width);
+        match dir {
+            Direction::North if y > 0 => Some(cell - self.width),
+            Direction::East if x + 1 < self.width => Some(cell + 1),
+            Direction::South if y + 1 < self.height => Some(cell + self.width),
+            Direction::West if x > 0 => Some(cell - 1),
+            _ => None,
+        }
+    }
+
+    /// Collapses one cell: of the undecided cells with the fewest options,
+    /// picks one at random and fixes it to a weighted choice among its
+    /// options. Returns false if no cell was left undecided. Returns
+    /// Err(()) on a contradiction, where some cell has no options left.
+    #[allow(clippy::result_unit_err)]
+    pub fn step<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> Result<bool, ()> {
+        if self.contradiction {
+            return Err(());
+        }
+        let mut best = None;
+        let mut fewest = usize::MAX;
+        let mut ties = 0;
+        for cell in 0..self.width * self.height {
+            let count = self.options_at(cell).count();
+            if count == 0 {
+                return Err(());
+            }
+            if count == 1 || count > fewest {
+                continue;
+            }
+            if count < fewest {
+                fewest = count;
+                ties = 0;
+            }
+            // reservoir sampling among the tied cells
+            ties += 1;
+            if bounded_u32(rng, ties) == 0 {
+                best = Some(cell);
+            }
+        }
+        let cell = match best {
+            Some(cell) => cell,
+            None => return Ok(false),
+        };
+        let tile = self
+            .tiles
+            .choose_weighted(rng, self.options_at(cell))
+            .unwrap();
+        let n = self.tiles.len();
+        for t in 0..n {
+            self.possible[cell * n + t] = t == tile;
+        }
+        self.propagate(cell)?;
+        Ok(true)
+    }
+
+    /// Removes options from neighbours that no remaining option of a
+    /// changed cell supports, until nothing changes.
+    fn propagate(&mut self, changed: usize) -> Result<(), ()> {
+        let n = self.tiles.len();
+        let mut stack = vec![changed];
+        while let Some(cell) = stack.pop() {
+            for &dir in &DIRECTIONS {
+                let neighbour = match self.neighbour(cell, dir) {
+                    Some(neighbour) => neighbour,
+                    None => continue,
+                };
+                let mut reduced = false;
+                for b in 0..n {
+                    if self.possible[neighbour * n + b]
+                        && !self.options_at(cell).any(|a| self.tiles.allows(a, dir, b))
+                    {
+                        self.possible[neighbour * n + b] = false;
+                        reduced = true;
+                    }
+                }
+                if reduced {
+                    if self.options_at(neighbour).next().is_none() {
+                        self.contradiction = true;
+                        return Err(());
+                    }
+                    stack.push(neighbour);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Collapses every cell, returning the tiles row by row, or None on a
+    /// contradiction. Retrying with the same generator draws afresh.
+    pub fn collapse<R: RngCore + ?Sized>(mut self, rng: &mut R) -> Option<Vec<usize>> {
+        loop {
+            match self.step(rng) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(()) => return None,
+            }
+        }
+        let cells = self.width * self.height;
+        Some(
+            (0..cells)
+                .map(|cell| self.options_at(cell).next().unwrap())
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    /// Water, sand and grass, where water never touches grass
+    fn coast() -> TileSet {
+        let mut tiles = TileSet::new(&[3.0, 1.0, 3.0]);
+        for &(a, b) in &[(0, 0), (0, 1), (1, 1), (1, 2), (2, 2)] {
+            tiles.allow_everywhere(a, b);
+        }
+        tiles
+    }
+
+    #[test]
+    fn test_pick() {
+        let tiles = coast();
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut counts = [0; 3];
+        for _ in 0..10000 {
+            counts[tiles.pick(&mut pcg, [None; 4]).unwrap()] += 1;
+        }
+        assert!((4000..4600).contains(&counts[0]));
+        assert!((1200..1700).contains(&counts[1]));
+        for _ in 0..100 {
+            let tile = tiles.pick(&mut pcg, [Some(0), None, None, None]).unwrap();
+            assert!(tile == 0 || tile == 1);
+        }
+        assert_eq!(
+            tiles.pick(&mut pcg, [Some(0), Some(2), None, None]),
+            Some(1)
+        );
+        assert_eq!(
+            tiles.pick(&mut pcg, [Some(0), None, None, Some(2)]),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_directional_rules() {
+        let mut tiles = TileSet::new(&[1.0, 1.0]);
+        tiles.allow(0, Direction::East, 1);
+        assert!(tiles.allows(0, Direction::East, 1));
+        assert!(tiles.allows(1, Direction::West, 0));
+        assert!(!tiles.allows(1, Direction::East, 0));
+    }
+
+    #[test]
+    fn test_collapse() {
+        let tiles = coast();
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let (w, h) = (12, 9);
+        let map = Wave::new(&tiles, w, h).collapse(&mut pcg).unwrap();
+        for y in 0..h {
+            for x in 0..w {
+                let tile = map[y * w + x];
+                if x + 1 < w {
+                    assert!(tiles.allows(tile, Direction::East, map[y * w + x + 1]));
+                }
+                if y + 1 < h {
+                    assert!(tiles.allows(tile, Direction::South, map[(y + 1) * w + x]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_contradiction() {
+        // a tile that may touch nothing cannot fill a grid of two cells
+        let tiles = TileSet::new(&[1.0]);
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        assert_eq!(Wave::new(&tiles, 2, 1).collapse(&mut pcg), None);
+        assert_eq!(Wave::new(&tiles, 1, 1).collapse(&mut pcg), Some(vec![0]));
+    }
+}
@@ -0,0 +1,207 @@
+/*! Randomized request traffic for load tests.
+ *
+ *  Arrivals follow an on/off pattern: bursts of fast arrivals alternate
+ *  with idle spells of slow ones (a Markov-modulated Poisson process).
+ *  Optionally, a token bucket shapes the result, delaying arrivals that
+ *  would exceed its rate and burst capacity.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::traffic::Traffic;
+ *  # use rand_core::SeedableRng;
+ *  # use std::time::Duration;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let mut traffic = Traffic::new(500.0, 5.0)
+ *      .with_phases(Duration::from_millis(200), Duration::from_secs(2))
+ *      .with_token_bucket(200.0, 20.0);
+ *
+ *  let gaps: Vec<Duration> = (0..100).map(|_| traffic.next_gap(&mut pcg)).collect();
+ *  assert_eq!(gaps.len(), 100);
+ *  ```
+ */
+use crate::distributions::open_unit_f64;
+use rand_core::RngCore;
+use std::time::Duration;
+
+/// Draws an exponential variate with the given rate; a zero rate never fires.
+fn exponential<R: RngCore + ?Sized>(rng: &mut R, rate: f64) -> f64 {
+    if rate > 0.0 {
+        -open_unit_f64(rng).ln() / rate
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// Generates inter-arrival times of requests
+#[derive(Clone, Debug)]
+pub struct Traffic {
+    burst_rate: f64,
+    idle_rate: f64,
+    mean_burst: f64,
+    mean_idle: f64,
+    bucket: Option<(f64, f64)>,
+    in_burst: bool,
+    /// Seconds until the pattern next switches between burst and idle,
+    /// or None before the first arrival has been drawn
+    phase_left: Option<f64>,
+    /// Seconds since the start of the last arrival, before and after shaping
+    now: f64,
+    last_emitted: f64,
+    tokens: f64,
+}
+
+impl Traffic {
+    /// Creates a pattern with the given arrival rates, in requests per
+    /// second, during bursts and idle spells. Bursts and idle spells last
+    /// one second each on average, starting with a burst.
+    pub fn new(burst_rate: f64, idle_rate: f64) -> Traffic {
+        assert!(burst_rate > 0.0, "burst rate must be positive");
+        assert!(idle_rate >= 0.0, "idle rate must not be negative");
+        Traffic {
+            burst_rate,
+            idle_rate,
+            mean_burst: 1.0,
+            mean_idle: 1.0,
+            bucket: None,
+            in_burst: true,
+            phase_left: None,
+            now: 0.0,
+            last_emitted: 0.0,
+            tokens: 0.0,
+        }
+    }
+
+    /// Sets the mean lengths of bursts and idle spells, which are
+    /// exponentially distributed.
+    pub fn with_phases(mut self, mean_burst: Duration, mean_idle: Duration) -> Traffic {
+        assert!(mean_burst > Duration::ZERO && mean_idle > Duration::ZERO);
+        self.mean_burst = mean_burst.as_secs_f64();
+        self.mean_idle = mean_idle.as_secs_f64();
+        self
+    }
+
+    /// Shapes arrivals with a token bucket refilling at `rate` tokens per
+    /// second up to `capacity`, which starts full. Each arrival takes a
+    /// token, waiting for one if the bucket is empty.
+    pub fn with_token_bucket(mut self, rate: f64, capacity: f64) -> Traffic {
+        assert!(
+            rate > 0.0 && capacity >= 1.0,
+            "token bucket cannot pass requests"
+        );
+        self.bucket = Some((rate, capacity));
+        self.tokens = capacity;
+        self
+    }
+
+    /// Whether the pattern is currently in a burst.
+    pub fn in_burst(&self) -> bool {
+        self.in_burst
+    }
+
+    /// Draws the time until the next request.
+    pub fn next_gap<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> Duration {
+        // arrivals held back by the bucket leave later ones queued behind
+        let arrival = self.next_arrival(rng).max(self.last_emitted);
+        let emitted = match self.bucket {
+            None => arrival,
+            Some((rate, capacity)) => {
+                let elapsed = arrival - self.last_emitted;
+                self.tokens = (self.tokens + elapsed * rate).min(capacity);
+                if self.tokens >= 1.0 {
+                    self.tokens -= 1.0;
+                    arrival
+                } else {
+                    let wait = (1.0 - self.tokens) / rate;
+                    self.tokens = 0.0;
+                    arrival + wait
+                }
+            }
+        };
+        let gap = emitted - self.last_emitted;
+        self.last_emitted = emitted;
+        Duration::from_secs_f64(gap)
+    }
+
+    /// Advances the on/off pattern to its next raw arrival.
+    fn next_arrival<R: RngCore + ?Sized>(&mut self, rng: &mut R) -> f64 {
+        let mut phase_left = match self.phase_left {
+            Some(left) => left,
+            None => exponential(rng, 1.0 / self.mean_burst),
+        };
+        loop {
+            let rate = if self.in_burst {
+                self.burst_rate
+            } else {
+                self.idle_rate
+            };
+            let wait = exponential(rng, rate);
+            if wait < phase_left {
+                self.now += wait;
+                self.phase_left = Some(phase_left - wait);
+                return self.now;
+            }
+            // the arrival process is memoryless, so the wait is redrawn
+            // from the switch under the new phase's rate
+            self.now += phase_left;
+            self.in_burst = !self.in_burst;
+            let mean = if self.in_burst {
+                self.mean_burst
+            } else {
+                self.mean_idle
+            };
+            phase_left = exponential(rng, 1.0 / mean);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_average_rate() {
+        let mut pcg = Pcg::seed_from_u64(0x2545F4914F6CDD1D);
+        let mut traffic = Traffic::new(100.0, 10.0)
+            .with_phases(Duration::from_millis(500), Duration::from_millis(1500));
+        let n = 50000;
+        let total: f64 = (0..n)
+            .map(|_| traffic.next_gap(&mut pcg).as_secs_f64())
+            .sum();
+        // bursts take a quarter of the time on average
+        let expected_rate = 0.25 * 100.0 + 0.75 * 10.0;
+        assert!((n as f64 / total - expected_rate).abs() < 0.1 * expected_rate);
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut traffic = Traffic::new(1000.0, 0.0).with_token_bucket(10.0, 5.0);
+        let mut times = vec![];
+        let mut now = 0.0;
+        for _ in 0..1000 {
+            now += traffic.next_gap(&mut pcg).as_secs_f64();
+            times.push(now);
+        }
+        // no window of one second may pass more than rate + capacity requests
+        for (i, &t) in times.iter().enumerate() {
+            let in_window = times[i..].iter().take_while(|&&u| u < t + 1.0).count();
+            assert!(in_window <= 15);
+        }
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let seed = rand::random::<u64>();
+        let gaps = |seed| {
+            let mut pcg = Pcg::seed_from_u64(seed);
+            let mut traffic = Traffic::new(50.0, 1.0).with_token_bucket(20.0, 3.0);
+            (0..100)
+                .map(|_| traffic.next_gap(&mut pcg))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(gaps(seed), gaps(seed));
+    }
+}
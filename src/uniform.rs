@@ -0,0 +1,157 @@
+//! Uniform integers in a range, for every primitive integer type.
+
+use crate::{bounded_u32, bounded_u64, Pcg};
+use rand_core::RngCore;
+use std::ops::{Bound, RangeBounds};
+
+/// Integer types `Pcg::gen_range` can draw
+pub trait UniformInt: Copy + PartialOrd {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// Draws a value uniformly from [low, high], which must not be empty.
+    fn sample_inclusive<R: RngCore + ?Sized>(rng: &mut R, low: Self, high: Self) -> Self;
+
+    fn checked_succ(self) -> Option<Self>;
+
+    fn checked_pred(self) -> Option<Self>;
+}
+
+/// Draws an offset in [0, max_offset], drawing only 32 bits when that
+/// covers it and the full width only for the full range
+fn offset_u64<R: RngCore + ?Sized>(rng: &mut R, max_offset: u64) -> u64 {
+    if max_offset == u64::MAX {
+        rng.next_u64()
+    } else if max_offset < u32::MAX as u64 {
+        bounded_u32(rng, max_offset as u32 + 1) as u64
+    } else {
+        bounded_u64(rng, max_offset + 1)
+    }
+}
+
+/// As offset_u64, by masked rejection for spans beyond 64 bits
+fn offset_u128<R: RngCore + ?Sized>(rng: &mut R, max_offset: u128) -> u128 {
+    if max_offset <= u64::MAX as u128 {
+        return offset_u64(rng, max_offset as u64) as u128;
+    }
+    let mask = u128::MAX >> max_offset.leading_zeros();
+    loop {
+        let x = ((rng.next_u64() as u128) << 64 | rng.next_u64() as u128) & mask;
+        if x <= max_offset {
+            return x;
+        }
+    }
+}
+
+macro_rules! uniform_int {
+    ($t:ty, $u:ty, $offset:ident, $wide:ty) => {
+        impl UniformInt for $t {
+            const MIN: $t = <$t>::MIN;
+            const MAX: $t = <$t>::MAX;
+
+            fn sample_inclusive<R: RngCore + ?Sized>(rng: &mut R, low: $t, high: $t) -> $t {
+                assert!(low <= high, "empty range");
+                let max_offset = (high as $u).wrapping_sub(low as $u) as $wide;
+                low.wrapping_add($offset(rng, max_offset) as $t)
+            }
+
+            fn checked_succ(self) -> Option<$t> {
+                self.checked_add(1)
+            }
+
+            fn checked_pred(self) -> Option<$t> {
+                self.checked_sub(1)
+            }
+        }
+    };
+}
+
+uniform_int!(u8, u8, offset_u64, u64);
+uniform_int!(u16, u16, offset_u64, u64);
+uniform_int!(u32, u32, offset_u64, u64);
+uniform_int!(u64, u64, offset_u64, u64);
+uniform_int!(u128, u128, offset_u128, u128);
+uniform_int!(usize, usize, offset_u64, u64);
+uniform_int!(i8, u8, offset_u64, u64);
+uniform_int!(i16, u16, offset_u64, u64);
+uniform_int!(i32, u32, offset_u64, u64);
+uniform_int!(i64, u64, offset_u64, u64);
+uniform_int!(i128, u128, offset_u128, u128);
+uniform_int!(isize, usize, offset_u64, u64);
+
+/// Draws a value uniformly from any range of a primitive integer type.
+/// Panics if the range is empty.
+pub(crate) fn gen_range<T, R, B>(rng: &mut R, range: B) -> T
+where
+    T: UniformInt,
+    R: RngCore + ?Sized,
+    B: RangeBounds<T>,
+{
+    let low = match range.start_bound() {
+        Bound::Included(&x) => x,
+        Bound::Excluded(&x) => x.checked_succ().expect("empty range"),
+        Bound::Unbounded => T::MIN,
+    };
+    let high = match range.end_bound() {
+        Bound::Included(&x) => x,
+        Bound::Excluded(&x) => x.checked_pred().expect("empty range"),
+        Bound::Unbounded => T::MAX,
+    };
+    T::sample_inclusive(rng, low, high)
+}
+
+impl Pcg {
+    /// Generate an integer of any primitive type uniformly distributed in
+    /// `range`, which may be inclusive, signed, or span the whole type.
+    /// Panics if the range is empty.
+    pub fn gen_range<T: UniformInt, B: RangeBounds<T>>(&mut self, range: B) -> T {
+        gen_range(self, range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_gen_range() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let mut seen = [false; 256];
+        for _ in 0..10000 {
+            let x: i16 = pcg.gen_range(-100i16..100);
+            assert!((-100..100).contains(&x));
+            assert!(pcg.gen_range(3usize..=4) >= 3);
+            assert_eq!(pcg.gen_range(i64::MIN..=i64::MIN), i64::MIN);
+            seen[(pcg.gen_range::<i8, _>(..) as i16 + 128) as usize] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+        let _: u64 = pcg.gen_range(..);
+        assert!(pcg.gen_range(u128::MAX - 3..) >= u128::MAX - 3);
+    }
+
+    #[test]
+    fn test_wide_ranges() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        // a span of 2^100 + 1 values, just past a power of two
+        let high = 1i128 << 100;
+        let mut upper = 0;
+        for _ in 0..1000 {
+            let x = pcg.gen_range(0..=high);
+            assert!(x <= high);
+            if x > high / 2 {
+                upper += 1;
+            }
+        }
+        assert!((400..600).contains(&upper));
+        let full: Vec<i128> = (0..4).map(|_| pcg.gen_range(..)).collect();
+        assert!(full.iter().any(|&x| x.unsigned_abs() > u64::MAX as u128));
+    }
+
+    #[test]
+    #[should_panic(expected = "empty range")]
+    fn test_empty_range() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        pcg.gen_range(5u8..5);
+    }
+}
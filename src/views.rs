@@ -0,0 +1,159 @@
+/*! Cheap derived views of one generator, for consumers that each need
+ *  only a little randomness and can share a stream.
+ *
+ *  Views borrow the generator and hold no state of their own, so every
+ *  draw through a view advances the underlying generator. That makes
+ *  them deterministic only as a whole: the values one consumer sees
+ *  depend on how many draws the others made before it, and a view is no
+ *  more independent of its siblings than consecutive outputs of the
+ *  generator are of each other.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *
+ *  let jitter: Vec<u16> = pcg.bits_high16().take(4).collect();
+ *  let coarse = pcg.every_nth(3).next_u32();
+ *  let folded = pcg.xor_fold().next_u32();
+ *
+ *  assert_eq!(pcg.draw_count(), 4 + 3 + 2);
+ *  ```
+ */
+use crate::Pcg;
+use rand_core::{impls, Error, RngCore};
+
+/// The high 16 bits of each output, one step per item
+pub struct BitsHigh16<'a> {
+    pcg: &'a mut Pcg,
+}
+
+impl Iterator for BitsHigh16<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        Some((self.pcg.next_u32() >> 16) as u16)
+    }
+}
+
+/// Every nth output, skipping the n - 1 before it. This is the output of
+/// a generator whose multiplier is the nth power of Pcg's, whose quality
+/// has not been tested.
+pub struct EveryNth<'a> {
+    pcg: &'a mut Pcg,
+    skip: i64,
+}
+
+impl RngCore for EveryNth<'_> {
+    fn next_u32(&mut self) -> u32 {
+        self.pcg.skip(self.skip);
+        self.pcg.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Outputs that fold two consecutive outputs together with xor, two
+/// steps per u32.
+pub struct XorFold<'a> {
+    pcg: &'a mut Pcg,
+}
+
+impl RngCore for XorFold<'_> {
+    fn next_u32(&mut self) -> u32 {
+        let x = self.pcg.next_u64();
+        (x >> 32) as u32 ^ x as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl Pcg {
+    /// A view yielding the high 16 bits of each output.
+    pub fn bits_high16(&mut self) -> BitsHigh16<'_> {
+        BitsHigh16 { pcg: self }
+    }
+
+    /// A view yielding every nth output, for n of at least 1.
+    pub fn every_nth(&mut self, n: u32) -> EveryNth<'_> {
+        assert!(n >= 1, "n must be at least 1");
+        EveryNth {
+            pcg: self,
+            skip: n as i64 - 1,
+        }
+    }
+
+    /// A view yielding the xor of pairs of outputs.
+    pub fn xor_fold(&mut self) -> XorFold<'_> {
+        XorFold { pcg: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn test_bits_high16() {
+        let seed = rand::random::<u64>();
+        let mut reference = Pcg::seed_from_u64(seed);
+        let mut pcg = Pcg::seed_from_u64(seed);
+        for x in pcg.bits_high16().take(100) {
+            assert_eq!(x as u32, reference.next_u32() >> 16);
+        }
+        assert_eq!(pcg.draw_count(), 100);
+    }
+
+    #[test]
+    fn test_every_nth() {
+        let seed = rand::random::<u64>();
+        let outputs: Vec<u32> = {
+            let mut pcg = Pcg::seed_from_u64(seed);
+            (0..100).map(|_| pcg.next_u32()).collect()
+        };
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut view = pcg.every_nth(5);
+        for i in 0..20 {
+            assert_eq!(view.next_u32(), outputs[5 * i + 4]);
+        }
+        let mut pcg = Pcg::seed_from_u64(seed);
+        assert_eq!(pcg.every_nth(1).next_u32(), outputs[0]);
+    }
+
+    #[test]
+    fn test_xor_fold() {
+        let seed = rand::random::<u64>();
+        let mut reference = Pcg::seed_from_u64(seed);
+        let mut pcg = Pcg::seed_from_u64(seed);
+        let mut view = pcg.xor_fold();
+        for _ in 0..50 {
+            let (a, b) = (reference.next_u32(), reference.next_u32());
+            assert_eq!(view.next_u32(), a ^ b);
+        }
+        assert_eq!(pcg.draw_count(), 100);
+    }
+}
@@ -0,0 +1,178 @@
+/*! Random walks on the integer grid, for carving corridors and for
+ *  testing pathfinding against random but reproducible layouts.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::Pcg;
+ *  # use pcg::walk::grid_walk;
+ *  # use rand_core::SeedableRng;
+ *  let mut pcg = Pcg::seed_from_u64(12345);
+ *  let corridor: Vec<(i64, i64)> = grid_walk(&mut pcg, (0, 0), 50, true)
+ *      .with_bounds((-10, -10), (10, 10))
+ *      .collect();
+ *
+ *  assert_eq!(corridor[0], (0, 0));
+ *  for step in corridor.windows(2) {
+ *      let (a, b) = (step[0], step[1]);
+ *      assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1);
+ *  }
+ *  ```
+ */
+use crate::bounded_usize;
+use rand_core::RngCore;
+use std::collections::HashSet;
+
+const MOVES: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Starts a walk at `start`, taking up to `steps` unit steps north, east,
+/// south or west, each chosen uniformly among those allowed. With
+/// `avoid_repeats`, the walk never revisits a position, and ends early if
+/// it traps itself. The iterator yields the start, then each position
+/// reached.
+pub fn grid_walk<R: RngCore + ?Sized>(
+    rng: &mut R,
+    start: (i64, i64),
+    steps: usize,
+    avoid_repeats: bool,
+) -> GridWalk<'_, R> {
+    GridWalk {
+        rng,
+        position: start,
+        steps_left: steps,
+        visited: if avoid_repeats {
+            Some(HashSet::new())
+        } else {
+            None
+        },
+        bounds: None,
+        started: false,
+    }
+}
+
+/// A random walk, yielding positions
+pub struct GridWalk<'a, R: ?Sized> {
+    rng: &'a mut R,
+    position: (i64, i64),
+    steps_left: usize,
+    visited: Option<HashSet<(i64, i64)>>,
+    bounds: Option<((i64, i64), (i64, i64))>,
+    started: bool,
+}
+
+impl<'a, R: RngCore + ?Sized> GridWalk<'a, R> {
+    /// Confines the walk to the rectangle from `min` to `max`, inclusive,
+    /// which must contain the start.
+    pub fn with_bounds(mut self, min: (i64, i64), max: (i64, i64)) -> GridWalk<'a, R> {
+        let (x, y) = self.position;
+        assert!(
+            min.0 <= x && x <= max.0 && min.1 <= y && y <= max.1,
+            "start lies outside the bounds"
+        );
+        self.bounds = Some((min, max));
+        self
+    }
+
+    fn allowed(&self, (x, y): (i64, i64)) -> bool {
+        let in_bounds = match self.bounds {
+            Some((min, max)) => min.0 <= x && x <= max.0 && min.1 <= y && y <= max.1,
+            None => true,
+        };
+        let fresh = match &self.visited {
+            Some(visited) => !visited.contains(&(x, y)),
+            None => true,
+        };
+        in_bounds && fresh
+    }
+}
+
+impl<R: RngCore + ?Sized> Iterator for GridWalk<'_, R> {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<(i64, i64)> {
+        if !self.started {
+            self.started = true;
+            if let Some(visited) = &mut self.visited {
+                visited.insert(self.position);
+            }
+            return Some(self.position);
+        }
+        if self.steps_left == 0 {
+            return None;
+        }
+        let (x, y) = self.position;
+        let mut options = [(0, 0); 4];
+        let mut count = 0;
+        for &(dx, dy) in &MOVES {
+            let next = (x + dx, y + dy);
+            if self.allowed(next) {
+                options[count] = next;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            self.steps_left = 0;
+            return None;
+        }
+        self.position = options[bounded_usize(self.rng, count)];
+        self.steps_left -= 1;
+        if let Some(visited) = &mut self.visited {
+            visited.insert(self.position);
+        }
+        Some(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Pcg;
+    use rand_core::SeedableRng;
+
+    fn assert_unit_steps(path: &[(i64, i64)]) {
+        for step in path.windows(2) {
+            let (a, b) = (step[0], step[1]);
+            assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1);
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let path: Vec<_> = grid_walk(&mut pcg, (3, -2), 1000, false).collect();
+        assert_eq!(path.len(), 1001);
+        assert_eq!(path[0], (3, -2));
+        assert_unit_steps(&path);
+        // a free walk revisits positions sooner or later
+        let distinct: HashSet<_> = path.iter().collect();
+        assert!(distinct.len() < path.len());
+    }
+
+    #[test]
+    fn test_self_avoiding() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        for _ in 0..50 {
+            let path: Vec<_> = grid_walk(&mut pcg, (0, 0), 200, true).collect();
+            assert!(path.len() <= 201);
+            assert_unit_steps(&path);
+            let distinct: HashSet<_> = path.iter().collect();
+            assert_eq!(distinct.len(), path.len());
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut pcg = Pcg::seed_from_u64(rand::random::<u64>());
+        let path: Vec<_> = grid_walk(&mut pcg, (0, 0), 500, false)
+            .with_bounds((0, 0), (2, 1))
+            .collect();
+        assert_eq!(path.len(), 501);
+        assert!(path
+            .iter()
+            .all(|&(x, y)| (0..=2).contains(&x) && (0..=1).contains(&y)));
+        // a self-avoiding walk in a 1x3 strip from an end fills it
+        let path: Vec<_> = grid_walk(&mut pcg, (0, 0), 10, true)
+            .with_bounds((0, 0), (0, 2))
+            .collect();
+        assert_eq!(path, vec![(0, 0), (0, 1), (0, 2)]);
+    }
+}
@@ -0,0 +1,229 @@
+/*! YCSB-style operation streams for storage engine benchmarks.
+ *
+ *  A workload starts from a table of `record_count` records with keys
+ *  0 to record_count - 1, and mixes reads, updates and inserts, each
+ *  insert adding the next key. The operations depend only on the spec and
+ *  the seed, so every machine benchmarks the same sequence.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::workload::{KeyChoice, Operation, WorkloadSpec};
+ *  // YCSB workload D: mostly reads of recently inserted records
+ *  let spec = WorkloadSpec::new(1000)
+ *      .with_mix(0.95, 0.0, 0.05)
+ *      .with_key_choice(KeyChoice::Latest(0.99));
+ *
+ *  for op in spec.operations(12345).take(100) {
+ *      match op {
+ *          Operation::Read(key) => assert!(key < 1100),
+ *          Operation::Update(_) => unreachable!(),
+ *          Operation::Insert(key) => assert!(key >= 1000),
+ *      }
+ *  }
+ *  ```
+ */
+use crate::distributions::{Distribution, Zipf};
+use crate::{bounded_u64, unit_f64, Pcg};
+use rand_core::SeedableRng;
+
+/// An operation on a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Read(u64),
+    Update(u64),
+    /// Adds a new record, always the next unused key
+    Insert(u64),
+}
+
+/// How reads and updates pick among the existing keys
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyChoice {
+    /// Every key equally likely
+    Uniform,
+    /// Key k with probability proportional to (k + 1)^-exponent, so the
+    /// oldest keys are the most popular
+    Zipfian(f64),
+    /// Like Zipfian, but ranked from the newest key, so that recently
+    /// inserted records are the most popular
+    Latest(f64),
+}
+
+/// The shape of a workload: table size, operation mix and key choice
+#[derive(Clone, Debug)]
+pub struct WorkloadSpec {
+    record_count: u64,
+    /// Read, update and insert proportions, summing to 1
+    mix: [f64; 3],
+    key_choice: KeyChoice,
+}
+
+impl WorkloadSpec {
+    /// Half reads and half updates, with Zipfian keys of exponent 0.99,
+    /// like YCSB workload A. The table must not be empty.
+    pub fn new(record_count: u64) -> WorkloadSpec {
+        assert!(record_count > 0, "the table needs at least one record");
+        WorkloadSpec {
+            record_count,
+            mix: [0.5, 0.5, 0.0],
+            key_choice: KeyChoice::Zipfian(0.99),
+        }
+    }
+
+    /// Sets the relative proportions of reads, updates and inserts, which
+    /// must be non-negative and not all zero.
+    pub fn with_mix(mut self, read: f64, update: f64, insert: f64) -> WorkloadSpec {
+        let total = read + update + insert;
+        assert!(
+            read >= 0.0 && update >= 0.0 && insert >= 0.0 && total > 0.0 && total.is_finite(),
+            "operation proportions must be non-negative and not all zero"
+        );
+        self.mix = [read / total, update / total, insert / total];
+        self
+    }
+
+    /// Sets how reads and updates choose keys. Exponents must be positive.
+    pub fn with_key_choice(mut self, key_choice: KeyChoice) -> WorkloadSpec {
+        if let KeyChoice::Zipfian(exponent) | KeyChoice::Latest(exponent) = key_choice {
+            assert!(exponent > 0.0, "Zipfian exponents must be positive");
+        }
+        self.key_choice = key_choice;
+        self
+    }
+
+    /// The endless stream of operations for `seed`.
+    pub fn operations(&self, seed: u64) -> Workload {
+        Workload {
+            spec: self.clone(),
+            pcg: Pcg::seed_from_u64(seed),
+            key_count: self.record_count,
+            zipf: None,
+        }
+    }
+}
+
+/// An iterator over the operations of a workload
+#[derive(Clone)]
+pub struct Workload {
+    spec: WorkloadSpec,
+    pcg: Pcg,
+    /// Keys inserted so far, including the initial records
+    key_count: u64,
+    /// Ranks over the current keys, rebuilt after inserts
+    zipf: Option<Zipf>,
+}
+
+impl Workload {
+    /// The number of keys that exist, counting inserts so far.
+    pub fn key_count(&self) -> u64 {
+        self.key_count
+    }
+
+    fn existing_key(&mut self) -> u64 {
+        let exponent = match self.spec.key_choice {
+            KeyChoice::Uniform => return bounded_u64(&mut self.pcg, self.key_count),
+            KeyChoice::Zipfian(exponent) | KeyChoice::Latest(exponent) => exponent,
+        };
+        let n = self.key_count;
+        let zipf = self
+            .zipf
+            .get_or_insert_with(|| Zipf::new(n, exponent).unwrap());
+        let rank = zipf.sample(&mut self.pcg) - 1;
+        match self.spec.key_choice {
+            KeyChoice::Latest(_) => n - 1 - rank,
+            _ => rank,
+        }
+    }
+}
+
+impl Iterator for Workload {
+    type Item = Operation;
+
+    fn next(&mut self) -> Option<Operation> {
+        let [read, update, _] = self.spec.mix;
+        let u = unit_f64(&mut self.pcg);
+        Some(if u < read {
+            Operation::Read(self.existing_key())
+        } else if u < read + update || self.spec.mix[2] == 0.0 {
+            Operation::Update(self.existing_key())
+        } else {
+            let key = self.key_count;
+            self.key_count += 1;
+            self.zipf = None;
+            Operation::Insert(key)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(ops: &[Operation]) -> [usize; 3] {
+        let mut counts = [0; 3];
+        for op in ops {
+            match op {
+                Operation::Read(_) => counts[0] += 1,
+                Operation::Update(_) => counts[1] += 1,
+                Operation::Insert(_) => counts[2] += 1,
+            }
+        }
+        counts
+    }
+
+    #[test]
+    fn test_mix() {
+        let spec = WorkloadSpec::new(100)
+            .with_mix(6.0, 3.0, 1.0)
+            .with_key_choice(KeyChoice::Uniform);
+        let ops: Vec<Operation> = spec.operations(rand::random::<u64>()).take(10000).collect();
+        let [reads, updates, inserts] = counts(&ops);
+        assert!((5700..6300).contains(&reads));
+        assert!((2750..3250).contains(&updates));
+        assert!((850..1150).contains(&inserts));
+
+        // inserts take consecutive new keys, and reads only existing ones
+        let mut next = 100;
+        for op in ops {
+            match op {
+                Operation::Insert(key) => {
+                    assert_eq!(key, next);
+                    next += 1;
+                }
+                Operation::Read(key) | Operation::Update(key) => assert!(key < next),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let spec = WorkloadSpec::new(1000).with_mix(0.5, 0.3, 0.2);
+        let seed = rand::random::<u64>();
+        let a: Vec<Operation> = spec.operations(seed).take(1000).collect();
+        let b: Vec<Operation> = spec.operations(seed).take(1000).collect();
+        assert_eq!(a, b);
+        let mut workload = spec.operations(seed);
+        workload.nth(999);
+        assert_eq!(workload.key_count(), 1000 + counts(&a)[2] as u64);
+    }
+
+    #[test]
+    fn test_key_choice() {
+        let seed = rand::random::<u64>();
+        let zipfian = WorkloadSpec::new(1000).with_mix(1.0, 0.0, 0.0);
+        let low = zipfian
+            .operations(seed)
+            .take(10000)
+            .filter(|&op| op == Operation::Read(0))
+            .count();
+        // key 0 has probability 1 / H(1000, 0.99), about 0.13
+        assert!((1100..1600).contains(&low));
+
+        let latest = zipfian.with_key_choice(KeyChoice::Latest(0.99));
+        let newest = latest
+            .operations(seed)
+            .take(10000)
+            .filter(|&op| op == Operation::Read(999))
+            .count();
+        assert!((1100..1600).contains(&newest));
+    }
+}
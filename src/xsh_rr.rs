@@ -0,0 +1,160 @@
+/*! PCG-XSH-RR 64/32 (MCG), the rotating counterpart of `Pcg`.
+ *
+ *  The state advances like `Pcg`'s, by multiplication alone, but the
+ *  output permutation rotates rather than shifts. The rotation mixes the
+ *  high state bits into every output bit, so the low bits of each output
+ *  are as good as the high ones. It is a drop-in replacement for `Pcg`,
+ *  with the same seed type; with the same seed, outputs match the
+ *  reference library's `pcg_mcg_64_xsh_rr_32`.
+ *
+ *  # Example use
+ *  ```
+ *  # use pcg::PcgXshRr;
+ *  # use rand_core::{RngCore, SeedableRng};
+ *  let mut pcg = PcgXshRr::seed_from_u64(12345);
+ *  let x = pcg.next_u32();
+ *
+ *  pcg.skip(-1);
+ *  assert_eq!(pcg.next_u32(), x);
+ *  ```
+ */
+use crate::{arr_to_u64, PcgSeed};
+use rand_core::{impls, Error, RngCore, SeedableRng};
+use std::num::Wrapping;
+
+/// The reference library's 64-bit multiplier
+const MULTIPLIER: u64 = 6364136223846793005;
+/// the inverse of MULTIPLIER; (MULTIPLIER*INVERSE)%(2^64) = 1
+const INVERSE: u64 = 0xC097EF87329E28A5;
+
+#[derive(Clone, Debug)]
+pub struct PcgXshRr {
+    state: u64,
+}
+
+impl PcgXshRr {
+    /// Advances the state by n steps, as if calling next_u32() n times,
+    /// or rewinds it for negative n, in O(log n) time.
+    pub fn skip(&mut self, n: i64) {
+        let base = if n >= 0 { MULTIPLIER } else { INVERSE };
+        let mut exponent = n.unsigned_abs();
+        let mut power = Wrapping(base);
+        let mut factor = Wrapping(1u64);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                factor *= power;
+            }
+            power *= power;
+            exponent >>= 1;
+        }
+        self.state = (Wrapping(self.state) * factor).0;
+    }
+}
+
+impl RngCore for PcgXshRr {
+    /// Generate a random u32, advancing the state one step.
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Generate a random u64, advancing the state two steps.
+    fn next_u64(&mut self) -> u64 {
+        ((self.next_u32() as u64) << 32) ^ (self.next_u32() as u64)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for PcgXshRr {
+    type Seed = PcgSeed;
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::seed_from_u64(arr_to_u64(seed))
+    }
+
+    /// Seeds the generator as the reference library does, by setting the
+    /// low bit; an odd state gives the full period of 2^62.
+    fn seed_from_u64(seed: u64) -> Self {
+        PcgXshRr { state: seed | 1 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_outputs() {
+        // from the reference library's pcg_mcg_64_xsh_rr_32, seeded with 42
+        let mut pcg = PcgXshRr::seed_from_u64(42);
+        let expected = [
+            0x00000000, 0x21b756ee, 0x135e80e8, 0xf6025706, 0xd2fc74a3, 0x157c82ab,
+        ];
+        for &x in &expected {
+            assert_eq!(pcg.next_u32(), x);
+        }
+    }
+
+    #[test]
+    fn test_next_u32() {
+        let seed = rand::random::<u64>();
+        let state = seed | 1;
+        let xorshifted = (((state >> 18) ^ state) >> 27) as u32;
+        let expected = xorshifted.rotate_right((state >> 59) as u32);
+
+        let mut pcg = PcgXshRr::seed_from_u64(seed);
+        assert_eq!(pcg.next_u32(), expected);
+        assert_eq!(pcg.state, state.wrapping_mul(MULTIPLIER));
+    }
+
+    #[test]
+    fn test_inverse() {
+        assert_eq!(MULTIPLIER.wrapping_mul(INVERSE), 1);
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut pcg = PcgXshRr::seed_from_u64(rand::random::<u64>());
+        let start = pcg.clone();
+        let steps = rand::random::<u16>();
+        let mut stepped = pcg.clone();
+        for _ in 0..steps {
+            stepped.next_u32();
+        }
+        pcg.skip(steps as i64);
+        assert_eq!(pcg.state, stepped.state);
+        pcg.skip(-(steps as i64));
+        assert_eq!(pcg.state, start.state);
+    }
+
+    #[test]
+    fn test_low_bits() {
+        // the lowest output bit should be an even coin, pair by pair
+        let mut pcg = PcgXshRr::seed_from_u64(rand::random::<u64>());
+        let mut pairs = [0; 4];
+        let mut previous = pcg.next_u32() & 1;
+        for _ in 0..40000 {
+            let bit = pcg.next_u32() & 1;
+            pairs[(previous << 1 | bit) as usize] += 1;
+            previous = bit;
+        }
+        assert!(pairs.iter().all(|&n| (9500..10500).contains(&n)));
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let arr = [0xef, 0xcd, 0xab, 0x89, 0x67, 0x45, 0x23, 0x01];
+        let pcg = PcgXshRr::from_seed(PcgSeed(arr));
+        assert_eq!(pcg.state, 0x0123456789abcdef);
+    }
+}